@@ -1,7 +1,7 @@
-use crate::bevy_mesh::BevyMeshBuilder;
 use crate::models;
 use bevy::asset::RenderAssetUsages;
 use bevy::render::mesh::Mesh as BevyMesh;
+use transvoxel::bevy_support::mesh::BevyMeshBuilder;
 use transvoxel::shrink_if_needed;
 use transvoxel::transition_sides::*;
 use transvoxel::{
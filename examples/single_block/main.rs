@@ -13,9 +13,6 @@ use transvoxel::voxel_source::*;
 mod models;
 use models::Model;
 
-#[path = "../shared/bevy_mesh.rs"]
-mod bevy_mesh;
-
 #[path = "../shared/shapes.rs"]
 mod shapes;
 use shapes::create_arrow;
@@ -0,0 +1,193 @@
+/*!
+Per-vertex ambient occlusion, baked from the density field after extraction
+
+For each vertex, rays are cast in a fixed set of directions over the hemisphere around the vertex normal.
+Each ray is sampled at regular steps up to a maximum distance, and is blocked if any sample is inside the volume.
+The result is the cosine-weighted fraction of unblocked rays: 1 for a fully open vertex, down to 0 for a fully occluded one.
+
+The field is sampled at world coordinates, possibly outside of the block the mesh was extracted from,
+and the ray directions only depend on the vertex normal: vertices shared by adjacent blocks get the same value,
+and the result is deterministic.
+
+```rust
+use transvoxel::ambient_occlusion::{AmbientOcclusion, AMBIENT_OCCLUSION_ATTRIBUTE};
+use transvoxel::generic_mesh::GenericMeshBuilder;
+use transvoxel::prelude::*;
+
+// Ground at y=2, with a pillar
+let mut field = |x: f32, y: f32, z: f32| {
+    let ground = 2.0 - y;
+    let pillar = 1.0 - ((x - 5.0) * (x - 5.0) + (z - 5.0) * (z - 5.0)).sqrt();
+    ground.max(pillar)
+};
+let block = Block::from([0.0, 0.0, 0.0], 10.0, 10);
+let mut mesh = extract_from_field(&mut field, &block, 0.0, transition_sides::no_side(), GenericMeshBuilder::new()).build();
+
+AmbientOcclusion::new(3.0).apply(&mut field, 0.0, &mut mesh);
+let occlusion = &mesh.attributes.iter().find(|a| a.name == AMBIENT_OCCLUSION_ATTRIBUTE).unwrap().values;
+assert!(occlusion.iter().any(|ao| *ao < 1.0));
+```
+*/
+
+use num::Float;
+
+use crate::generic_mesh::{averaged_normals, Mesh, VertexAttribute};
+use crate::traits::{Coordinate, Density, VoxelData};
+use crate::voxel_source::DataField;
+
+/// Name of the attribute added by [AmbientOcclusion::apply]
+pub const AMBIENT_OCCLUSION_ATTRIBUTE: &str = "ambient_occlusion";
+
+/// Ambient occlusion settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AmbientOcclusion<F> {
+    distance: F,
+    rays: usize,
+    steps: usize,
+}
+
+impl<F: Coordinate> AmbientOcclusion<F> {
+    /// Settings casting 16 rays per vertex, up to `distance`, with 4 samples on each ray
+    pub fn new(distance: F) -> Self {
+        Self {
+            distance,
+            rays: 16,
+            steps: 4,
+        }
+    }
+
+    /// Number of rays cast from each vertex
+    pub fn with_rays(mut self, rays: usize) -> Self {
+        self.rays = rays;
+        self
+    }
+
+    /// Number of samples along each ray
+    pub fn with_steps(mut self, steps: usize) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    /**
+    Ambient occlusion of each vertex of the mesh, sampling `field` (which should be the one the mesh was extracted from, with the same threshold).
+    If the mesh has no normals, vertex normals are computed by averaging the normals of the triangles around each vertex
+    (which does not give matching results across blocks)
+    */
+    pub fn compute<V, R>(
+        &self,
+        field: &mut impl DataField<V, F>,
+        threshold: V::Density,
+        mesh: &Mesh<F, R>,
+    ) -> Vec<F>
+    where
+        V: VoxelData,
+    {
+        let averaged;
+        let normals = if mesh.normals.is_empty() {
+            averaged = averaged_normals(mesh);
+            &averaged
+        } else {
+            &mesh.normals
+        };
+        let directions = hemisphere_directions::<F>(self.rays);
+        mesh.positions
+            .chunks_exact(3)
+            .zip(normals.chunks_exact(3))
+            .map(|(position, normal)| {
+                self.vertex_occlusion(
+                    field,
+                    &threshold,
+                    &directions,
+                    [position[0], position[1], position[2]],
+                    [normal[0], normal[1], normal[2]],
+                )
+            })
+            .collect()
+    }
+
+    /// Compute the ambient occlusion of each vertex, and add it to the mesh attributes, as [AMBIENT_OCCLUSION_ATTRIBUTE]
+    pub fn apply<V, R>(
+        &self,
+        field: &mut impl DataField<V, F>,
+        threshold: V::Density,
+        mesh: &mut Mesh<F, R>,
+    ) where
+        V: VoxelData,
+    {
+        let values = self.compute(field, threshold, mesh);
+        mesh.attributes.push(VertexAttribute {
+            name: AMBIENT_OCCLUSION_ATTRIBUTE.to_string(),
+            values,
+        });
+    }
+
+    fn vertex_occlusion<V: VoxelData>(
+        &self,
+        field: &mut impl DataField<V, F>,
+        threshold: &V::Density,
+        directions: &[[F; 3]],
+        position: [F; 3],
+        normal: [F; 3],
+    ) -> F {
+        if normal == [F::zero(); 3] {
+            return F::one();
+        }
+        let (tangent, bitangent) = orthonormal_basis(normal);
+        let step = self.distance / F::from(self.steps).unwrap();
+        let mut open = F::zero();
+        let mut total = F::zero();
+        for local in directions {
+            let direction: [F; 3] = std::array::from_fn(|c| {
+                tangent[c] * local[0] + bitangent[c] * local[1] + normal[c] * local[2]
+            });
+            let blocked = (1..=self.steps).any(|s| {
+                let t = step * F::from(s).unwrap();
+                field
+                    .get_data(
+                        position[0] + direction[0] * t,
+                        position[1] + direction[1] * t,
+                        position[2] + direction[2] * t,
+                    )
+                    .density()
+                    .inside(threshold)
+            });
+            // Cosine weighting: rays close to the normal matter more
+            total = total + local[2];
+            if !blocked {
+                open = open + local[2];
+            }
+        }
+        if total > F::zero() {
+            open / total
+        } else {
+            F::one()
+        }
+    }
+}
+
+// Directions evenly spread over the hemisphere around +z (Fibonacci spiral)
+fn hemisphere_directions<F: Float>(count: usize) -> Vec<[F; 3]> {
+    let golden_angle = std::f64::consts::PI * (3.0 - 5.0f64.sqrt());
+    (0..count)
+        .map(|i| {
+            let z = 1.0 - (i as f64 + 0.5) / count as f64;
+            let r = (1.0 - z * z).sqrt();
+            let phi = golden_angle * i as f64;
+            [r * phi.cos(), r * phi.sin(), z].map(|c| F::from(c).unwrap())
+        })
+        .collect()
+}
+
+// Two unit vectors orthogonal to the (unit) normal and to each other, only depending on the normal
+// (Duff et al., "Building an Orthonormal Basis, Revisited")
+fn orthonormal_basis<F: Float>(normal: [F; 3]) -> ([F; 3], [F; 3]) {
+    let [x, y, z] = normal;
+    let one = F::one();
+    let sign = one.copysign(z);
+    let a = -one / (sign + z);
+    let b = x * y * a;
+    (
+        [one + sign * x * x * a, sign * b, -sign * x],
+        [b, sign + y * y * a, -y],
+    )
+}
@@ -0,0 +1,246 @@
+/*!
+A [VoxelSource] over pre-sampled voxel data stored in flat arrays
+
+The regular samples of a block with `n` subdivisions are stored in a `(n + 3)³` array: the `(n + 1)³` voxels of the block,
+plus a one voxel apron around it (needed for gradients). They are ordered x-major: sample for voxel `x, y, z` (each from -1 to `n + 1`)
+is at `(x + 1) * (n + 3)² + (y + 1) * (n + 3) + (z + 1)`.
+
+For transition sides, the algorithm also needs double-resolution samples on the block faces.
+These can be provided separately for each side: see [transition_samples_ranges].
+When they are missing for a side, the source falls back to the coinciding regular sample, or the one just below in each
+direction. This is only an approximation, and the transition cells will not add any detail.
+
+```
+# use transvoxel::array_source::*;
+# use transvoxel::prelude::*;
+let block = Block::from([0.0, 0.0, 0.0], 10.0, 4);
+let n = block.subdivisions as isize;
+let mut data = Vec::with_capacity(regular_samples_count(block.subdivisions));
+for x in -1..=n + 1 {
+    for y in -1..=n + 1 {
+        for z in -1..=n + 1 {
+            data.push(if y < 2 { 1f32 } else { -1f32 });
+        }
+    }
+}
+let source = ArrayVoxelSource::new(&block, data).unwrap();
+let mesh = extract(source, &block, 0.0, transition_sides::no_side(), transvoxel::generic_mesh::GenericMeshBuilder::new());
+assert!(mesh.build().num_tris() > 0);
+```
+
+[VoxelSource]: crate::voxel_source::VoxelSource
+*/
+
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::ops::RangeInclusive;
+
+use crate::traits::{Coordinate, VoxelData};
+use crate::transition_sides::TransitionSide;
+use crate::voxel_coordinates::{HighResolutionVoxelIndex, RegularVoxelIndex};
+use crate::voxel_source::{density_bounds_of, Block, VoxelSource};
+
+/// Error when building an [ArrayVoxelSource] from data of the wrong size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArrayVoxelSourceError {
+    /// The regular samples count is not `(subdivisions + 3)³`
+    RegularSamplesCount {
+        /// Expected count
+        expected: usize,
+        /// Provided count
+        actual: usize,
+    },
+    /// The transition samples count for a side is not [transition_samples_count]
+    TransitionSamplesCount {
+        /// The side for which samples were provided
+        side: TransitionSide,
+        /// Expected count
+        expected: usize,
+        /// Provided count
+        actual: usize,
+    },
+}
+
+impl Display for ArrayVoxelSourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArrayVoxelSourceError::RegularSamplesCount { expected, actual } => write!(
+                f,
+                "wrong regular samples count: expected {}, got {}",
+                expected, actual
+            ),
+            ArrayVoxelSourceError::TransitionSamplesCount {
+                side,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "wrong transition samples count for side {:?}: expected {}, got {}",
+                side, expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ArrayVoxelSourceError {}
+
+/// Number of regular samples needed for a block with the given subdivisions
+pub fn regular_samples_count(subdivisions: usize) -> usize {
+    (subdivisions + 3) * (subdivisions + 3) * (subdivisions + 3)
+}
+
+/// Number of double-resolution samples needed for one transition side of a block with the given subdivisions
+pub fn transition_samples_count(subdivisions: usize) -> usize {
+    3 * (2 * subdivisions + 3) * (2 * subdivisions + 3)
+}
+
+/**
+Ranges of the x, y, z indices of the double-resolution samples needed for a transition side.
+
+Indices are on a grid of `2 * subdivisions` cells across the block: index `i` is at `base + size * i / (2 * subdivisions)`.
+The samples form a slab 3 voxels thick across the face (one on each side of it, for gradients), and are ordered x-major,
+like the regular samples.
+*/
+pub fn transition_samples_ranges(
+    subdivisions: usize,
+    side: TransitionSide,
+) -> [RangeInclusive<isize>; 3] {
+    let double_size = 2 * subdivisions as isize;
+    let axis = side as usize / 2;
+    let high = side as usize % 2 == 1;
+    let mut ranges = [
+        -1..=double_size + 1,
+        -1..=double_size + 1,
+        -1..=double_size + 1,
+    ];
+    ranges[axis] = if high {
+        double_size - 1..=double_size + 1
+    } else {
+        -1..=1
+    };
+    ranges
+}
+
+/**
+A [VoxelSource] reading pre-sampled data for one block. See the [module](self) documentation for the data layout.
+
+`D` can be anything giving access to a slice of voxel data: `Vec<V>`, `&[V]`, `Box<[V]>`...
+*/
+pub struct ArrayVoxelSource<V, D>
+where
+    V: VoxelData,
+    D: AsRef<[V]>,
+{
+    subdivisions: usize,
+    regular_samples: D,
+    transition_samples: [Option<D>; 6],
+    // Of all samples of the block (not the apron), and all transition samples
+    density_bounds: Option<(V::Density, V::Density)>,
+    _voxel_data: PhantomData<V>,
+}
+
+impl<V, D> ArrayVoxelSource<V, D>
+where
+    V: VoxelData,
+    D: AsRef<[V]>,
+{
+    /// Wrap the regular samples for the block (including the apron)
+    pub fn new<C: Coordinate>(
+        block: &Block<C>,
+        regular_samples: D,
+    ) -> Result<Self, ArrayVoxelSourceError> {
+        let expected = regular_samples_count(block.subdivisions);
+        let actual = regular_samples.as_ref().len();
+        if actual != expected {
+            return Err(ArrayVoxelSourceError::RegularSamplesCount { expected, actual });
+        }
+        let mut source = Self {
+            subdivisions: block.subdivisions,
+            regular_samples,
+            transition_samples: Default::default(),
+            density_bounds: None,
+            _voxel_data: PhantomData,
+        };
+        let n = block.subdivisions as isize;
+        let block_range = move || 0..=n;
+        let regular = &source;
+        let density_bounds = density_bounds_of(block_range().flat_map(move |x| {
+            block_range()
+                .flat_map(move |y| block_range().map(move |z| regular.regular_sample(x, y, z)))
+        }));
+        source.density_bounds = density_bounds;
+        Ok(source)
+    }
+
+    /// Add the double-resolution samples for a transition side (see [transition_samples_ranges])
+    pub fn with_transition_samples(
+        mut self,
+        side: TransitionSide,
+        samples: D,
+    ) -> Result<Self, ArrayVoxelSourceError> {
+        let expected = transition_samples_count(self.subdivisions);
+        let actual = samples.as_ref().len();
+        if actual != expected {
+            return Err(ArrayVoxelSourceError::TransitionSamplesCount {
+                side,
+                expected,
+                actual,
+            });
+        }
+        let bounds = self
+            .density_bounds
+            .into_iter()
+            .flat_map(|(min, max)| [min, max]);
+        self.density_bounds =
+            density_bounds_of(bounds.chain(samples.as_ref().iter().map(|v| v.density())));
+        self.transition_samples[side as usize] = Some(samples);
+        Ok(self)
+    }
+
+    /// Subdivisions of the block this source was built for
+    pub fn subdivisions(&self) -> usize {
+        self.subdivisions
+    }
+
+    fn regular_sample(&self, x: isize, y: isize, z: isize) -> V {
+        let size = self.subdivisions as isize + 3;
+        let index = (x + 1) * size * size + (y + 1) * size + (z + 1);
+        self.regular_samples.as_ref()[index as usize]
+    }
+}
+
+impl<V, D> VoxelSource<V> for ArrayVoxelSource<V, D>
+where
+    V: VoxelData,
+    D: AsRef<[V]>,
+{
+    fn get_regular_voxel(&mut self, voxel_index: &RegularVoxelIndex) -> V {
+        self.regular_sample(voxel_index.x, voxel_index.y, voxel_index.z)
+    }
+
+    fn get_transition_voxel(&mut self, voxel_index: &HighResolutionVoxelIndex) -> V {
+        let side = voxel_index.cell.side;
+        let index = voxel_index.to_double_resolution_index(self.subdivisions);
+        match self.transition_samples[side as usize].as_ref() {
+            Some(samples) => {
+                let [x_range, y_range, z_range] =
+                    transition_samples_ranges(self.subdivisions, side);
+                let y_size = y_range.end() - y_range.start() + 1;
+                let z_size = z_range.end() - z_range.start() + 1;
+                let flat_index = (index.x - x_range.start()) * y_size * z_size
+                    + (index.y - y_range.start()) * z_size
+                    + (index.z - z_range.start());
+                samples.as_ref()[flat_index as usize]
+            }
+            None => self.regular_sample(
+                index.x.div_euclid(2),
+                index.y.div_euclid(2),
+                index.z.div_euclid(2),
+            ),
+        }
+    }
+
+    fn density_bounds(&self) -> Option<(V::Density, V::Density)> {
+        self.density_bounds
+    }
+}
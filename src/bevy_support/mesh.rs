@@ -1,78 +1,88 @@
 /*!
-This module contains mesh builders to produce [Bevy](https://bevyengine.org/) meshes.
+Mesh builders producing [Bevy](https://bevyengine.org/) meshes
 */
 
 use bevy::asset::RenderAssetUsages;
-use bevy::render::mesh::Mesh;
+use bevy::render::mesh::{Indices, Mesh};
 use bevy::render::render_resource::PrimitiveTopology::{LineList, TriangleList};
-use transvoxel::{mesh_builder::*, traits::*};
+use num::NumCast;
 
+use crate::mesh_builder::{GridPoint, MeshBuilder, VertexIndex};
+use crate::traits::{Density, DensityFloat, VoxelData};
+
+/// A simple bevy mesh builder that:
+///  - only populates position/normal attributes
+///  - only looks at density of the VoxelData
 #[derive(Default)]
 pub struct BevyMeshBuilder {
+    /// Vertex positions
     pub positions: Vec<[f32; 3]>,
+    /// Vertex normals
     pub normals: Vec<[f32; 3]>,
+    /// Each consecutive 3 indices form a triangle
     pub triangle_indices: Vec<u32>,
     vertices: usize,
 }
 
-/// A simple bevy mesh builder that:
-///  - only populates position/normal attributes
-///  - only looks at density of the VoxelData
 impl BevyMeshBuilder {
     /**
     Build a Bevy mesh, producing a triangle list mesh with positions and normals
-    from our mesh, but UV coordinates all set to 0
+    from our mesh, but no UV coordinates
     */
     pub fn build(self) -> Mesh {
         let mut bevy_mesh = Mesh::new(TriangleList, RenderAssetUsages::default());
-        let indices = bevy::render::mesh::Indices::U32(self.triangle_indices);
-        bevy_mesh.insert_indices(indices);
+        bevy_mesh.insert_indices(Indices::U32(self.triangle_indices));
         bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
         bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
         bevy_mesh
     }
+
     /**
     Convert to a Bevy mesh lines list, with positions and normals
-    from our mesh, but UV coordinates all set to 0.
+    from our mesh, but no UV coordinates.
     Lines shared between 2 triangles are repeated, for implementation simplicity.
     */
     pub fn build_wireframe(self) -> Mesh {
         let mut bevy_mesh = Mesh::new(LineList, RenderAssetUsages::default());
         let tris_count = self.triangle_indices.len() / 3;
         let indices = (0..tris_count)
-            .flat_map(|i| vec![3 * i, 3 * i + 1, 3 * i + 1, 3 * i + 2, 3 * i + 2, 3 * i])
+            .flat_map(|i| [3 * i, 3 * i + 1, 3 * i + 1, 3 * i + 2, 3 * i + 2, 3 * i])
             .map(|j| self.triangle_indices[j])
             .collect();
-        let indices = bevy::render::mesh::Indices::U32(indices);
-        bevy_mesh.insert_indices(indices);
+        bevy_mesh.insert_indices(Indices::U32(indices));
         bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
         bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
         bevy_mesh
     }
 }
 
+fn to_f32<A: NumCast>(a: A) -> f32 {
+    <f32 as NumCast>::from(a).unwrap()
+}
+
 impl<V> MeshBuilder<V, f32> for BevyMeshBuilder
 where
-    V: VoxelData<Density = f32>,
+    V: VoxelData,
 {
     fn add_vertex_between(
         &mut self,
         point_a: GridPoint<V, f32>,
         point_b: GridPoint<V, f32>,
-        interp_toward_b: f32,
+        interp_toward_b: DensityFloat<V>,
     ) -> VertexIndex {
         let position = point_a
             .position
-            .interp_toward(&point_b.position, interp_toward_b);
+            .interp_toward(&point_b.position, to_f32(interp_toward_b));
         let gradient_x =
             point_a.gradient.0 + interp_toward_b * (point_b.gradient.0 - point_a.gradient.0);
         let gradient_y =
             point_a.gradient.1 + interp_toward_b * (point_b.gradient.1 - point_a.gradient.1);
         let gradient_z =
             point_a.gradient.2 + interp_toward_b * (point_b.gradient.2 - point_a.gradient.2);
-        let normal = f32::gradients_to_normal(gradient_x, gradient_y, gradient_z);
+        let normal = V::Density::gradients_to_normal(gradient_x, gradient_y, gradient_z);
         self.positions.push([position.x, position.y, position.z]);
-        self.normals.push(normal);
+        self.normals
+            .push([to_f32(normal[0]), to_f32(normal[1]), to_f32(normal[2])]);
         let index = self.vertices;
         self.vertices += 1;
         VertexIndex(index)
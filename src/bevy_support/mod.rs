@@ -0,0 +1,10 @@
+/*!
+[Bevy](https://bevyengine.org/) integration (requires the `bevy` feature)
+
+ * [mesh]: mesh builders producing Bevy meshes, either solid or wireframe
+ * [plugin]: a plugin extracting the meshes of chunk entities in the background, and keeping them up to date
+   when their block or transition sides change
+*/
+
+pub mod mesh;
+pub mod plugin;
@@ -0,0 +1,227 @@
+/*!
+A Bevy plugin managing chunk entities: their meshes are extracted on the async compute task pool, and swapped in when ready
+
+Spawn entities with a [Chunk] component: the plugin gives them a `Mesh3d` (add a material yourself).
+Whenever the block (typically its subdivisions, when the level of detail changes) or the transition sides of a [Chunk] change,
+the mesh is extracted again in the background, and replaces the previous one once ready (the previous one is removed from the assets).
+An [ChunkMeshReady] event is sent each time.
+
+The field can be any [DataField] that can be cloned and sent to other threads: a closure (capturing only
+shared data, for example through an `Arc`), a [sdf](crate::sdf) shape, or your own type. It is cloned for each extraction.
+
+The plugin needs the `Assets<Mesh>` resource: with `DefaultPlugins` it is there, otherwise (for example in headless tests with
+`MinimalPlugins`) add the `AssetPlugin` and call `init_asset::<Mesh>()`.
+
+```no_run
+use bevy::prelude::*;
+use transvoxel::bevy_support::plugin::{Chunk, TransvoxelPlugin};
+use transvoxel::prelude::*;
+
+let sphere = |x: f32, y: f32, z: f32| 5.0 - (x * x + y * y + z * z).sqrt();
+App::new()
+    .add_plugins((DefaultPlugins, TransvoxelPlugin::new(sphere, 0.0)))
+    .add_systems(Startup, |mut commands: Commands| {
+        commands.spawn(Chunk::new(Block::from([0.0, 0.0, 0.0], 10.0, 16), transition_sides::no_side()));
+    })
+    .run();
+```
+
+[DataField]: crate::voxel_source::DataField
+*/
+
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use bevy::tasks::{block_on, poll_once, AsyncComputeTaskPool, Task};
+
+use super::mesh::BevyMeshBuilder;
+use crate::extraction::extract_from_field;
+use crate::traits::VoxelData;
+use crate::transition_sides::TransitionSides;
+use crate::voxel_source::{Block, DataField};
+
+/// The field the plugin extracts meshes from, and its threshold
+#[derive(Resource)]
+pub struct TransvoxelField<V, F>
+where
+    V: VoxelData,
+    V::Density: Send + Sync + 'static,
+    F: DataField<V, f32> + Clone + Send + Sync + 'static,
+{
+    field: F,
+    threshold: V::Density,
+    // fn() so that the voxel data type itself does not need to be Send and Sync
+    voxel_data: PhantomData<fn() -> V>,
+}
+
+impl<V, F> TransvoxelField<V, F>
+where
+    V: VoxelData,
+    V::Density: Send + Sync + 'static,
+    F: DataField<V, f32> + Clone + Send + Sync + 'static,
+{
+    /// Wrap a field
+    pub fn new(field: F, threshold: V::Density) -> Self {
+        Self {
+            field,
+            threshold,
+            voxel_data: PhantomData,
+        }
+    }
+}
+
+impl<V, F> Clone for TransvoxelField<V, F>
+where
+    V: VoxelData,
+    V::Density: Send + Sync + 'static,
+    F: DataField<V, f32> + Clone + Send + Sync + 'static,
+{
+    fn clone(&self) -> Self {
+        Self::new(self.field.clone(), self.threshold)
+    }
+}
+
+/// What to extract for an entity
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+#[require(Transform, Visibility)]
+pub struct Chunk {
+    /// The zone, and its subdivisions
+    pub block: Block<f32>,
+    /// The sides that need transition cells
+    pub transition_sides: TransitionSides,
+    /// Whether to build a lines list instead of a triangles list
+    pub wireframe: bool,
+}
+
+impl Chunk {
+    /// A solid chunk
+    pub fn new(block: Block<f32>, transition_sides: TransitionSides) -> Self {
+        Self {
+            block,
+            transition_sides,
+            wireframe: false,
+        }
+    }
+}
+
+/// What the current mesh of a chunk entity was extracted for
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct ExtractedChunk(pub Chunk);
+
+/// Sent when a chunk entity got a new mesh
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkMeshReady(pub Entity);
+
+// A running extraction
+#[derive(Component)]
+struct ExtractionTask {
+    chunk: Chunk,
+    task: Task<Mesh>,
+}
+
+/// The plugin. See the [module documentation](self)
+pub struct TransvoxelPlugin<V, F>
+where
+    V: VoxelData,
+    V::Density: Send + Sync + 'static,
+    F: DataField<V, f32> + Clone + Send + Sync + 'static,
+{
+    field: TransvoxelField<V, F>,
+}
+
+impl<V, F> TransvoxelPlugin<V, F>
+where
+    V: VoxelData,
+    V::Density: Send + Sync + 'static,
+    F: DataField<V, f32> + Clone + Send + Sync + 'static,
+{
+    /// Plugin extracting from the given field, at the given threshold
+    pub fn new(field: F, threshold: V::Density) -> Self {
+        Self {
+            field: TransvoxelField::new(field, threshold),
+        }
+    }
+}
+
+impl<V, F> Plugin for TransvoxelPlugin<V, F>
+where
+    V: VoxelData + 'static,
+    V::Density: Send + Sync + 'static,
+    F: DataField<V, f32> + Clone + Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.field.clone())
+            .add_event::<ChunkMeshReady>()
+            .add_systems(
+                Update,
+                (start_extractions::<V, F>, finish_extractions).chain(),
+            );
+    }
+}
+
+type ChunkState<'a> = (
+    Entity,
+    &'a Chunk,
+    Option<&'a ExtractedChunk>,
+    Option<&'a ExtractionTask>,
+);
+
+fn start_extractions<V, F>(
+    mut commands: Commands,
+    field: Res<TransvoxelField<V, F>>,
+    chunks: Query<ChunkState, Changed<Chunk>>,
+) where
+    V: VoxelData + 'static,
+    V::Density: Send + Sync + 'static,
+    F: DataField<V, f32> + Clone + Send + Sync + 'static,
+{
+    for (entity, chunk, extracted, running) in chunks.iter() {
+        if extracted.map(|e| &e.0) == Some(chunk) {
+            // Changed back before the extraction finished: dropping the task cancels it
+            commands.entity(entity).remove::<ExtractionTask>();
+            continue;
+        }
+        if running.map(|r| &r.chunk) == Some(chunk) {
+            continue;
+        }
+        let field = field.clone();
+        let chunk = *chunk;
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            let builder = extract_from_field(
+                field.field,
+                &chunk.block,
+                field.threshold,
+                chunk.transition_sides,
+                BevyMeshBuilder::default(),
+            );
+            if chunk.wireframe {
+                builder.build_wireframe()
+            } else {
+                builder.build()
+            }
+        });
+        commands
+            .entity(entity)
+            .insert(ExtractionTask { chunk, task });
+    }
+}
+
+fn finish_extractions(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut ready: EventWriter<ChunkMeshReady>,
+    mut tasks: Query<(Entity, &mut ExtractionTask, Option<&Mesh3d>)>,
+) {
+    for (entity, mut running, previous) in tasks.iter_mut() {
+        if let Some(mesh) = block_on(poll_once(&mut running.task)) {
+            if let Some(previous) = previous {
+                meshes.remove(previous.id());
+            }
+            commands
+                .entity(entity)
+                .insert((Mesh3d(meshes.add(mesh)), ExtractedChunk(running.chunk)))
+                .remove::<ExtractionTask>();
+            ready.send(ChunkMeshReady(entity));
+        }
+    }
+}
@@ -0,0 +1,204 @@
+/*!
+Chunk-by-chunk storage of voxel data, at several levels of detail
+
+A [ChunkedVoxelWorld] stores voxel data for the nodes of a [LodOctree](crate::lod::LodOctree)-like structure, identified by [NodeKey]s.
+Each chunk holds the `n³` voxels of one block with `n` subdivisions (voxels 0 to `n - 1` in each direction, in x-major order).
+The voxels on the high faces of a block, the one voxel apron around it (needed for gradients), and the double-resolution voxels on
+its transition faces all belong to other chunks. [ChunkedVoxelWorld::source] gives a [VoxelSource] for a block that fetches them:
+ * regular voxels from the chunks of the same level
+ * transition voxels from the chunks one level finer (`lod - 1`)
+
+Voxels of chunks that are not stored are `V::default()`.
+
+```
+# use transvoxel::chunked_world::*;
+# use transvoxel::lod::NodeKey;
+# use transvoxel::prelude::*;
+let subdivisions = 4;
+let mut world = ChunkedVoxelWorld::<f32>::new(subdivisions);
+for x in -1..=1 {
+    for y in -1..=1 {
+        for z in -1..=1 {
+            let voxels = (0..64).map(|i| if i % 4 < 2 { 1.0 } else { -1.0 }).collect();
+            world.insert_chunk(NodeKey::from(0, x, y, z), voxels).unwrap();
+        }
+    }
+}
+let block = Block::from([0.0, 0.0, 0.0], 10.0, subdivisions);
+let source = world.source(NodeKey::from(0, 0, 0, 0));
+let mesh = extract(source, &block, 0.0, transition_sides::no_side(), transvoxel::generic_mesh::GenericMeshBuilder::new());
+assert!(mesh.build().num_tris() > 0);
+```
+
+[VoxelSource]: crate::voxel_source::VoxelSource
+*/
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::lod::NodeKey;
+use crate::traits::VoxelData;
+use crate::voxel_coordinates::{HighResolutionVoxelIndex, RegularVoxelIndex};
+use crate::voxel_source::VoxelSource;
+
+/// Error when inserting a chunk with the wrong number of voxels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkSizeError {
+    /// Expected voxels count (`subdivisions³`)
+    pub expected: usize,
+    /// Provided voxels count
+    pub actual: usize,
+}
+
+impl Display for ChunkSizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "wrong chunk voxels count: expected {}, got {}",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for ChunkSizeError {}
+
+/// Voxel data stored by chunks, at several levels of detail. See the [module](self) documentation
+pub struct ChunkedVoxelWorld<V>
+where
+    V: VoxelData,
+{
+    subdivisions: usize,
+    chunks: HashMap<NodeKey, Vec<V>>,
+}
+
+impl<V> ChunkedVoxelWorld<V>
+where
+    V: VoxelData,
+{
+    /// Create an empty world, for blocks with the given subdivisions (at every level)
+    pub fn new(subdivisions: usize) -> Self {
+        Self {
+            subdivisions,
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Subdivisions of the blocks, and size of the chunks in each direction
+    pub fn subdivisions(&self) -> usize {
+        self.subdivisions
+    }
+
+    /// Store the voxels of a chunk, returning the previous ones if any
+    pub fn insert_chunk(
+        &mut self,
+        key: NodeKey,
+        voxels: Vec<V>,
+    ) -> Result<Option<Vec<V>>, ChunkSizeError> {
+        let expected = self.subdivisions * self.subdivisions * self.subdivisions;
+        if voxels.len() != expected {
+            return Err(ChunkSizeError {
+                expected,
+                actual: voxels.len(),
+            });
+        }
+        Ok(self.chunks.insert(key, voxels))
+    }
+
+    /// Forget the voxels of a chunk, returning them
+    pub fn remove_chunk(&mut self, key: &NodeKey) -> Option<Vec<V>> {
+        self.chunks.remove(key)
+    }
+
+    /// Voxels of a chunk
+    pub fn chunk(&self, key: &NodeKey) -> Option<&[V]> {
+        self.chunks.get(key).map(|voxels| &voxels[..])
+    }
+
+    /// Voxels of a chunk, for modification
+    pub fn chunk_mut(&mut self, key: &NodeKey) -> Option<&mut [V]> {
+        self.chunks.get_mut(key).map(|voxels| &mut voxels[..])
+    }
+
+    /// Keys of all stored chunks
+    pub fn keys(&self) -> impl Iterator<Item = &NodeKey> + '_ {
+        self.chunks.keys()
+    }
+
+    /**
+    Voxel at the given level, by its index counted from the world origin (in voxels of that level).
+    Voxel `i` of the chunk at coordinates `c` has index `c * subdivisions + i`
+    */
+    pub fn voxel(&self, lod: usize, index: [isize; 3]) -> V {
+        let n = self.subdivisions as isize;
+        let key = NodeKey {
+            lod,
+            coords: [
+                index[0].div_euclid(n) as i32,
+                index[1].div_euclid(n) as i32,
+                index[2].div_euclid(n) as i32,
+            ],
+        };
+        match self.chunks.get(&key) {
+            Some(voxels) => {
+                let x = index[0].rem_euclid(n);
+                let y = index[1].rem_euclid(n);
+                let z = index[2].rem_euclid(n);
+                voxels[(x * n * n + y * n + z) as usize]
+            }
+            None => V::default(),
+        }
+    }
+
+    /// A [VoxelSource] for the block of the given node
+    pub fn source(&self, key: NodeKey) -> ChunkedVoxelSource<'_, V> {
+        ChunkedVoxelSource { world: self, key }
+    }
+}
+
+/// [VoxelSource] for one block of a [ChunkedVoxelWorld], obtained with [ChunkedVoxelWorld::source]
+pub struct ChunkedVoxelSource<'w, V>
+where
+    V: VoxelData,
+{
+    world: &'w ChunkedVoxelWorld<V>,
+    key: NodeKey,
+}
+
+impl<V> VoxelSource<V> for ChunkedVoxelSource<'_, V>
+where
+    V: VoxelData,
+{
+    fn get_regular_voxel(&mut self, voxel_index: &RegularVoxelIndex) -> V {
+        let n = self.world.subdivisions as isize;
+        let c = self.key.coords;
+        self.world.voxel(
+            self.key.lod,
+            [
+                c[0] as isize * n + voxel_index.x,
+                c[1] as isize * n + voxel_index.y,
+                c[2] as isize * n + voxel_index.z,
+            ],
+        )
+    }
+
+    fn get_transition_voxel(&mut self, index: &HighResolutionVoxelIndex) -> V {
+        if self.key.lod == 0 {
+            // No finer level
+            return V::default();
+        }
+        let n = self.world.subdivisions;
+        // The index is relative to a block of the same size as ours, at double resolution, on the other side of the face
+        let neighbour_index = index.to_higher_res_neighbour_block_index(n);
+        let neighbour = self.key.neighbour(index.cell.side);
+        let fine_n = 2 * n as isize;
+        let c = neighbour.coords;
+        self.world.voxel(
+            self.key.lod - 1,
+            [
+                c[0] as isize * fine_n + neighbour_index.x,
+                c[1] as isize * fine_n + neighbour_index.y,
+                c[2] as isize * fine_n + neighbour_index.z,
+            ],
+        )
+    }
+}
@@ -2,11 +2,11 @@
 Main mesh extraction methods
 */
 
-use super::implementation::algorithm::Extractor;
+use super::implementation::algorithm::{ExtractionBuffers, Extractor};
 use super::mesh_builder::*;
 use super::traits::*;
 use super::voxel_source::*;
-use crate::transition_sides::TransitionSides;
+use crate::transition_sides::{no_side, TransitionSide, TransitionSides};
 
 /**
 Extracts an iso-surface mesh for a [VoxelSource]
@@ -35,6 +35,24 @@ where
     Extractor::new(source, block, threshold, transition_sides, mesh_builder).extract()
 }
 
+/**
+Whether a block can be skipped, because the density bounds of its source are all on the same side of the threshold
+
+This is only possible when the source provides bounds (see [VoxelSource::density_bounds]), and relies on
+[Density::inside] being monotonic: if both bounds are on the same side of the threshold, every density between them must
+be too. All extraction functions already use this to skip empty blocks without querying any voxel.
+*/
+pub fn is_block_empty<V, S>(source: &S, threshold: V::Density) -> bool
+where
+    V: VoxelData,
+    S: VoxelSource<V>,
+{
+    match source.density_bounds() {
+        Some((min, max)) => min.inside(&threshold) == max.inside(&threshold),
+        None => false,
+    }
+}
+
 /**
 Extracts an iso-surface mesh for a [DataField]
 
@@ -90,3 +108,221 @@ where
     let source = WorldMappingVoxelSource { field, block };
     Extractor::new(source, block, threshold, transition_sides, mesh_builder).extract()
 }
+
+/**
+Storage used during extractions, that can be reused from one extraction to the next.
+
+Extracting a block needs some memory proportional to its subdivisions cubed (vertices reuse tables, voxel caches).
+The plain extraction functions allocate it for every block. When extracting many blocks,
+use one context (per thread) with the `*_with_context` functions to avoid these allocations.
+
+A context can be used for blocks of any size. It grows when needed, and then keeps its storage
+for blocks of up to [capacity](ExtractionContext::capacity) subdivisions.
+*/
+pub struct ExtractionContext<V> {
+    buffers: ExtractionBuffers<V>,
+    capacity: usize,
+}
+
+impl<V> ExtractionContext<V> {
+    /// Create an empty context. It will allocate during its first use
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+    /// Create a context, already allocated for blocks of up to `subdivisions`
+    pub fn with_capacity(subdivisions: usize) -> Self {
+        Self {
+            buffers: ExtractionBuffers::with_capacity(subdivisions),
+            capacity: subdivisions,
+        }
+    }
+    /// Block subdivisions this context can be used for without allocating
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+impl<V> Default for ExtractionContext<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/**
+Extracts an iso-surface mesh for a [VoxelSource], reusing the storage of a context
+
+See [extract] and [ExtractionContext] for details
+ */
+pub fn extract_with_context<C, V, S, M>(
+    context: &mut ExtractionContext<V>,
+    source: S,
+    block: &Block<C>,
+    threshold: V::Density,
+    transition_sides: TransitionSides,
+    mesh_builder: M,
+) -> M
+where
+    C: Coordinate,
+    V: VoxelData,
+    S: VoxelSource<V>,
+    M: MeshBuilder<V, C>,
+{
+    let buffers = std::mem::take(&mut context.buffers);
+    let (mesh_builder, buffers) = Extractor::with_buffers(
+        source,
+        block,
+        threshold,
+        transition_sides,
+        mesh_builder,
+        buffers,
+    )
+    .extract_keeping_buffers();
+    context.buffers = buffers;
+    context.capacity = context.capacity.max(block.subdivisions);
+    mesh_builder
+}
+
+/**
+Extracts an iso-surface mesh for a [DataField], reusing the storage of a context
+
+See [extract_from_field] and [ExtractionContext] for details
+ */
+pub fn extract_from_field_with_context<C, V, FIELD, M>(
+    context: &mut ExtractionContext<V>,
+    field: FIELD,
+    block: &Block<C>,
+    threshold: V::Density,
+    transition_sides: TransitionSides,
+    mesh_builder: M,
+) -> M
+where
+    C: Coordinate,
+    V: VoxelData,
+    FIELD: DataField<V, C>,
+    M: MeshBuilder<V, C>,
+{
+    let source = WorldMappingVoxelSource { field, block };
+    extract_with_context(
+        context,
+        source,
+        block,
+        threshold,
+        transition_sides,
+        mesh_builder,
+    )
+}
+
+/**
+Extracts an iso-surface mesh for a closure, reusing the storage of a context
+
+See [extract_from_fn] and [ExtractionContext] for details
+ */
+pub fn extract_from_fn_with_context<C, V, FUN, M>(
+    context: &mut ExtractionContext<V>,
+    field: FUN,
+    block: &Block<C>,
+    threshold: V::Density,
+    transition_sides: TransitionSides,
+    mesh_builder: M,
+) -> M
+where
+    C: Coordinate,
+    V: VoxelData,
+    FUN: FnMut(C, C, C) -> V,
+    M: MeshBuilder<V, C>,
+{
+    extract_from_field_with_context(
+        context,
+        field,
+        block,
+        threshold,
+        transition_sides,
+        mesh_builder,
+    )
+}
+
+/**
+Result of [extract_separated]: the mesh for the regular cells and, separately, the transition cells of each requested side.
+
+This allows a renderer to enable or disable each transition side at draw time, without re-extracting:
+ * all grid points are output at their unshrunk `position`, as if there was no transition side
+ * when some sides are enabled, a grid point should be moved to its `secondary_position` if all its `near_faces` are enabled sides
+ * the transition meshes of enabled sides are drawn along with the regular mesh
+
+See [GridPoint] for the `secondary_position` and `near_faces` details.
+
+[GridPoint]: crate::mesh_builder::GridPoint
+*/
+pub struct SeparatedMeshes<M> {
+    /// Builder which received the regular cells
+    pub regular: M,
+    /// Builders which received the transition cells, indexed by `TransitionSide as usize`. None for sides that were not requested
+    pub transitions: [Option<M>; 6],
+}
+
+impl<M> SeparatedMeshes<M> {
+    /// Builder which received the transition cells of one side, if it was requested
+    pub fn transition(&self, side: TransitionSide) -> Option<&M> {
+        self.transitions[side as usize].as_ref()
+    }
+
+    /// Convert all the builders, for example to build them into meshes: `separated.map(|builder| builder.build())`
+    pub fn map<N>(self, mut f: impl FnMut(M) -> N) -> SeparatedMeshes<N> {
+        SeparatedMeshes {
+            regular: f(self.regular),
+            transitions: self.transitions.map(|t| t.map(&mut f)),
+        }
+    }
+}
+
+/**
+Extracts an iso-surface mesh for a [VoxelSource], outputting the transition cells of each side to a separate builder
+
+Arguments:
+ * `source`: the voxel data source
+ * `block`: the world zone for which to extract, and its subdivisions count
+ * `threshold`: density value defining the iso-surface
+ * `transition_sides`: the set of sides for which transition cells should be extracted (typically all sides that might need to be enabled while this mesh is in use)
+ * `new_builder`: called to create the builder for the regular cells, then one builder for each side in `transition_sides`
+ */
+pub fn extract_separated<C, V, S, M, FM>(
+    source: S,
+    block: &Block<C>,
+    threshold: V::Density,
+    transition_sides: TransitionSides,
+    mut new_builder: FM,
+) -> SeparatedMeshes<M>
+where
+    C: Coordinate,
+    V: VoxelData,
+    S: VoxelSource<V>,
+    M: MeshBuilder<V, C>,
+    FM: FnMut() -> M,
+{
+    let regular_builder = new_builder();
+    Extractor::new(source, block, threshold, no_side(), regular_builder)
+        .extract_separated(transition_sides, new_builder)
+}
+
+/**
+Extracts an iso-surface mesh for a [DataField], outputting the transition cells of each side to a separate builder
+
+See [extract_separated] for details
+ */
+pub fn extract_separated_from_field<C, V, FIELD, M, FM>(
+    field: FIELD,
+    block: &Block<C>,
+    threshold: V::Density,
+    transition_sides: TransitionSides,
+    new_builder: FM,
+) -> SeparatedMeshes<M>
+where
+    C: Coordinate,
+    V: VoxelData,
+    FIELD: DataField<V, C>,
+    M: MeshBuilder<V, C>,
+    FM: FnMut() -> M,
+{
+    let source = WorldMappingVoxelSource { field, block };
+    extract_separated(source, block, threshold, transition_sides, new_builder)
+}
@@ -5,41 +5,123 @@ A generic (engine independent) implementation of a Mesh, and an associated MeshB
 use std::fmt::Debug;
 use std::fmt::Display;
 
-use num::Float;
+use num::{Float, NumCast};
 
 use crate::mesh_builder::GridPoint;
 use crate::mesh_builder::MeshBuilder;
+use crate::mesh_builder::NormalMode;
 use crate::mesh_builder::VertexIndex;
-use crate::traits::Density;
+use crate::mesh_builder::VertexPlacement;
+use crate::traits::{Coordinate, Density, DensityFloat, VoxelData};
+use crate::transition_sides::TransitionSides;
 
 /**
 Mesh
+
+`R` is the type of the voxel data recorded for each vertex, if requested from the builder (see [GenericMeshBuilder::with_voxel_data])
 */
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Mesh<F>
+pub struct Mesh<F, R = ()>
 where
     F: Float,
 {
     /// Flat vector of the vertex positions. Each consecutive three floats define x,y,z for one vertex
     pub positions: Vec<F>,
-    /// Flat vector of the vertex normals. Each consecutive three floats define x,y,z for one vertex
+    /// Flat vector of the vertex normals. Each consecutive three floats define x,y,z for one vertex.
+    /// Empty if the mesh was built with [NormalMode::None]
     pub normals: Vec<F>,
     /**
     Flat vector of the triangle indices. Each consecutive i,j,k define one triangle by 3 indices.
     Indices are referring to the `positions` and `normals` "triples", so each index is in 0..positions.len()
     */
     pub triangle_indices: Vec<usize>,
+    /**
+    Flat vector of the vertex secondary positions (only present if requested from the builder).
+    Each vertex is created between two grid points, which are moved independently (see `near_face_mask`). So each consecutive nine floats
+    define three positions for one vertex: when both grid points are moved, when only the first one is, and when only the second one is.
+    This allows applying the transition shrinking on the GPU, for a mesh extracted without transition sides
+    */
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub secondary_positions: Option<Vec<F>>,
+    /**
+    For each vertex, the sets of block faces its two grid points are near (only present if requested from the builder):
+    each consecutive two masks are for the first and the second grid point of one vertex.
+    A grid point is moved when its mask is not empty, and all the faces in it are transition sides.
+    Bit `i` is set for `TransitionSide` number `i` (ie the bits of a [TransitionSides])
+    */
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub near_face_mask: Option<Vec<u8>>,
+    /// For each vertex, the voxel data of the two grid points it was created between (only present if requested from the builder)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub voxel_data: Option<Vec<VertexVoxelData<R, F>>>,
+    /**
+    Flat vector of the vertex colors, if any. Each consecutive four floats define r,g,b,a (from 0 to 1) for one vertex.
+    Never set by the builder: this is for you to fill, for example from the voxel data, before exporting the mesh (see [mesh_io](crate::mesh_io))
+    */
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub colors: Option<Vec<F>>,
+    /// Additional named per-vertex values. Never set by the builder, like `colors`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub attributes: Vec<VertexAttribute<F>>,
 }
 
-/// A MeshBuilder that builds Mesh
-pub struct GenericMeshBuilder<F>
+/// Named values, one per vertex of a [Mesh]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VertexAttribute<F> {
+    /// Name of the attribute
+    pub name: String,
+    /// One value per vertex
+    pub values: Vec<F>,
+}
+
+/// Voxel data of the two grid points a vertex was created between
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VertexVoxelData<R, F> {
+    /// Data at the first grid point
+    pub a: R,
+    /// Data at the second grid point
+    pub b: R,
+    /// Where the vertex is between them: 0 at `a`, 1 at `b`
+    pub interp_toward_b: F,
+}
+
+/**
+A MeshBuilder that builds Mesh
+
+It works with any voxel data, and with f32 or f64 coordinates: `F` is the coordinate type, also used for the mesh output.
+Interpolation factors and normals are computed in the density float type, and converted to `F` with [NumCast].
+By default, no voxel data is recorded in the mesh (`R` is `()`): see [GenericMeshBuilder::with_voxel_data]
+*/
+pub struct GenericMeshBuilder<F, R = ()>
 where
     F: Float,
 {
     positions: Vec<F>,
     normals: Vec<F>,
     triangle_indices: Vec<usize>,
+    secondary_positions: Option<Vec<F>>,
+    near_face_mask: Option<Vec<u8>>,
+    voxel_data: Option<Vec<VertexVoxelData<R, F>>>,
+    normal_mode: NormalMode,
+    vertex_placement: VertexPlacement,
     vertices: usize,
 }
 
@@ -54,20 +136,196 @@ where
             positions: vec![],
             normals: vec![],
             triangle_indices: vec![],
+            secondary_positions: None,
+            near_face_mask: None,
+            voxel_data: None,
+            normal_mode: NormalMode::Gradient,
+            vertex_placement: VertexPlacement::Interpolated,
             vertices: 0,
         }
     }
+    /**
+    Also record, for each vertex, the voxel data of the two grid points it was created between (see [Mesh::voxel_data]).
+    `V` has to be the voxel data type of the extraction
+    */
+    pub fn with_voxel_data<V: VoxelData>(self) -> GenericMeshBuilder<F, V> {
+        GenericMeshBuilder {
+            positions: self.positions,
+            normals: self.normals,
+            triangle_indices: self.triangle_indices,
+            secondary_positions: self.secondary_positions,
+            near_face_mask: self.near_face_mask,
+            voxel_data: Some(vec![]),
+            normal_mode: self.normal_mode,
+            vertex_placement: self.vertex_placement,
+            vertices: self.vertices,
+        }
+    }
+}
+
+impl<F, R> GenericMeshBuilder<F, R>
+where
+    F: Float,
+    R: Copy,
+{
+    /**
+    Choose which normals to output (default is [NormalMode::Gradient]).
+    With [NormalMode::Face], vertices are not shared between triangles anymore: each triangle gets its own 3 vertices
+    */
+    pub fn with_normal_mode(mut self, normal_mode: NormalMode) -> Self {
+        self.normal_mode = normal_mode;
+        self
+    }
+    /// Choose how vertices are placed along edges (default is [VertexPlacement::Interpolated])
+    pub fn with_vertex_placement(mut self, vertex_placement: VertexPlacement) -> Self {
+        self.vertex_placement = vertex_placement;
+        self
+    }
+    /// Also output secondary positions and near faces masks for the vertices (see [Mesh::secondary_positions])
+    pub fn with_secondary_positions(mut self) -> Self {
+        self.secondary_positions = Some(vec![]);
+        self.near_face_mask = Some(vec![]);
+        self
+    }
     /// Output the Mesh
-    pub fn build(self) -> Mesh<F> {
+    pub fn build(self) -> Mesh<F, R> {
+        let face_normals = self.normal_mode == NormalMode::Face;
+        let mesh = self.build_indexed();
+        if face_normals {
+            mesh.with_face_normals()
+        } else {
+            mesh
+        }
+    }
+    // The mesh with shared vertices, even for face normals (it then has no normals yet)
+    pub(crate) fn build_indexed(self) -> Mesh<F, R> {
         Mesh {
             positions: self.positions,
             normals: self.normals,
             triangle_indices: self.triangle_indices,
+            secondary_positions: self.secondary_positions,
+            near_face_mask: self.near_face_mask,
+            voxel_data: self.voxel_data,
+            colors: None,
+            attributes: vec![],
+        }
+    }
+}
+
+impl<F, R> Mesh<F, R>
+where
+    F: Float,
+    R: Copy,
+{
+    // Un-share vertices, and give each triangle its face normal. All the other per-vertex values follow their vertex
+    pub(crate) fn with_face_normals(self) -> Self {
+        let num_vertices = self.triangle_indices.len();
+        let mut positions = Vec::with_capacity(3 * num_vertices);
+        let mut normals = Vec::with_capacity(3 * num_vertices);
+        for tri in self.triangle_indices.chunks(3) {
+            let corners: Vec<[F; 3]> = tri
+                .iter()
+                .map(|i| {
+                    [
+                        self.positions[3 * i],
+                        self.positions[3 * i + 1],
+                        self.positions[3 * i + 2],
+                    ]
+                })
+                .collect();
+            let normal = face_normal(&corners[0], &corners[1], &corners[2]);
+            for corner in corners.iter() {
+                positions.extend_from_slice(corner);
+                normals.extend_from_slice(&normal);
+            }
+        }
+        let indices = &self.triangle_indices;
+        // Values of each (new) vertex, when each (old) vertex has `n` consecutive ones
+        let expand = |values: &[F], n: usize| -> Vec<F> {
+            indices
+                .iter()
+                .flat_map(|i| values[n * i..n * i + n].iter().copied())
+                .collect()
+        };
+        Mesh {
+            secondary_positions: self
+                .secondary_positions
+                .as_ref()
+                .map(|secondary| expand(secondary, 9)),
+            near_face_mask: self.near_face_mask.as_ref().map(|mask| {
+                indices
+                    .iter()
+                    .flat_map(|i| [mask[2 * i], mask[2 * i + 1]])
+                    .collect()
+            }),
+            voxel_data: self
+                .voxel_data
+                .as_ref()
+                .map(|data| indices.iter().map(|i| data[*i]).collect()),
+            colors: self.colors.as_ref().map(|colors| expand(colors, 4)),
+            attributes: self
+                .attributes
+                .iter()
+                .map(|attribute| VertexAttribute {
+                    name: attribute.name.clone(),
+                    values: expand(&attribute.values, 1),
+                })
+                .collect(),
+            positions,
+            normals,
+            triangle_indices: (0..num_vertices).collect(),
+        }
+    }
+}
+
+// Normal of a counter-clockwise triangle (zero for a degenerate one)
+pub(crate) fn face_normal<F: Float>(a: &[F; 3], b: &[F; 3], c: &[F; 3]) -> [F; 3] {
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let cross = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let norm = (cross[0] * cross[0] + cross[1] * cross[1] + cross[2] * cross[2]).sqrt();
+    if norm > F::zero() {
+        [cross[0] / norm, cross[1] / norm, cross[2] / norm]
+    } else {
+        [F::zero(); 3]
+    }
+}
+
+// Vertex normals averaged from the normals of the triangles around each vertex (zero for isolated vertices)
+pub(crate) fn averaged_normals<F: Float, R>(mesh: &Mesh<F, R>) -> Vec<F> {
+    let mut sums = vec![F::zero(); mesh.positions.len()];
+    let position = |i: usize| {
+        [
+            mesh.positions[3 * i],
+            mesh.positions[3 * i + 1],
+            mesh.positions[3 * i + 2],
+        ]
+    };
+    for tri in mesh.triangle_indices.chunks_exact(3) {
+        let normal = face_normal(&position(tri[0]), &position(tri[1]), &position(tri[2]));
+        for i in tri {
+            for c in 0..3 {
+                sums[3 * i + c] = sums[3 * i + c] + normal[c];
+            }
         }
     }
+    sums.chunks_exact(3)
+        .flat_map(|s| {
+            let norm = (s[0] * s[0] + s[1] * s[1] + s[2] * s[2]).sqrt();
+            if norm > F::zero() {
+                [s[0] / norm, s[1] / norm, s[2] / norm]
+            } else {
+                [F::zero(); 3]
+            }
+        })
+        .collect()
 }
 
-impl<F> Mesh<F>
+impl<F, R> Mesh<F, R>
 where
     F: Float,
 {
@@ -75,6 +333,40 @@ where
     pub fn num_tris(&self) -> usize {
         self.triangle_indices.len() / 3
     }
+    /**
+    Vertex positions to use when the given sides are transition sides (this is what a vertex shader would do with secondary positions).
+    None if the mesh was built without secondary positions
+    */
+    pub fn positions_for_sides(&self, transition_sides: TransitionSides) -> Option<Vec<F>> {
+        let secondary_positions = self.secondary_positions.as_ref()?;
+        let near_face_mask = self.near_face_mask.as_ref()?;
+        let enabled = transition_sides.bits();
+        let moved = |mask: u8| (mask != 0) && (mask & !enabled == 0);
+        let mut positions = self.positions.clone();
+        for (i, masks) in near_face_mask.chunks_exact(2).enumerate() {
+            let secondary = match (moved(masks[0]), moved(masks[1])) {
+                (true, true) => 0,
+                (true, false) => 1,
+                (false, true) => 2,
+                (false, false) => continue,
+            };
+            let start = 9 * i + 3 * secondary;
+            positions[3 * i..3 * i + 3].copy_from_slice(&secondary_positions[start..start + 3]);
+        }
+        Some(positions)
+    }
+    // Zero if the mesh has no normals
+    fn normal(&self, vertex_index: usize) -> [F; 3] {
+        if self.normals.is_empty() {
+            [F::zero(); 3]
+        } else {
+            [
+                self.normals[3 * vertex_index],
+                self.normals[3 * vertex_index + 1],
+                self.normals[3 * vertex_index + 2],
+            ]
+        }
+    }
     /// Outputs a copy of triangles in a structured format
     pub fn tris(&self) -> Vec<Triangle<F>> {
         let mut tris: Vec<Triangle<F>> = vec![];
@@ -90,11 +382,7 @@ where
                             self.positions[3 * i1 + 1],
                             self.positions[3 * i1 + 2],
                         ],
-                        normal: [
-                            self.normals[3 * i1],
-                            self.normals[3 * i1 + 1],
-                            self.normals[3 * i1 + 2],
-                        ],
+                        normal: self.normal(i1),
                     },
                     Vertex {
                         position: [
@@ -102,11 +390,7 @@ where
                             self.positions[3 * i2 + 1],
                             self.positions[3 * i2 + 2],
                         ],
-                        normal: [
-                            self.normals[3 * i2],
-                            self.normals[3 * i2 + 1],
-                            self.normals[3 * i2 + 2],
-                        ],
+                        normal: self.normal(i2),
                     },
                     Vertex {
                         position: [
@@ -114,11 +398,7 @@ where
                             self.positions[3 * i3 + 1],
                             self.positions[3 * i3 + 2],
                         ],
-                        normal: [
-                            self.normals[3 * i3],
-                            self.normals[3 * i3 + 1],
-                            self.normals[3 * i3 + 2],
-                        ],
+                        normal: self.normal(i3),
                     },
                 ],
             });
@@ -163,35 +443,53 @@ where
     }
 }
 
-impl MeshBuilder<f32, f32> for GenericMeshBuilder<f32> {
-    fn add_vertex_between(
+impl<F, R> GenericMeshBuilder<F, R>
+where
+    F: Coordinate,
+{
+    fn add_vertex<V: VoxelData>(
         &mut self,
-        point_a: GridPoint<f32, f32>,
-        point_b: GridPoint<f32, f32>,
-        interp_toward_b: f32,
+        point_a: &GridPoint<V, F>,
+        point_b: &GridPoint<V, F>,
+        interp_toward_b: DensityFloat<V>,
     ) -> VertexIndex {
         let position = point_a
             .position
-            .interp_toward(&point_b.position, interp_toward_b);
-        let gradient_x =
-            point_a.gradient.0 + interp_toward_b * (point_b.gradient.0 - point_a.gradient.0);
-        let gradient_y =
-            point_a.gradient.1 + interp_toward_b * (point_b.gradient.1 - point_a.gradient.1);
-        let gradient_z =
-            point_a.gradient.2 + interp_toward_b * (point_b.gradient.2 - point_a.gradient.2);
-        let normal = f32::gradients_to_normal(gradient_x, gradient_y, gradient_z);
+            .interp_toward(&point_b.position, convert(interp_toward_b));
         self.positions.push(position.x);
         self.positions.push(position.y);
         self.positions.push(position.z);
-        self.normals.push(normal[0]);
-        self.normals.push(normal[1]);
-        self.normals.push(normal[2]);
+        if self.normal_mode == NormalMode::Gradient {
+            let gradient_x =
+                point_a.gradient.0 + interp_toward_b * (point_b.gradient.0 - point_a.gradient.0);
+            let gradient_y =
+                point_a.gradient.1 + interp_toward_b * (point_b.gradient.1 - point_a.gradient.1);
+            let gradient_z =
+                point_a.gradient.2 + interp_toward_b * (point_b.gradient.2 - point_a.gradient.2);
+            let normal = V::Density::gradients_to_normal(gradient_x, gradient_y, gradient_z);
+            self.normals.push(convert(normal[0]));
+            self.normals.push(convert(normal[1]));
+            self.normals.push(convert(normal[2]));
+        }
+        if let Some(secondary_positions) = self.secondary_positions.as_mut() {
+            for secondary_position in
+                point_a.secondary_positions_with(point_b, convert(interp_toward_b))
+            {
+                secondary_positions.push(secondary_position.x);
+                secondary_positions.push(secondary_position.y);
+                secondary_positions.push(secondary_position.z);
+            }
+        }
+        if let Some(near_face_mask) = self.near_face_mask.as_mut() {
+            near_face_mask.push(point_a.near_faces.bits());
+            near_face_mask.push(point_b.near_faces.bits());
+        }
         let index = self.vertices;
         self.vertices += 1;
         VertexIndex(index)
     }
 
-    fn add_triangle(
+    fn add_tri(
         &mut self,
         vertex_1_index: VertexIndex,
         vertex_2_index: VertexIndex,
@@ -202,3 +500,79 @@ impl MeshBuilder<f32, f32> for GenericMeshBuilder<f32> {
         self.triangle_indices.push(vertex_3_index.0);
     }
 }
+
+fn convert<A: NumCast, B: NumCast>(a: A) -> B {
+    B::from(a).unwrap()
+}
+
+impl<V, F> MeshBuilder<V, F> for GenericMeshBuilder<F>
+where
+    V: VoxelData,
+    F: Coordinate,
+{
+    fn normal_mode(&self) -> NormalMode {
+        self.normal_mode
+    }
+
+    fn vertex_placement(&self) -> VertexPlacement {
+        self.vertex_placement
+    }
+
+    fn add_vertex_between(
+        &mut self,
+        point_a: GridPoint<V, F>,
+        point_b: GridPoint<V, F>,
+        interp_toward_b: DensityFloat<V>,
+    ) -> VertexIndex {
+        self.add_vertex(&point_a, &point_b, interp_toward_b)
+    }
+
+    fn add_triangle(
+        &mut self,
+        vertex_1_index: VertexIndex,
+        vertex_2_index: VertexIndex,
+        vertex_3_index: VertexIndex,
+    ) {
+        self.add_tri(vertex_1_index, vertex_2_index, vertex_3_index)
+    }
+}
+
+/// When recording voxel data
+impl<V, F> MeshBuilder<V, F> for GenericMeshBuilder<F, V>
+where
+    V: VoxelData,
+    F: Coordinate,
+{
+    fn normal_mode(&self) -> NormalMode {
+        self.normal_mode
+    }
+
+    fn vertex_placement(&self) -> VertexPlacement {
+        self.vertex_placement
+    }
+
+    fn add_vertex_between(
+        &mut self,
+        point_a: GridPoint<V, F>,
+        point_b: GridPoint<V, F>,
+        interp_toward_b: DensityFloat<V>,
+    ) -> VertexIndex {
+        if let Some(voxel_data) = self.voxel_data.as_mut() {
+            voxel_data.push(VertexVoxelData {
+                a: point_a.voxel_data,
+                b: point_b.voxel_data,
+                interp_toward_b: convert(interp_toward_b),
+            });
+        }
+        self.add_vertex(&point_a, &point_b, interp_toward_b)
+    }
+
+    fn add_triangle(
+        &mut self,
+        vertex_1_index: VertexIndex,
+        vertex_2_index: VertexIndex,
+        vertex_3_index: VertexIndex,
+    ) {
+        self.add_tri(vertex_1_index, vertex_2_index, vertex_3_index)
+    }
+}
@@ -0,0 +1,434 @@
+/*!
+Export of extracted meshes to binary glTF 2.0 (`.glb`) files, to review them in standard viewers
+
+A [GlbScene] gathers one node per [Block]. Each node is placed at the block base (so the vertex positions are stored relative to it),
+and holds one or several primitives: typically one per material, plus optionally the transition patches from [extract_separated], so
+that the seams can be inspected separately.
+
+Positions, normals (if any) and colors (if any) are exported as `f32` vertex attributes, and indices as `u32`.
+
+```rust
+use transvoxel::prelude::*;
+use transvoxel::generic_mesh::{GenericMeshBuilder, Mesh};
+use transvoxel::gltf_export::GlbScene;
+
+let field = |x: f32, y: f32, z: f32| 15.0 - (x * x + y * y + z * z).sqrt();
+let blocks = [
+    Block::from([0.0, 0.0, 0.0], 10.0, 10),
+    Block::from([10.0, 0.0, 0.0], 10.0, 10),
+];
+let meshes: Vec<Mesh<f32>> = blocks
+    .iter()
+    .map(|block| extract_from_field(&field, block, 0.0, transition_sides::no_side(), GenericMeshBuilder::new()).build())
+    .collect();
+
+let mut scene = GlbScene::new();
+let rock = scene.add_material("rock", [0.5, 0.45, 0.4, 1.0]);
+for (block, mesh) in blocks.iter().zip(meshes.iter()) {
+    scene.add_block(block, mesh, Some(rock));
+}
+let mut file = Vec::new();
+scene.write_glb(&mut file).unwrap();
+assert_eq!(&file[0..4], b"glTF");
+```
+
+[Block]: crate::voxel_source::Block
+[extract_separated]: crate::extraction::extract_separated
+*/
+
+use std::fmt::Write as _;
+use std::io::Write;
+
+use num::Float;
+
+use crate::extraction::SeparatedMeshes;
+use crate::generic_mesh::Mesh;
+use crate::mesh_io::MeshIoError;
+use crate::traits::Coordinate;
+use crate::transition_sides::{all_sides, TransitionSide};
+use crate::voxel_source::Block;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+const COMPONENT_FLOAT: u32 = 5126;
+const COMPONENT_UNSIGNED_INT: u32 = 5125;
+const TARGET_ARRAY_BUFFER: u32 = 34962;
+const TARGET_ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const MODE_TRIANGLES: u32 = 4;
+
+/// One mesh of a node, drawn with one material
+#[derive(Debug, Clone, Copy)]
+pub struct GltfPrimitive<'a, F>
+where
+    F: Float,
+{
+    /// The mesh, with positions in world coordinates
+    pub mesh: &'a Mesh<F>,
+    /// Index of the material, as returned by [GlbScene::add_material]. None for the viewer's default material
+    pub material: Option<usize>,
+    /// If this primitive is the transition patch of one side. This is only recorded in the primitive's `extras`
+    pub transition_side: Option<TransitionSide>,
+}
+
+struct GltfMaterial {
+    name: String,
+    base_color: [f32; 4],
+}
+
+struct GltfNode<'a, F>
+where
+    F: Float,
+{
+    name: String,
+    translation: [f64; 3],
+    primitives: Vec<GltfPrimitive<'a, F>>,
+}
+
+/// A set of blocks meshes, to be written as a glTF binary file
+pub struct GlbScene<'a, F>
+where
+    F: Float,
+{
+    materials: Vec<GltfMaterial>,
+    nodes: Vec<GltfNode<'a, F>>,
+}
+
+#[allow(clippy::new_without_default)]
+impl<'a, F> GlbScene<'a, F>
+where
+    F: Float,
+{
+    /// Create an empty scene
+    pub fn new() -> Self {
+        Self {
+            materials: vec![],
+            nodes: vec![],
+        }
+    }
+
+    /// Add a simple material (non metallic, fully rough), and return its index
+    pub fn add_material(&mut self, name: &str, base_color: [f32; 4]) -> usize {
+        self.materials.push(GltfMaterial {
+            name: name.to_string(),
+            base_color,
+        });
+        self.materials.len() - 1
+    }
+
+    /// Add a node for a block, with a single primitive. Returns the node index
+    pub fn add_block<C: Coordinate>(
+        &mut self,
+        block: &Block<C>,
+        mesh: &'a Mesh<F>,
+        material: Option<usize>,
+    ) -> usize {
+        self.add_block_primitives(
+            block,
+            vec![GltfPrimitive {
+                mesh,
+                material,
+                transition_side: None,
+            }],
+        )
+    }
+
+    /// Add a node for a block, with any number of primitives (for example one per material). Returns the node index
+    pub fn add_block_primitives<C: Coordinate>(
+        &mut self,
+        block: &Block<C>,
+        primitives: Vec<GltfPrimitive<'a, F>>,
+    ) -> usize {
+        let base = block.dims.base;
+        let translation = [0, 1, 2].map(|i| base[i].to_f64().unwrap_or(0.0));
+        self.nodes.push(GltfNode {
+            name: format!(
+                "block_{}_{}_{}_{}",
+                translation[0],
+                translation[1],
+                translation[2],
+                block.dims.size.to_f64().unwrap_or(0.0)
+            ),
+            translation,
+            primitives,
+        });
+        self.nodes.len() - 1
+    }
+
+    /**
+    Add a node for a block extracted with [extract_separated](crate::extraction::extract_separated):
+    the regular mesh is one primitive, and each transition patch another one.
+
+    `transition_material` allows drawing the patches differently, to make the seams visible. Returns the node index
+    */
+    pub fn add_separated<C: Coordinate>(
+        &mut self,
+        block: &Block<C>,
+        meshes: &'a SeparatedMeshes<Mesh<F>>,
+        material: Option<usize>,
+        transition_material: Option<usize>,
+    ) -> usize {
+        let mut primitives = vec![GltfPrimitive {
+            mesh: &meshes.regular,
+            material,
+            transition_side: None,
+        }];
+        for side in all_sides() {
+            if let Some(mesh) = meshes.transition(side) {
+                primitives.push(GltfPrimitive {
+                    mesh,
+                    material: transition_material.or(material),
+                    transition_side: Some(side),
+                });
+            }
+        }
+        self.add_block_primitives(block, primitives)
+    }
+
+    /// Write the scene as a `.glb` file. Empty primitives are skipped, as glTF does not allow them
+    pub fn write_glb(&self, mut writer: impl Write) -> Result<(), MeshIoError> {
+        let mut out = GlbWriter::default();
+        let mut nodes_json = vec![];
+        let mut meshes_json = vec![];
+        for node in &self.nodes {
+            let mut primitives_json = vec![];
+            for primitive in &node.primitives {
+                if primitive.mesh.triangle_indices.is_empty() {
+                    continue;
+                }
+                primitives_json.push(out.add_primitive(primitive, &node.translation));
+            }
+            let mut node_json = format!(
+                r#"{{"name":{},"translation":[{},{},{}]"#,
+                json_string(&node.name),
+                node.translation[0],
+                node.translation[1],
+                node.translation[2]
+            );
+            if !primitives_json.is_empty() {
+                write!(node_json, r#","mesh":{}"#, meshes_json.len()).unwrap();
+                meshes_json.push(format!(
+                    r#"{{"name":{},"primitives":[{}]}}"#,
+                    json_string(&node.name),
+                    primitives_json.join(",")
+                ));
+            }
+            node_json.push('}');
+            nodes_json.push(node_json);
+        }
+        let materials_json: Vec<String> = self
+            .materials
+            .iter()
+            .map(|m| {
+                format!(
+                    r#"{{"name":{},"pbrMetallicRoughness":{{"baseColorFactor":[{},{},{},{}],"metallicFactor":0,"roughnessFactor":1}}}}"#,
+                    json_string(&m.name),
+                    m.base_color[0],
+                    m.base_color[1],
+                    m.base_color[2],
+                    m.base_color[3]
+                )
+            })
+            .collect();
+        let mut json = String::new();
+        write!(
+            json,
+            r#"{{"asset":{{"version":"2.0","generator":"transvoxel"}},"scene":0,"scenes":[{{"nodes":[{}]}}],"nodes":[{}]"#,
+            (0..self.nodes.len())
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+            nodes_json.join(",")
+        )
+        .unwrap();
+        for (key, values) in [
+            ("meshes", &meshes_json),
+            ("materials", &materials_json),
+            ("accessors", &out.accessors),
+            ("bufferViews", &out.buffer_views),
+        ] {
+            if !values.is_empty() {
+                write!(json, r#","{}":[{}]"#, key, values.join(",")).unwrap();
+            }
+        }
+        if !out.bin.is_empty() {
+            write!(json, r#","buffers":[{{"byteLength":{}}}]"#, out.bin.len()).unwrap();
+        }
+        json.push('}');
+
+        let mut json = json.into_bytes();
+        pad(&mut json, b' ');
+        let mut bin = out.bin;
+        pad(&mut bin, 0);
+        let mut total_length = 12 + 8 + json.len();
+        if !bin.is_empty() {
+            total_length += 8 + bin.len();
+        }
+        for value in [GLB_MAGIC, GLB_VERSION, total_length as u32] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.write_all(&(json.len() as u32).to_le_bytes())?;
+        writer.write_all(&CHUNK_JSON.to_le_bytes())?;
+        writer.write_all(&json)?;
+        if !bin.is_empty() {
+            writer.write_all(&(bin.len() as u32).to_le_bytes())?;
+            writer.write_all(&CHUNK_BIN.to_le_bytes())?;
+            writer.write_all(&bin)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+// Accumulates the binary buffer, and the JSON of its views and accessors
+#[derive(Default)]
+struct GlbWriter {
+    bin: Vec<u8>,
+    buffer_views: Vec<String>,
+    accessors: Vec<String>,
+}
+
+impl GlbWriter {
+    // Returns the accessor index
+    fn add_accessor(
+        &mut self,
+        bytes: &[u8],
+        target: u32,
+        component_type: u32,
+        count: usize,
+        accessor_type: &str,
+        min_max: Option<([f32; 3], [f32; 3])>,
+    ) -> usize {
+        let offset = self.bin.len();
+        self.bin.extend_from_slice(bytes);
+        self.buffer_views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{}}}"#,
+            offset,
+            bytes.len(),
+            target
+        ));
+        let mut accessor = format!(
+            r#"{{"bufferView":{},"componentType":{},"count":{},"type":"{}""#,
+            self.buffer_views.len() - 1,
+            component_type,
+            count,
+            accessor_type
+        );
+        if let Some((min, max)) = min_max {
+            write!(
+                accessor,
+                r#","min":[{},{},{}],"max":[{},{},{}]"#,
+                min[0], min[1], min[2], max[0], max[1], max[2]
+            )
+            .unwrap();
+        }
+        accessor.push('}');
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn add_floats<F: Float>(
+        &mut self,
+        values: &[F],
+        accessor_type: &str,
+        components: usize,
+        min_max: Option<([f32; 3], [f32; 3])>,
+    ) -> usize {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flat_map(|v| v.to_f32().unwrap_or(f32::NAN).to_le_bytes())
+            .collect();
+        self.add_accessor(
+            &bytes,
+            TARGET_ARRAY_BUFFER,
+            COMPONENT_FLOAT,
+            values.len() / components,
+            accessor_type,
+            min_max,
+        )
+    }
+
+    // Returns the primitive JSON
+    fn add_primitive<F: Float>(
+        &mut self,
+        primitive: &GltfPrimitive<F>,
+        translation: &[f64; 3],
+    ) -> String {
+        let mesh = primitive.mesh;
+        let positions: Vec<f32> = mesh
+            .positions
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p.to_f64().unwrap_or(f64::NAN) - translation[i % 3]) as f32)
+            .collect();
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+        for position in positions.chunks_exact(3) {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(position[axis]);
+                max[axis] = max[axis].max(position[axis]);
+            }
+        }
+        let mut attributes = format!(
+            r#""POSITION":{}"#,
+            self.add_floats(&positions, "VEC3", 3, Some((min, max)))
+        );
+        if !mesh.normals.is_empty() {
+            let normals = self.add_floats(&mesh.normals, "VEC3", 3, None);
+            write!(attributes, r#","NORMAL":{}"#, normals).unwrap();
+        }
+        if let Some(colors) = &mesh.colors {
+            let colors = self.add_floats(colors, "VEC4", 4, None);
+            write!(attributes, r#","COLOR_0":{}"#, colors).unwrap();
+        }
+        let indices: Vec<u8> = mesh
+            .triangle_indices
+            .iter()
+            .flat_map(|&i| (i as u32).to_le_bytes())
+            .collect();
+        let indices = self.add_accessor(
+            &indices,
+            TARGET_ELEMENT_ARRAY_BUFFER,
+            COMPONENT_UNSIGNED_INT,
+            mesh.triangle_indices.len(),
+            "SCALAR",
+            None,
+        );
+        let mut json = format!(
+            r#"{{"attributes":{{{}}},"indices":{},"mode":{}"#,
+            attributes, indices, MODE_TRIANGLES
+        );
+        if let Some(material) = primitive.material {
+            write!(json, r#","material":{}"#, material).unwrap();
+        }
+        if let Some(side) = primitive.transition_side {
+            write!(json, r#","extras":{{"transition_side":"{:?}"}}"#, side).unwrap();
+        }
+        json.push('}');
+        json
+    }
+}
+
+// Chunks must be 4-bytes aligned
+// (`is_multiple_of` would require a much more recent compiler)
+#[allow(clippy::manual_is_multiple_of)]
+fn pad(bytes: &mut Vec<u8>, with: u8) {
+    while bytes.len() % 4 != 0 {
+        bytes.push(with);
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
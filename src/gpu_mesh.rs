@@ -0,0 +1,474 @@
+/*!
+A [MeshBuilder] producing interleaved vertex buffers, ready to be uploaded to the GPU by any renderer (requires the `bytemuck` feature)
+
+Each vertex is written as consecutive 4-bytes aligned attributes, in this order (see [VertexLayout]):
+ * position: 3 `f32`
+ * normal (optional): 3 `f32`
+ * color (optional): 4 `u8` (typically used as normalized values)
+ * material id (optional): 1 `u32`
+ * secondary positions and near faces masks (optional): 9 `f32` and 1 `u32` (see [Mesh::secondary_positions] for their meaning)
+ * triplanar UVs, tangents and blend weights (optional): 3 times 2 `f32`, 3 times 4 `f32`, and 3 `f32`, for the
+   projections along x, y and z (see [Triplanar])
+
+Colors and material ids are computed from the voxel data of the two grid points each vertex is created between, by functions you provide.
+
+Indices are `u16` if there are few enough vertices, `u32` otherwise.
+
+The vertex buffer can be used as raw bytes, or as a slice of any `#[repr(C)]` [Pod] struct matching the layout.
+[GpuVertex], [GpuColorVertex] and [GpuSecondaryVertex] match some common layouts:
+```rust
+use transvoxel::prelude::*;
+use transvoxel::gpu_mesh::{GpuColorVertex, GpuMeshBuilder, IndexBuffer};
+
+let field = |x: f32, y: f32, z: f32| 5.0 - (x * x + y * y + z * z).sqrt();
+let block = Block::from([0.0, 0.0, 0.0], 10.0, 10);
+let builder = GpuMeshBuilder::new().with_color(|_, _, _| [255, 0, 0, 255]);
+let mesh = extract_from_field(&field, &block, 0.0, transition_sides::no_side(), builder).build();
+
+let vertices: &[GpuColorVertex] = mesh.vertices().unwrap();
+assert_eq!(vertices[0].color, [255, 0, 0, 255]);
+assert_eq!(mesh.vertex_bytes().len(), vertices.len() * mesh.layout.stride());
+assert!(matches!(mesh.indices, IndexBuffer::U16(_)));
+```
+
+[Mesh::secondary_positions]: crate::generic_mesh::Mesh::secondary_positions
+[Triplanar]: crate::triplanar::Triplanar
+[MeshBuilder]: crate::mesh_builder::MeshBuilder
+[Pod]: bytemuck::Pod
+*/
+
+use std::mem::size_of;
+
+use bytemuck::{Pod, PodCastError, Zeroable};
+use num::NumCast;
+
+use crate::mesh_builder::{GridPoint, MeshBuilder, NormalMode, VertexIndex, VertexPlacement};
+use crate::traits::{Coordinate, Density, DensityFloat, VoxelData};
+use crate::triplanar::Triplanar;
+
+/// Which attributes are present in each vertex, and where
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VertexLayout {
+    /// Vertex normal, computed from the density gradients
+    pub normal: bool,
+    /// RGBA color
+    pub color: bool,
+    /// Material id
+    pub material: bool,
+    /// Secondary positions and near faces masks
+    pub secondary_position: bool,
+    /// Triplanar UVs, tangents and blend weights
+    pub triplanar: bool,
+}
+
+impl Default for VertexLayout {
+    /// Positions and normals only
+    fn default() -> Self {
+        Self {
+            normal: true,
+            color: false,
+            material: false,
+            secondary_position: false,
+            triplanar: false,
+        }
+    }
+}
+
+impl VertexLayout {
+    /// Size in bytes of one vertex
+    pub fn stride(&self) -> usize {
+        4 * self.words()
+    }
+
+    /// Byte offset of the normal within a vertex, if present
+    pub fn normal_offset(&self) -> Option<usize> {
+        self.offset_if(self.normal, 3)
+    }
+
+    /// Byte offset of the color within a vertex, if present
+    pub fn color_offset(&self) -> Option<usize> {
+        self.offset_if(self.color, 3 + self.normal_words())
+    }
+
+    /// Byte offset of the material id within a vertex, if present
+    pub fn material_offset(&self) -> Option<usize> {
+        self.offset_if(self.material, 3 + self.normal_words() + self.color as usize)
+    }
+
+    /// Byte offset of the secondary positions within a vertex, if present. The near faces masks follow them, 36 bytes later
+    pub fn secondary_position_offset(&self) -> Option<usize> {
+        self.offset_if(
+            self.secondary_position,
+            3 + self.normal_words() + self.color as usize + self.material as usize,
+        )
+    }
+
+    /// Byte offset of the triplanar UVs within a vertex, if present. Those of the x, y and z projections follow each other
+    pub fn uv_offset(&self) -> Option<usize> {
+        self.offset_if(
+            self.triplanar,
+            3 + self.normal_words()
+                + self.color as usize
+                + self.material as usize
+                + self.secondary_position_words(),
+        )
+    }
+
+    /// Byte offset of the triplanar tangents within a vertex, if present. Those of the x, y and z projections follow each other
+    pub fn tangent_offset(&self) -> Option<usize> {
+        self.uv_offset().map(|offset| offset + 24)
+    }
+
+    /// Byte offset of the triplanar blend weights within a vertex, if present
+    pub fn weights_offset(&self) -> Option<usize> {
+        self.tangent_offset().map(|offset| offset + 48)
+    }
+
+    fn offset_if(&self, present: bool, words: usize) -> Option<usize> {
+        if present {
+            Some(4 * words)
+        } else {
+            None
+        }
+    }
+
+    fn normal_words(&self) -> usize {
+        if self.normal {
+            3
+        } else {
+            0
+        }
+    }
+
+    fn secondary_position_words(&self) -> usize {
+        if self.secondary_position {
+            10
+        } else {
+            0
+        }
+    }
+
+    fn words(&self) -> usize {
+        3 + self.normal_words()
+            + self.color as usize
+            + self.material as usize
+            + self.secondary_position_words()
+            + if self.triplanar { 21 } else { 0 }
+    }
+}
+
+/// Vertex matching the default [VertexLayout]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GpuVertex {
+    /// Position
+    pub position: [f32; 3],
+    /// Normal
+    pub normal: [f32; 3],
+}
+
+/// Vertex matching a [VertexLayout] with normals and colors
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GpuColorVertex {
+    /// Position
+    pub position: [f32; 3],
+    /// Normal
+    pub normal: [f32; 3],
+    /// RGBA color
+    pub color: [u8; 4],
+}
+
+/// Vertex matching a [VertexLayout] with normals and secondary positions
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GpuSecondaryVertex {
+    /// Position
+    pub position: [f32; 3],
+    /// Normal
+    pub normal: [f32; 3],
+    /// Positions to use when both grid points of the vertex are moved, when only the first one is, and when only the second one is
+    pub secondary_positions: [[f32; 3]; 3],
+    /// Near faces of the first grid point in the low byte, and of the second one in the next byte.
+    /// Bit `i` of a byte is set for `TransitionSide` number `i`
+    pub near_faces: u32,
+}
+
+/// Vertex matching a [VertexLayout] with normals and triplanar attributes
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GpuTriplanarVertex {
+    /// Position
+    pub position: [f32; 3],
+    /// Normal
+    pub normal: [f32; 3],
+    /// UVs of the projections along x, y and z
+    pub uvs: [[f32; 2]; 3],
+    /// Tangents (x,y,z, and handedness in w) of the projections along x, y and z
+    pub tangents: [[f32; 4]; 3],
+    /// Blend weights of the projections along x, y and z
+    pub weights: [f32; 3],
+}
+
+// Safety: these are repr(C), only made of 4-bytes aligned fields of 4-bytes multiple sizes (so there is no padding),
+// and any bit pattern is valid for them
+unsafe impl Zeroable for GpuVertex {}
+unsafe impl Pod for GpuVertex {}
+unsafe impl Zeroable for GpuColorVertex {}
+unsafe impl Pod for GpuColorVertex {}
+unsafe impl Zeroable for GpuSecondaryVertex {}
+unsafe impl Pod for GpuSecondaryVertex {}
+unsafe impl Zeroable for GpuTriplanarVertex {}
+unsafe impl Pod for GpuTriplanarVertex {}
+
+/// Index buffer, with the smallest index type fitting the vertex count
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexBuffer {
+    /// For meshes with at most 65536 vertices
+    U16(Vec<u16>),
+    /// For bigger meshes
+    U32(Vec<u32>),
+}
+
+impl IndexBuffer {
+    /// Number of indices (3 per triangle)
+    pub fn len(&self) -> usize {
+        match self {
+            IndexBuffer::U16(indices) => indices.len(),
+            IndexBuffer::U32(indices) => indices.len(),
+        }
+    }
+
+    /// Whether there are no indices
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The indices, as raw bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            IndexBuffer::U16(indices) => bytemuck::cast_slice(indices),
+            IndexBuffer::U32(indices) => bytemuck::cast_slice(indices),
+        }
+    }
+}
+
+/// Mesh built by [GpuMeshBuilder]
+#[derive(Debug, Clone)]
+pub struct GpuMesh {
+    /// Layout of each vertex
+    pub layout: VertexLayout,
+    /// Number of vertices
+    pub vertex_count: usize,
+    /// Triangle indices
+    pub indices: IndexBuffer,
+    // Stored as words, for alignment
+    vertex_data: Vec<u32>,
+}
+
+impl GpuMesh {
+    /// The interleaved vertex buffer, as raw bytes
+    pub fn vertex_bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.vertex_data)
+    }
+
+    /// The interleaved vertex buffer, as a slice of vertex structs. Fails if `T` does not have the size of the layout
+    pub fn vertices<T: Pod>(&self) -> Result<&[T], PodCastError> {
+        if size_of::<T>() != self.layout.stride() {
+            return Err(PodCastError::SizeMismatch);
+        }
+        bytemuck::try_cast_slice(&self.vertex_data)
+    }
+}
+
+/// Computes a vertex attribute from the voxel data of the two grid points, and where the vertex is between them (0 at the first one, 1 at the second one)
+pub type VoxelAttribute<V, T> = fn(&V, &V, f32) -> T;
+
+/// A MeshBuilder that builds [GpuMesh]
+pub struct GpuMeshBuilder<V> {
+    layout: VertexLayout,
+    color: Option<VoxelAttribute<V, [u8; 4]>>,
+    material: Option<VoxelAttribute<V, u32>>,
+    triplanar: Option<Triplanar<f32>>,
+    vertex_placement: VertexPlacement,
+    vertex_data: Vec<u32>,
+    indices: Vec<u32>,
+    vertices: usize,
+}
+
+#[allow(clippy::new_without_default)]
+impl<V> GpuMeshBuilder<V> {
+    /// Create a fresh builder, for positions and normals only
+    pub fn new() -> Self {
+        Self {
+            layout: VertexLayout::default(),
+            color: None,
+            material: None,
+            triplanar: None,
+            vertex_placement: VertexPlacement::Interpolated,
+            vertex_data: vec![],
+            indices: vec![],
+            vertices: 0,
+        }
+    }
+
+    /// Set whether to output normals. Without them, density gradients are not even computed
+    pub fn with_normals(mut self, normals: bool) -> Self {
+        self.layout.normal = normals;
+        self
+    }
+
+    /// Output a color for each vertex, computed by `color`
+    pub fn with_color(mut self, color: VoxelAttribute<V, [u8; 4]>) -> Self {
+        self.layout.color = true;
+        self.color = Some(color);
+        self
+    }
+
+    /// Output a material id for each vertex, computed by `material`
+    pub fn with_material(mut self, material: VoxelAttribute<V, u32>) -> Self {
+        self.layout.material = true;
+        self.material = Some(material);
+        self
+    }
+
+    /// Output the secondary positions and near faces masks of each vertex
+    pub fn with_secondary_positions(mut self) -> Self {
+        self.layout.secondary_position = true;
+        self
+    }
+
+    /// Output the UVs, tangents and blend weights of the projections of `triplanar` for each vertex. Normals are computed for them even if not output
+    pub fn with_triplanar(mut self, triplanar: Triplanar<f32>) -> Self {
+        self.layout.triplanar = true;
+        self.triplanar = Some(triplanar);
+        self
+    }
+
+    /// Choose how vertices are placed on cell edges
+    pub fn with_vertex_placement(mut self, vertex_placement: VertexPlacement) -> Self {
+        self.vertex_placement = vertex_placement;
+        self
+    }
+
+    /// Layout of the vertices this builder outputs
+    pub fn layout(&self) -> VertexLayout {
+        self.layout
+    }
+
+    /// Build the mesh
+    pub fn build(self) -> GpuMesh {
+        let indices = if self.vertices <= u16::MAX as usize + 1 {
+            IndexBuffer::U16(self.indices.iter().map(|&i| i as u16).collect())
+        } else {
+            IndexBuffer::U32(self.indices)
+        };
+        GpuMesh {
+            layout: self.layout,
+            vertex_count: self.vertices,
+            indices,
+            vertex_data: self.vertex_data,
+        }
+    }
+
+    fn push_floats(&mut self, values: [f32; 3]) {
+        self.vertex_data
+            .extend(values.iter().map(|value| value.to_bits()));
+    }
+}
+
+fn to_f32<A: NumCast>(a: A) -> f32 {
+    <f32 as NumCast>::from(a).unwrap()
+}
+
+impl<V, C> MeshBuilder<V, C> for GpuMeshBuilder<V>
+where
+    V: VoxelData,
+    C: Coordinate,
+{
+    fn normal_mode(&self) -> NormalMode {
+        if self.layout.normal || self.layout.triplanar {
+            NormalMode::Gradient
+        } else {
+            NormalMode::None
+        }
+    }
+
+    fn vertex_placement(&self) -> VertexPlacement {
+        self.vertex_placement
+    }
+
+    fn add_vertex_between(
+        &mut self,
+        point_a: GridPoint<V, C>,
+        point_b: GridPoint<V, C>,
+        interp_toward_b: DensityFloat<V>,
+    ) -> VertexIndex {
+        let interp_c: C = NumCast::from(interp_toward_b).unwrap();
+        let position = point_a.position.interp_toward(&point_b.position, interp_c);
+        let position = [to_f32(position.x), to_f32(position.y), to_f32(position.z)];
+        self.push_floats(position);
+        let mut normal = [0.0; 3];
+        if self.layout.normal || self.layout.triplanar {
+            let gradient_x =
+                point_a.gradient.0 + interp_toward_b * (point_b.gradient.0 - point_a.gradient.0);
+            let gradient_y =
+                point_a.gradient.1 + interp_toward_b * (point_b.gradient.1 - point_a.gradient.1);
+            let gradient_z =
+                point_a.gradient.2 + interp_toward_b * (point_b.gradient.2 - point_a.gradient.2);
+            normal =
+                V::Density::gradients_to_normal(gradient_x, gradient_y, gradient_z).map(to_f32);
+        }
+        if self.layout.normal {
+            self.push_floats(normal);
+        }
+        let interp = to_f32(interp_toward_b);
+        if let Some(color) = self.color {
+            let color = color(&point_a.voxel_data, &point_b.voxel_data, interp);
+            self.vertex_data.push(u32::from_ne_bytes(color));
+        }
+        if let Some(material) = self.material {
+            let material = material(&point_a.voxel_data, &point_b.voxel_data, interp);
+            self.vertex_data.push(material);
+        }
+        if self.layout.secondary_position {
+            for secondary_position in point_a.secondary_positions_with(&point_b, interp_c) {
+                self.push_floats([
+                    to_f32(secondary_position.x),
+                    to_f32(secondary_position.y),
+                    to_f32(secondary_position.z),
+                ]);
+            }
+            self.vertex_data
+                .push(point_a.near_faces.bits() as u32 | (point_b.near_faces.bits() as u32) << 8);
+        }
+        if let Some(triplanar) = &self.triplanar {
+            let projected = triplanar.project(position, normal);
+            self.vertex_data
+                .extend(projected.uvs.iter().flatten().map(|value| value.to_bits()));
+            self.vertex_data.extend(
+                projected
+                    .tangents
+                    .iter()
+                    .flatten()
+                    .map(|value| value.to_bits()),
+            );
+            self.vertex_data
+                .extend(projected.weights.iter().map(|value| value.to_bits()));
+        }
+        let index = self.vertices;
+        self.vertices += 1;
+        VertexIndex(index)
+    }
+
+    fn add_triangle(
+        &mut self,
+        vertex_1_index: VertexIndex,
+        vertex_2_index: VertexIndex,
+        vertex_3_index: VertexIndex,
+    ) {
+        self.indices.push(vertex_1_index.0 as u32);
+        self.indices.push(vertex_2_index.0 as u32);
+        self.indices.push(vertex_3_index.0 as u32);
+    }
+}
@@ -32,8 +32,11 @@ Known room for improvements:
  - a lot of things are probably copied, that should not
  */
 
-use super::density_caching::PreCachingVoxelSource;
+use num::Zero;
 
+use super::density_caching::{PreCachingVoxelSource, VoxelCaches};
+
+use super::super::extraction::{is_block_empty, SeparatedMeshes};
 use super::super::mesh_builder::*;
 use super::super::traits::*;
 use super::super::transition_sides::*;
@@ -48,6 +51,31 @@ A point on the grid, for output purposes.
 This is not necessarily at the same place as a Voxel sample, because grid points can be shifted ("shrink")
 */
 
+// Storage used by an Extractor, that can be kept to avoid reallocating it for the next extraction
+pub struct ExtractionBuffers<V> {
+    vertex_indices: SharedVertexIndices,
+    voxels: VoxelCaches<V>,
+}
+
+impl<V> Default for ExtractionBuffers<V> {
+    fn default() -> Self {
+        Self {
+            vertex_indices: SharedVertexIndices::new(0),
+            voxels: VoxelCaches::default(),
+        }
+    }
+}
+
+impl<V> ExtractionBuffers<V> {
+    // Allocated for blocks of up to `block_subdivisions`
+    pub fn with_capacity(block_subdivisions: usize) -> Self {
+        Self {
+            vertex_indices: SharedVertexIndices::new(block_subdivisions),
+            voxels: VoxelCaches::with_capacity(block_subdivisions),
+        }
+    }
+}
+
 pub struct Extractor<'b, C, V, S, M>
 where
     C: Coordinate,
@@ -64,6 +92,10 @@ where
     //vertices_normals: Vec<C>,
     //tri_indices: Vec<usize>,
     mesh_builder: M,
+    compute_gradients: bool,
+    compute_neighbours: bool,
+    vertex_placement: VertexPlacement,
+    empty: bool,
     shared_storage: SharedVertexIndices,
     current_rotation: &'static Rotation,
 }
@@ -82,22 +114,92 @@ where
         transition_sides: TransitionSides,
         mesh_builder: M,
     ) -> Self {
+        Self::with_buffers(
+            density_source,
+            block,
+            threshold,
+            transition_sides,
+            mesh_builder,
+            ExtractionBuffers::default(),
+        )
+    }
+
+    // Same as `new`, but reusing the storage from a previous extraction
+    pub fn with_buffers(
+        density_source: S,
+        block: &'b Block<C>,
+        threshold: V::Density,
+        transition_sides: TransitionSides,
+        mesh_builder: M,
+        buffers: ExtractionBuffers<V>,
+    ) -> Self {
+        let empty = is_block_empty(&density_source, threshold);
+        let mut density_source =
+            PreCachingVoxelSource::with_caches(density_source, block.subdivisions, buffers.voxels);
+        if !empty {
+            density_source.load_regular_block_voxels();
+        }
         Extractor::<'b, C, V, S, M> {
-            density_source: PreCachingVoxelSource::new(density_source, block.subdivisions),
+            density_source,
             block,
             threshold,
             transition_sides,
             //vertices: 0,
+            compute_gradients: mesh_builder.normal_mode().needs_gradients(),
+            compute_neighbours: mesh_builder.needs_neighbours(),
+            vertex_placement: mesh_builder.vertex_placement(),
+            empty,
             mesh_builder,
-            shared_storage: SharedVertexIndices::new(block.subdivisions),
+            shared_storage: buffers.vertex_indices.resized(block.subdivisions),
             current_rotation: Rotation::default(),
         }
     }
 
-    pub fn extract(mut self) -> M {
-        self.extract_regular_cells();
-        self.extract_transition_cells();
-        self.mesh_builder
+    pub fn extract(self) -> M {
+        self.extract_keeping_buffers().0
+    }
+
+    // Also gives back the storage, for a later `with_buffers`
+    pub fn extract_keeping_buffers(mut self) -> (M, ExtractionBuffers<V>) {
+        if !self.empty {
+            self.extract_regular_cells();
+            self.extract_transition_cells();
+        }
+        let buffers = ExtractionBuffers {
+            vertex_indices: self.shared_storage,
+            voxels: self.density_source.into_caches(),
+        };
+        (self.mesh_builder, buffers)
+    }
+
+    // The extractor must have been created without transition sides, so that no grid point is shrunk.
+    // Each side in `sides` gets its transition cells output to a new builder
+    pub fn extract_separated<FM>(
+        mut self,
+        sides: TransitionSides,
+        mut new_builder: FM,
+    ) -> SeparatedMeshes<M>
+    where
+        FM: FnMut() -> M,
+    {
+        debug_assert!(self.transition_sides.is_empty());
+        if !self.empty {
+            self.extract_regular_cells();
+            self.density_source.load_transition_voxels(sides);
+        }
+        let mut transitions: [Option<M>; 6] = Default::default();
+        for side in sides {
+            let regular_builder = std::mem::replace(&mut self.mesh_builder, new_builder());
+            if !self.empty {
+                self.extract_transition_side(side);
+            }
+            transitions[side as usize] =
+                Some(std::mem::replace(&mut self.mesh_builder, regular_builder));
+        }
+        SeparatedMeshes {
+            regular: self.mesh_builder,
+            transitions,
+        }
     }
 
     fn extract_regular_cells(&mut self) {
@@ -118,12 +220,6 @@ where
     fn extract_regular_cell(&mut self, cell_index: RegularCellIndex) {
         let case_number = self.regular_cell_case(&cell_index);
         let cell_class: u8 = transvoxel_data::regular_cell_data::REGULAR_CELL_CLASS[case_number];
-        if cell_class != 0 {
-            // To optimize, we could also check if the cell is on a border of the block, here
-            // we only need voxels out of the block when such a cell generates vertices, because
-            // these are for vertex normals
-            self.density_source.load_regular_extended_voxels();
-        }
         let triangulation_info =
             transvoxel_data::regular_cell_data::REGULAR_CELL_DATA[cell_class as usize];
         let vertices_data = transvoxel_data::regular_cell_data::REGULAR_VERTEX_DATA[case_number];
@@ -150,12 +246,16 @@ where
         self.density_source
             .load_transition_voxels(self.transition_sides);
         for side in self.transition_sides {
-            self.current_rotation = Rotation::for_side(side);
-            for cell_u in 0..self.block.subdivisions {
-                for cell_v in 0..self.block.subdivisions {
-                    let cell_index = TransitionCellIndex::from(side, cell_u, cell_v);
-                    self.extract_transition_cell(&cell_index);
-                }
+            self.extract_transition_side(side);
+        }
+    }
+
+    fn extract_transition_side(&mut self, side: TransitionSide) {
+        self.current_rotation = Rotation::for_side(side);
+        for cell_u in 0..self.block.subdivisions {
+            for cell_v in 0..self.block.subdivisions {
+                let cell_index = TransitionCellIndex::from(side, cell_u, cell_v);
+                self.extract_transition_cell(&cell_index);
             }
         }
     }
@@ -345,31 +445,51 @@ where
     }
 
     fn regular_grid_point(&mut self, voxel_index: RegularVoxelIndex) -> GridPoint<V, C> {
-        let position = self.regular_grid_point_position(&voxel_index);
+        let position = self.regular_grid_point_position(&voxel_index, &self.transition_sides);
+        let unshrunk_position = self.regular_grid_point_position(&voxel_index, &no_side());
+        let secondary_position = self.regular_grid_point_position(&voxel_index, &all_sides());
+        let near_faces = grid_point_faces(&voxel_index, self.block.subdivisions);
         let gradient = self.regular_voxel_gradient(&voxel_index);
+        let neighbours = self.regular_voxel_neighbours(&voxel_index);
         let voxel_data = self.regular_voxel_data(&voxel_index);
         GridPoint {
             position,
+            unshrunk_position,
+            secondary_position,
+            near_faces,
             gradient,
+            neighbours,
             voxel_data,
         }
     }
 
-    fn regular_grid_point_position(&self, voxel_index: &RegularVoxelIndex) -> Position<C> {
+    fn regular_grid_point_position(
+        &self,
+        voxel_index: &RegularVoxelIndex,
+        transition_sides: &TransitionSides,
+    ) -> Position<C> {
         let mut x = self.block.dims.base[0]
             + self.block.dims.size * C::from_ratio(voxel_index.x, self.block.subdivisions);
         let mut y = self.block.dims.base[1]
             + self.block.dims.size * C::from_ratio(voxel_index.y, self.block.subdivisions);
         let mut z = self.block.dims.base[2]
             + self.block.dims.size * C::from_ratio(voxel_index.z, self.block.subdivisions);
-        self.shrink_if_needed(&mut x, &mut y, &mut z, voxel_index);
+        self.shrink_if_needed(&mut x, &mut y, &mut z, voxel_index, transition_sides);
         Position { x, y, z }
     }
 
     fn regular_voxel_gradient(
         &mut self,
         voxel_index: &RegularVoxelIndex,
-    ) -> (V::Density, V::Density, V::Density) {
+    ) -> (DensityFloat<V>, DensityFloat<V>, DensityFloat<V>) {
+        if !self.compute_gradients {
+            return (Zero::zero(), Zero::zero(), Zero::zero());
+        }
+        if let Some([x, y, z]) = self.density_source.get_regular_gradient(voxel_index) {
+            return (x, y, z);
+        }
+        // Estimated from the neighbouring voxels. For border voxels, these are out of the block
+        self.density_source.load_regular_extended_voxels();
         let xgradient = self
             .regular_voxel_data(&(voxel_index + RegularVoxelDelta { x: 1, y: 0, z: 0 }))
             .density()
@@ -394,6 +514,23 @@ where
         (xgradient, ygradient, zgradient)
     }
 
+    fn regular_voxel_neighbours(&mut self, voxel_index: &RegularVoxelIndex) -> [V; 6] {
+        if !self.compute_neighbours {
+            return [V::default(); 6];
+        }
+        // Same as for gradients: for border voxels, some are out of the block
+        self.density_source.load_regular_extended_voxels();
+        let delta = |x, y, z| RegularVoxelDelta { x, y, z };
+        [
+            self.regular_voxel_data(&(voxel_index + delta(-1, 0, 0))),
+            self.regular_voxel_data(&(voxel_index + delta(1, 0, 0))),
+            self.regular_voxel_data(&(voxel_index + delta(0, -1, 0))),
+            self.regular_voxel_data(&(voxel_index + delta(0, 1, 0))),
+            self.regular_voxel_data(&(voxel_index + delta(0, 0, -1))),
+            self.regular_voxel_data(&(voxel_index + delta(0, 0, 1))),
+        ]
+    }
+
     fn transition_grid_point(
         &mut self,
         cell_index: &TransitionCellIndex,
@@ -429,10 +566,15 @@ where
         let voxel_index = cell_index + &delta;
         let position = self.high_res_face_grid_point_position(cell_index, delta);
         let gradient = self.high_res_face_grid_point_gradient(&voxel_index);
+        let neighbours = self.high_res_face_grid_point_neighbours(&voxel_index);
         let voxel_data = self.high_res_face_grid_point_data(&voxel_index);
         GridPoint {
             position,
+            unshrunk_position: position,
+            secondary_position: position,
+            near_faces: no_side(),
             gradient,
+            neighbours,
             voxel_data,
         }
     }
@@ -451,10 +593,12 @@ where
     fn high_res_face_grid_point_gradient(
         &mut self,
         base_voxel_index: &HighResolutionVoxelIndex,
-    ) -> (V::Density, V::Density, V::Density) {
+    ) -> (DensityFloat<V>, DensityFloat<V>, DensityFloat<V>) {
         // This might not be correct, and we might want to only use high-res steps for the gradients,
         // even for voxels at the corners of the face (to better match normals with the neighbouring block)
-        if base_voxel_index.on_regular_grid() {
+        if !self.compute_gradients {
+            (Zero::zero(), Zero::zero(), Zero::zero())
+        } else if base_voxel_index.on_regular_grid() {
             let regular_index =
                 base_voxel_index.as_regular_index(self.current_rotation, self.block.subdivisions);
             self.regular_voxel_gradient(&regular_index)
@@ -466,7 +610,13 @@ where
     fn high_res_face_grid_point_gradient_non_regular(
         &mut self,
         base_voxel_index: &HighResolutionVoxelIndex,
-    ) -> (V::Density, V::Density, V::Density) {
+    ) -> (DensityFloat<V>, DensityFloat<V>, DensityFloat<V>) {
+        if let Some([x, y, z]) = self
+            .density_source
+            .get_transition_gradient(base_voxel_index)
+        {
+            return (x, y, z);
+        }
         let rot = self.current_rotation;
         let x_gradient = self
             .transition_grid_point_data(&(base_voxel_index + &rot.plus_x_as_uvw))
@@ -492,6 +642,30 @@ where
         (x_gradient, y_gradient, z_gradient)
     }
 
+    // Taken at the same places as the gradient estimation
+    fn high_res_face_grid_point_neighbours(
+        &mut self,
+        voxel_index: &HighResolutionVoxelIndex,
+    ) -> [V; 6] {
+        if !self.compute_neighbours {
+            [V::default(); 6]
+        } else if voxel_index.on_regular_grid() {
+            let regular_index =
+                voxel_index.as_regular_index(self.current_rotation, self.block.subdivisions);
+            self.regular_voxel_neighbours(&regular_index)
+        } else {
+            let rot = self.current_rotation;
+            [
+                self.transition_grid_point_data(&(voxel_index - &rot.plus_x_as_uvw)),
+                self.transition_grid_point_data(&(voxel_index + &rot.plus_x_as_uvw)),
+                self.transition_grid_point_data(&(voxel_index - &rot.plus_y_as_uvw)),
+                self.transition_grid_point_data(&(voxel_index + &rot.plus_y_as_uvw)),
+                self.transition_grid_point_data(&(voxel_index - &rot.plus_z_as_uvw)),
+                self.transition_grid_point_data(&(voxel_index + &rot.plus_z_as_uvw)),
+            ]
+        }
+    }
+
     fn high_res_face_grid_point_data(&mut self, voxel_index: &HighResolutionVoxelIndex) -> V {
         self.transition_grid_point_data(voxel_index)
     }
@@ -501,11 +675,11 @@ where
         point_a: GridPoint<V, C>,
         point_b: GridPoint<V, C>,
     ) -> VertexIndex {
-        let interp_toward_b = V::Density::interp(
+        let interp_toward_b = self.vertex_placement.place(V::Density::interp(
             point_a.voxel_data.density(),
             point_b.voxel_data.density(),
             self.threshold,
-        );
+        ));
         self.mesh_builder
             .add_vertex_between(point_a, point_b, interp_toward_b)
     }
@@ -516,6 +690,7 @@ where
         grid_point_y: &mut C,
         grid_point_z: &mut C,
         voxel_index: &RegularVoxelIndex,
+        transition_sides: &TransitionSides,
     ) {
         let cell_size = self.block.dims.size * C::from_ratio(1, self.block.subdivisions);
         shrink_if_needed::<C>(
@@ -527,11 +702,33 @@ where
             voxel_index.z,
             cell_size,
             self.block.subdivisions,
-            &self.transition_sides,
+            transition_sides,
         )
     }
 }
 
+// The faces of the block on which a regular grid point lies
+fn grid_point_faces(voxel_index: &RegularVoxelIndex, subdivisions: usize) -> TransitionSides {
+    let mut faces = no_side();
+    let last = subdivisions as isize;
+    if voxel_index.x == 0 {
+        faces |= TransitionSide::LowX;
+    } else if voxel_index.x == last {
+        faces |= TransitionSide::HighX;
+    }
+    if voxel_index.y == 0 {
+        faces |= TransitionSide::LowY;
+    } else if voxel_index.y == last {
+        faces |= TransitionSide::HighY;
+    }
+    if voxel_index.z == 0 {
+        faces |= TransitionSide::LowZ;
+    } else if voxel_index.z == last {
+        faces |= TransitionSide::HighZ;
+    }
+    faces
+}
+
 // 0 to 3
 /**
  Reuse index : ![Image](reuse_index.png)
@@ -551,10 +748,20 @@ struct SharedVertexIndices {
 impl SharedVertexIndices {
     pub fn new(block_size: usize) -> Self {
         SharedVertexIndices {
-            regular: vec![VertexIndex(0); 4 * block_size * block_size * block_size], // 4 reusable vertex positions for each cell
-            transition: vec![VertexIndex(0); 10 * 6 * block_size * block_size], // 10 reusable vertex positions potentially on each of the cell on each of the block sides
-            block_size,
+            regular: vec![],
+            transition: vec![],
+            block_size: 0,
         }
+        .resized(block_size)
+    }
+    // Vertices are always stored before being reused within one extraction, so stale values from a previous block never get read
+    pub fn resized(mut self, block_size: usize) -> Self {
+        self.regular
+            .resize(4 * block_size * block_size * block_size, VertexIndex(0)); // 4 reusable vertex positions for each cell
+        self.transition
+            .resize(10 * 6 * block_size * block_size, VertexIndex(0)); // 10 reusable vertex positions potentially on each of the cell on each of the block sides
+        self.block_size = block_size;
+        self
     }
     pub fn get_regular(
         &self,
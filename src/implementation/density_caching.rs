@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::traits::VoxelData;
+use crate::traits::{DensityFloat, VoxelData};
 
 use super::super::{
     transition_sides::TransitionSides,
@@ -8,6 +8,37 @@ use super::super::{
     voxel_source::VoxelSource,
 };
 
+// The storage of a PreCachingVoxelSource, that can be kept to be reused by the next one
+pub struct VoxelCaches<V> {
+    regular_cache: Vec<V>,
+    regular_cache_extended: Vec<V>,
+    transition_cache: Vec<V>,
+    transition_cache_slices: HashMap<usize, usize>, // side -> slice in the cache
+}
+
+impl<V> Default for VoxelCaches<V> {
+    fn default() -> Self {
+        Self {
+            regular_cache: Vec::new(),
+            regular_cache_extended: Vec::new(),
+            transition_cache: Vec::new(),
+            transition_cache_slices: HashMap::new(),
+        }
+    }
+}
+
+impl<V> VoxelCaches<V> {
+    pub fn with_capacity(block_subdivisions: usize) -> Self {
+        let subs = block_subdivisions;
+        Self {
+            regular_cache: Vec::with_capacity((subs + 1) * (subs + 1) * (subs + 1)),
+            regular_cache_extended: Vec::with_capacity(6 * (subs + 1) * (subs + 1)),
+            transition_cache: Vec::with_capacity(6 * (2 * subs + 1) * (2 * subs + 1)),
+            transition_cache_slices: HashMap::with_capacity(6),
+        }
+    }
+}
+
 pub struct PreCachingVoxelSource<V, S> {
     inner_source: S,
     block_subdivisions: usize,
@@ -24,22 +55,32 @@ where
     V: VoxelData,
     S: VoxelSource<V>,
 {
-    pub fn new(source: S, block_subdivisions: usize) -> Self {
-        let mut object = Self {
+    // Caches are entirely overwritten before being read, so they can come from any previous extraction
+    pub fn with_caches(source: S, block_subdivisions: usize, caches: VoxelCaches<V>) -> Self {
+        let mut transition_cache_slices = caches.transition_cache_slices;
+        transition_cache_slices.clear();
+        Self {
             inner_source: source,
             block_subdivisions,
-            regular_cache: Vec::new(),
-            regular_cache_extended: Vec::new(),
+            regular_cache: caches.regular_cache,
+            regular_cache_extended: caches.regular_cache_extended,
             regular_cache_extended_loaded: false,
-            transition_cache: Vec::new(),
+            transition_cache: caches.transition_cache,
             transition_cache_loaded: false,
-            transition_cache_slices: HashMap::new(),
-        };
-        object.load_regular_block_voxels();
-        object
+            transition_cache_slices,
+        }
     }
 
-    fn load_regular_block_voxels(&mut self) {
+    pub fn into_caches(self) -> VoxelCaches<V> {
+        VoxelCaches {
+            regular_cache: self.regular_cache,
+            regular_cache_extended: self.regular_cache_extended,
+            transition_cache: self.transition_cache,
+            transition_cache_slices: self.transition_cache_slices,
+        }
+    }
+
+    pub fn load_regular_block_voxels(&mut self) {
         let subs = self.block_subdivisions;
         self.regular_cache
             .resize((subs + 1) * (subs + 1) * (subs + 1), V::default());
@@ -254,6 +295,21 @@ where
         }
     }
 
+    // Gradients are not cached: they are only queried for grid points around vertices
+    pub fn get_regular_gradient(
+        &mut self,
+        voxel_index: &RegularVoxelIndex,
+    ) -> Option<[DensityFloat<V>; 3]> {
+        self.inner_source.get_regular_voxel_gradient(voxel_index)
+    }
+
+    pub fn get_transition_gradient(
+        &mut self,
+        index: &HighResolutionVoxelIndex,
+    ) -> Option<[DensityFloat<V>; 3]> {
+        self.inner_source.get_transition_voxel_gradient(index)
+    }
+
     pub fn get_transition_data(&mut self, index: &HighResolutionVoxelIndex) -> V {
         let c = index.cell;
         let d = index.delta;
@@ -21,7 +21,20 @@ To implement a fully consistent dynamic level-of-detail system, you will also pr
    * two rendered adjacent blocks can only either have the same resolution, or one have double the resolution of the other
    * in that second case, the low resolution block must also be rendered with a transition face in the direction of the high resolution block
 
-Currently, it is not possible to "flip" a transition face status on a block, without re-extracting a new mesh for the block. Which means changing the resolution for one block can cascade through constraints to re-generating a few other blocks as well
+The [lod] module provides an octree that can take care of these decisions for you, given a camera position.
+The [chunked_world] module can store your voxel data per octree node, and serve each block the voxels it needs from its neighbours and from the finer level.
+
+With the regular extraction functions, it is not possible to "flip" a transition face status on a block, without re-extracting a new mesh for the block. Which means changing the resolution for one block can cascade through constraints to re-generating a few other blocks as well.
+To avoid this, [extract_separated] outputs the transition cells of each side separately from the regular cells, along with secondary vertex positions, so that transition faces can be toggled at draw time.
+
+# New in version 2.0.0
+ * many new modules: level of detail ([lod]), voxel storage ([chunked_world], [array_source], [pyramid]), shapes ([sdf]), meshes post-processing and export, and more. See the modules list
+ * Bevy mesh builders are back, in the `bevy_support` module with the `bevy` feature (see below), along with a plugin managing chunk entities
+ * breaking: [Density] no longer requires [Float]. Its `EPSILON`, `HALF` and `ZERO` constants are replaced by an associated `Float` type, used for interpolation factors and gradients, with `to_float` and `from_float` conversions. Integer densities are supported. For a custom density, implement these instead of the constants (`type Float = f32` and two conversions, for a density wrapping a `f32`)
+ * breaking: [MeshBuilder::add_vertex_between] gets `interp_toward_b` as a [DensityFloat] instead of a density, and the `gradient` of [GridPoint] is made of [DensityFloat]s as well. For float densities, this is the same type as before
+ * breaking: [GridPoint] has new fields (`unshrunk_position`, `secondary_position`, `near_faces` and `neighbours`), so building one yourself needs them too
+ * breaking: [Mesh] has new public fields for optional attributes (`secondary_positions`, `near_face_mask`, `voxel_data`, `colors` and `attributes`), and a type parameter for the recorded voxel data. Code creating a `Mesh` directly must set them (to `None`, or no attributes)
+ * breaking: [GenericMeshBuilder] is generic over the voxel data and coordinates, instead of only building from `f32`. Type annotations may be needed where they were inferred before, for example `GenericMeshBuilder::<f32>::new()`
 
 # New in version 1.0.0
  * complete rework of the interfaces. Notably: you can now implement a [MeshBuilder] yourself
@@ -138,16 +151,39 @@ assert!(sides.contains(TransitionSide::LowX));
 assert!(!sides.contains(TransitionSide::HighX));
 ```
 
+# Optional features
+ * `serde` (default): Serialize/Deserialize implementations for blocks and meshes
+ * `rayon`: the `parallel` module, to extract many blocks in parallel
+ * `ndarray`: the `ndarray_source` module, to extract directly from `ndarray` arrays
+ * `bevy`: the `bevy_support` module, with Bevy mesh builders and a plugin managing chunk entities
+ * `bytemuck`: the `gpu_mesh` module, to build interleaved vertex buffers ready for GPU upload
+
+# Examples
+The `single_block` and `transition_across_blocks` examples display meshes with Bevy, so they need the `bevy` feature:
+```sh
+cargo run --example single_block --features bevy
+cargo run --example transition_across_blocks --features bevy
+```
+
 # Limitations / possible improvements
- * Provide a way to extract without normals, or with face normals, which would be much faster
- * Output/Input positions/normals are only f32. It should be feasible easily to extend that to f64
  * Voxel densities caching is sub-optimal: probably only in the case of an empty block will densities be queried only once per voxel. In non-empty blocks, densities are very likely to be queried several times for some voxels
  * Algorithm improvements. See [Algorithm]
 
 [Algorithm]: crate::implementation::algorithm
-[Density]: crate::density::Density
+[Density]: crate::traits::Density
 [Float]: num::Float
 [MeshBuilder]: crate::mesh_builder::MeshBuilder
+[MeshBuilder::add_vertex_between]: crate::mesh_builder::MeshBuilder::add_vertex_between
+[GridPoint]: crate::mesh_builder::GridPoint
+[DensityFloat]: crate::traits::DensityFloat
+[Mesh]: crate::generic_mesh::Mesh
+[GenericMeshBuilder]: crate::generic_mesh::GenericMeshBuilder
+[lod]: crate::lod
+[array_source]: crate::array_source
+[pyramid]: crate::pyramid
+[sdf]: crate::sdf
+[chunked_world]: crate::chunked_world
+[extract_separated]: crate::extraction::extract_separated
 
 */
 #![warn(missing_docs)]
@@ -156,14 +192,35 @@ assert!(!sides.contains(TransitionSide::HighX));
 #[cfg(test)]
 mod unit_tests;
 
+pub mod ambient_occlusion;
+pub mod array_source;
+#[cfg(feature = "bevy")]
+pub mod bevy_support;
+pub mod chunked_world;
 pub mod extraction;
 pub mod generic_mesh;
+pub mod gltf_export;
+#[cfg(feature = "bytemuck")]
+pub mod gpu_mesh;
+pub mod lod;
 pub mod mesh_builder;
+pub mod mesh_io;
+pub mod multi_material;
+#[cfg(feature = "ndarray")]
+pub mod ndarray_source;
+#[cfg(feature = "rayon")]
+pub mod parallel;
 pub mod prelude;
+pub mod pyramid;
+pub mod sdf;
+pub mod splat;
 pub mod traits;
 pub mod transition_sides;
+pub mod triplanar;
+pub mod validate;
 pub mod voxel_coordinates;
 pub mod voxel_source;
+pub mod weld;
 
 mod implementation;
 pub use implementation::algorithm::shrink_if_needed;
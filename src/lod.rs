@@ -0,0 +1,416 @@
+/*!
+Octree-based level of detail management
+
+This module takes care of the part left to the user by the extraction functions: deciding which
+blocks to render, at which resolution, and which of their sides need to be transition sides.
+
+The world is covered by a grid of root blocks, each being the root of an octree. Every level of
+the octree halves the block size, while keeping the same number of subdivisions, so that a block
+has exactly double the resolution of its parent. Level of detail (`lod`) 0 is the finest level.
+
+```
+# use transvoxel::lod::*;
+let settings = LodSettings {
+    origin: [0.0, 0.0, 0.0],
+    leaf_size: 10.0,
+    subdivisions: 16,
+    lod_distances: vec![20.0, 40.0],
+    roots: [2, 1, 2],
+};
+let mut octree = LodOctree::new(settings);
+let diff = octree.update([5.0, 5.0, 5.0]);
+for lod_block in diff.added.iter() {
+    // Extract a mesh for lod_block.block, using lod_block.transition_sides
+}
+// Later, when the camera moved
+let diff = octree.update([75.0, 5.0, 75.0]);
+// Drop the meshes for diff.removed, extract diff.added and re-extract diff.re_extract
+```
+*/
+
+use std::collections::BTreeMap;
+
+use flagset::Flags;
+
+use crate::traits::Coordinate;
+use crate::transition_sides::{no_side, TransitionSide, TransitionSides};
+use crate::voxel_source::Block;
+
+/// Identifies a node of the octree (a block, whether it is rendered or further subdivided)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NodeKey {
+    /// Level of detail. 0 is the finest level, and each level above doubles the block size
+    pub lod: usize,
+    /// Integer position of the block, counted in blocks of the same level from the world origin
+    pub coords: [i32; 3],
+}
+
+impl NodeKey {
+    /// Shorthand constructor
+    pub fn from(lod: usize, x: i32, y: i32, z: i32) -> Self {
+        Self {
+            lod,
+            coords: [x, y, z],
+        }
+    }
+
+    /// The node one level coarser, containing this one
+    pub fn parent(&self) -> NodeKey {
+        NodeKey {
+            lod: self.lod + 1,
+            coords: [
+                self.coords[0].div_euclid(2),
+                self.coords[1].div_euclid(2),
+                self.coords[2].div_euclid(2),
+            ],
+        }
+    }
+
+    /// The 8 nodes one level finer, contained in this one. Must not be called on a level 0 node
+    pub fn children(&self) -> [NodeKey; 8] {
+        debug_assert!(self.lod > 0);
+        let mut children = [*self; 8];
+        for (i, child) in children.iter_mut().enumerate() {
+            child.lod = self.lod - 1;
+            child.coords = [
+                2 * self.coords[0] + (i & 1) as i32,
+                2 * self.coords[1] + ((i >> 1) & 1) as i32,
+                2 * self.coords[2] + ((i >> 2) & 1) as i32,
+            ];
+        }
+        children
+    }
+
+    /// The node at the same level, sharing the given face with this one
+    pub fn neighbour(&self, side: TransitionSide) -> NodeKey {
+        let (axis, delta) = side_axis_and_direction(side);
+        let mut coords = self.coords;
+        coords[axis] += delta;
+        NodeKey {
+            lod: self.lod,
+            coords,
+        }
+    }
+
+    /// Whether this node touches the given face of its parent
+    fn on_parent_face(&self, side: TransitionSide) -> bool {
+        let (axis, delta) = side_axis_and_direction(side);
+        let high = self.coords[axis].rem_euclid(2) == 1;
+        high == (delta > 0)
+    }
+}
+
+fn side_axis_and_direction(side: TransitionSide) -> (usize, i32) {
+    match side {
+        TransitionSide::LowX => (0, -1),
+        TransitionSide::HighX => (0, 1),
+        TransitionSide::LowY => (1, -1),
+        TransitionSide::HighY => (1, 1),
+        TransitionSide::LowZ => (2, -1),
+        TransitionSide::HighZ => (2, 1),
+    }
+}
+
+fn opposite(side: TransitionSide) -> TransitionSide {
+    match side {
+        TransitionSide::LowX => TransitionSide::HighX,
+        TransitionSide::HighX => TransitionSide::LowX,
+        TransitionSide::LowY => TransitionSide::HighY,
+        TransitionSide::HighY => TransitionSide::LowY,
+        TransitionSide::LowZ => TransitionSide::HighZ,
+        TransitionSide::HighZ => TransitionSide::LowZ,
+    }
+}
+
+/// Parameters of a [LodOctree]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LodSettings<C>
+where
+    C: Coordinate,
+{
+    /// Lowest x,y,z point of the world
+    pub origin: [C; 3],
+    /// Size of the finest (level 0) blocks
+    pub leaf_size: C,
+    /// Subdivisions used for every block, whatever its level
+    pub subdivisions: usize,
+    /// Splitting distances: a block of level `l` is split into 8 blocks of level `l - 1` when the camera is closer than `lod_distances[l - 1]` to it.
+    /// The number of levels in the octree is `lod_distances.len() + 1`, so the root blocks have level `lod_distances.len()`
+    pub lod_distances: Vec<C>,
+    /// How many root blocks make up the world, along each axis
+    pub roots: [usize; 3],
+}
+
+/// A block selected for rendering
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LodBlock<C>
+where
+    C: Coordinate,
+{
+    /// Which octree node it is
+    pub key: NodeKey,
+    /// The world zone and subdivisions to pass to extraction
+    pub block: Block<C>,
+    /// The transition sides to pass to extraction
+    pub transition_sides: TransitionSides,
+}
+
+/// Difference between two successive selections of blocks (see [LodOctree::update])
+#[derive(Clone, Debug)]
+pub struct LodDiff<C>
+where
+    C: Coordinate,
+{
+    /// Blocks that were not rendered before, and need a mesh
+    pub added: Vec<LodBlock<C>>,
+    /// Blocks that should not be rendered anymore
+    pub removed: Vec<NodeKey>,
+    /// Blocks that are still rendered, but with different transition sides, and need a new mesh
+    pub re_extract: Vec<LodBlock<C>>,
+}
+
+impl<C> Default for LodDiff<C>
+where
+    C: Coordinate,
+{
+    fn default() -> Self {
+        Self {
+            added: Vec::new(),
+            removed: Vec::new(),
+            re_extract: Vec::new(),
+        }
+    }
+}
+
+impl<C> LodDiff<C>
+where
+    C: Coordinate,
+{
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.re_extract.is_empty()
+    }
+}
+
+/**
+Selects blocks to render depending on the camera position, and computes their transition sides.
+
+The selection guarantees that two adjacent rendered blocks differ by at most one level, and
+the coarser of the two gets a transition side toward the finer one.
+*/
+pub struct LodOctree<C>
+where
+    C: Coordinate,
+{
+    settings: LodSettings<C>,
+    leaves: BTreeMap<NodeKey, TransitionSides>,
+}
+
+impl<C> LodOctree<C>
+where
+    C: Coordinate,
+{
+    /// Create an octree where only the root blocks are selected. Call [LodOctree::update] to refine it
+    pub fn new(settings: LodSettings<C>) -> Self {
+        let mut octree = Self {
+            settings,
+            leaves: BTreeMap::new(),
+        };
+        let root_lod = octree.root_lod();
+        for x in 0..octree.settings.roots[0] {
+            for y in 0..octree.settings.roots[1] {
+                for z in 0..octree.settings.roots[2] {
+                    let key = NodeKey::from(root_lod, x as i32, y as i32, z as i32);
+                    octree.leaves.insert(key, no_side());
+                }
+            }
+        }
+        octree
+    }
+
+    /// The settings used by this octree
+    pub fn settings(&self) -> &LodSettings<C> {
+        &self.settings
+    }
+
+    /// Level of the root blocks
+    pub fn root_lod(&self) -> usize {
+        self.settings.lod_distances.len()
+    }
+
+    /// Select blocks for a new camera position, and return what changed compared to the previous selection
+    pub fn update(&mut self, camera: [C; 3]) -> LodDiff<C> {
+        let mut leaves = BTreeMap::new();
+        let root_lod = self.root_lod();
+        for x in 0..self.settings.roots[0] {
+            for y in 0..self.settings.roots[1] {
+                for z in 0..self.settings.roots[2] {
+                    let key = NodeKey::from(root_lod, x as i32, y as i32, z as i32);
+                    self.refine(key, &camera, &mut leaves);
+                }
+            }
+        }
+        self.balance(&mut leaves);
+        self.compute_transition_sides(&mut leaves);
+        let mut diff = LodDiff::default();
+        for key in self.leaves.keys() {
+            if !leaves.contains_key(key) {
+                diff.removed.push(*key);
+            }
+        }
+        for (key, sides) in leaves.iter() {
+            match self.leaves.get(key) {
+                None => diff.added.push(self.lod_block(*key, *sides)),
+                Some(previous_sides) if previous_sides != sides => {
+                    diff.re_extract.push(self.lod_block(*key, *sides))
+                }
+                _ => {}
+            }
+        }
+        self.leaves = leaves;
+        diff
+    }
+
+    /// The currently selected blocks
+    pub fn blocks(&self) -> impl Iterator<Item = LodBlock<C>> + '_ {
+        self.leaves
+            .iter()
+            .map(move |(key, sides)| self.lod_block(*key, *sides))
+    }
+
+    /// Transition sides of a currently selected block. None if the block is not selected
+    pub fn transition_sides(&self, key: &NodeKey) -> Option<TransitionSides> {
+        self.leaves.get(key).copied()
+    }
+
+    /// The world zone covered by any node (selected or not)
+    pub fn block(&self, key: &NodeKey) -> Block<C> {
+        let size = self.node_size(key.lod);
+        let base = [
+            self.settings.origin[0] + size * C::from_ratio(key.coords[0] as isize, 1),
+            self.settings.origin[1] + size * C::from_ratio(key.coords[1] as isize, 1),
+            self.settings.origin[2] + size * C::from_ratio(key.coords[2] as isize, 1),
+        ];
+        Block::from(base, size, self.settings.subdivisions)
+    }
+
+    fn node_size(&self, lod: usize) -> C {
+        self.settings.leaf_size * C::from_ratio(1 << lod, 1)
+    }
+
+    fn lod_block(&self, key: NodeKey, transition_sides: TransitionSides) -> LodBlock<C> {
+        LodBlock {
+            key,
+            block: self.block(&key),
+            transition_sides,
+        }
+    }
+
+    fn refine(
+        &self,
+        key: NodeKey,
+        camera: &[C; 3],
+        leaves: &mut BTreeMap<NodeKey, TransitionSides>,
+    ) {
+        if key.lod > 0 && self.distance(&key, camera) < self.settings.lod_distances[key.lod - 1] {
+            for child in key.children().iter() {
+                self.refine(*child, camera, leaves);
+            }
+        } else {
+            leaves.insert(key, no_side());
+        }
+    }
+
+    // Distance from the camera to the closest point of the node
+    fn distance(&self, key: &NodeKey, camera: &[C; 3]) -> C {
+        let block = self.block(key);
+        let mut squared = C::zero();
+        for (low, position) in block.dims.base.iter().zip(camera.iter()) {
+            let high = *low + block.dims.size;
+            let d = if *position < *low {
+                *low - *position
+            } else if *position > high {
+                *position - high
+            } else {
+                C::zero()
+            };
+            squared = squared + d * d;
+        }
+        squared.sqrt()
+    }
+
+    // Split leaves until no two adjacent leaves differ by more than one level
+    fn balance(&self, leaves: &mut BTreeMap<NodeKey, TransitionSides>) {
+        loop {
+            let mut to_split = Vec::new();
+            for key in leaves.keys() {
+                let needs_split = TransitionSide::LIST.iter().any(|side| {
+                    let neighbour = key.neighbour(*side);
+                    self.is_subdivided(&neighbour, leaves)
+                        && neighbour
+                            .children()
+                            .iter()
+                            .filter(|child| child.on_parent_face(opposite(*side)))
+                            .any(|child| self.is_subdivided(child, leaves))
+                });
+                if needs_split {
+                    to_split.push(*key);
+                }
+            }
+            if to_split.is_empty() {
+                return;
+            }
+            for key in to_split {
+                leaves.remove(&key);
+                for child in key.children().iter() {
+                    leaves.insert(*child, no_side());
+                }
+            }
+        }
+    }
+
+    fn compute_transition_sides(&self, leaves: &mut BTreeMap<NodeKey, TransitionSides>) {
+        let keys: Vec<NodeKey> = leaves.keys().copied().collect();
+        for key in keys {
+            let mut sides = no_side();
+            for side in TransitionSide::LIST.iter() {
+                if self.is_subdivided(&key.neighbour(*side), leaves) {
+                    sides |= *side;
+                }
+            }
+            leaves.insert(key, sides);
+        }
+    }
+
+    fn in_world(&self, key: &NodeKey) -> bool {
+        let root_lod = self.root_lod();
+        if key.lod > root_lod {
+            return false;
+        }
+        let per_root = 1i64 << (root_lod - key.lod);
+        (0..3).all(|axis| {
+            let c = key.coords[axis] as i64;
+            c >= 0 && c < per_root * self.settings.roots[axis] as i64
+        })
+    }
+
+    // A node is subdivided if it is neither selected itself, nor contained in a selected node
+    fn is_subdivided(&self, key: &NodeKey, leaves: &BTreeMap<NodeKey, TransitionSides>) -> bool {
+        if key.lod == 0 || !self.in_world(key) {
+            return false;
+        }
+        let root_lod = self.root_lod();
+        let mut node = *key;
+        loop {
+            if leaves.contains_key(&node) {
+                return false;
+            }
+            if node.lod == root_lod {
+                return true;
+            }
+            node = node.parent();
+        }
+    }
+}
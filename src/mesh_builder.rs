@@ -6,11 +6,15 @@ use std::fmt::Debug;
 use std::ops::Add;
 use std::ops::Mul;
 
+use num::Float;
+
 use crate::traits::Coordinate;
+use crate::traits::DensityFloat;
 use crate::traits::VoxelData;
+use crate::transition_sides::TransitionSides;
 
 /// A world space position
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Position<C: Coordinate> {
     /// X
     pub x: C,
@@ -65,20 +69,112 @@ where
 /// when creating vertices
 #[derive(Debug)]
 pub struct GridPoint<V: VoxelData, C: Coordinate> {
-    /// World location of the grid point
+    /// World location of the grid point (shrunk if needed by the transition sides of the extraction)
     pub position: Position<C>,
-    /// Density gradient (estimated) at the grid point
-    pub gradient: (V::Density, V::Density, V::Density),
+    /// World location of the grid point if there was no transition side
+    pub unshrunk_position: Position<C>,
+    /// World location of the grid point if all the faces in `near_faces` were transition sides (Lengyel's "secondary position").
+    /// It is the same as `unshrunk_position` for grid points not lying on a face of the block, or lying on a high resolution face
+    pub secondary_position: Position<C>,
+    /// The faces of the block on which the grid point lies, and away from which it gets moved when they are all transition sides
+    pub near_faces: TransitionSides,
+    /// Density gradient (estimated, unless provided by the voxel source) at the grid point
+    pub gradient: (DensityFloat<V>, DensityFloat<V>, DensityFloat<V>),
+    /// Data at the 6 neighbouring grid points, in the -x, +x, -y, +y, -z and +z directions (the ones used to estimate
+    /// the gradient). Only obtained when the mesh builder asks for them (see [MeshBuilder::needs_neighbours]), default
+    /// values otherwise
+    pub neighbours: [V; 6],
     /// Data at the grid point that was obtained from the field
     pub voxel_data: V,
 }
 
+impl<V: VoxelData, C: Coordinate> GridPoint<V, C> {
+    /// Secondary positions of a vertex created between `self` and `other`, at `factor` toward `other`:
+    /// when both grid points are moved to their `secondary_position`, when only `self` is, and when only `other` is
+    pub fn secondary_positions_with(&self, other: &GridPoint<V, C>, factor: C) -> [Position<C>; 3] {
+        [
+            self.secondary_position
+                .interp_toward(&other.secondary_position, factor),
+            self.secondary_position
+                .interp_toward(&other.unshrunk_position, factor),
+            self.unshrunk_position
+                .interp_toward(&other.secondary_position, factor),
+        ]
+    }
+}
+
 /// An index in the vertex buffer
 #[derive(Default, Clone, Copy)]
 pub struct VertexIndex(pub usize);
 
+/// What kind of normals a [MeshBuilder] produces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NormalMode {
+    /// No normals at all
+    None,
+    /// One normal per triangle, computed from its vertices positions
+    Face,
+    /// One normal per vertex, computed from the density gradients at the grid points
+    #[default]
+    Gradient,
+}
+
+impl NormalMode {
+    /// Whether density gradients need to be computed for grid points
+    pub fn needs_gradients(&self) -> bool {
+        *self == NormalMode::Gradient
+    }
+}
+
+/// How the extraction places vertices along the edges crossing the surface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VertexPlacement {
+    /// Linear interpolation of the densities, at full precision
+    #[default]
+    Interpolated,
+    /// Linear interpolation of the densities, rounded to 1/256 of the edge, like the 8 bit fixed-point
+    /// computation of Lengyel's reference implementation. Vertices very close to a voxel end up exactly on it
+    FixedPoint,
+}
+
+impl VertexPlacement {
+    /// Apply this placement to an interpolation factor
+    pub fn place<F: Float>(&self, interp_toward_b: F) -> F {
+        match self {
+            VertexPlacement::Interpolated => interp_toward_b,
+            VertexPlacement::FixedPoint => {
+                let steps = F::from(256).unwrap();
+                (interp_toward_b * steps).round() / steps
+            }
+        }
+    }
+}
+
 /// Trait you need to implement to build a mesh
 pub trait MeshBuilder<V: VoxelData, C: Coordinate> {
+    /// Called once by the extraction algorithm before extracting, to know which normals will be produced.
+    ///
+    /// Unless this returns [NormalMode::Gradient] (the default), density gradients are not computed, and
+    /// are zero in the [GridPoint]s passed to `add_vertex_between`. This saves a lot of density queries.
+    fn normal_mode(&self) -> NormalMode {
+        NormalMode::Gradient
+    }
+
+    /// Called once by the extraction algorithm before extracting, to know how to place vertices.
+    ///
+    /// The `interp_toward_b` factors passed to `add_vertex_between` follow this placement.
+    fn vertex_placement(&self) -> VertexPlacement {
+        VertexPlacement::Interpolated
+    }
+
+    /// Called once by the extraction algorithm before extracting, to know whether the `neighbours` of grid points are needed.
+    ///
+    /// Unless this returns true (false by default), they are not obtained, and have default values in the [GridPoint]s
+    /// passed to `add_vertex_between`. This saves density queries.
+    fn needs_neighbours(&self) -> bool {
+        false
+    }
+
     /// Called by the extraction algorithm when a new vertex it to be created between 2 grid points.
     ///
     /// Must return the index in the vertex buffer of the created vertex, as this will potentially get reused later.
@@ -87,7 +183,7 @@ pub trait MeshBuilder<V: VoxelData, C: Coordinate> {
         &mut self,
         point_a: GridPoint<V, C>,
         point_b: GridPoint<V, C>,
-        interp_toward_b: V::Density,
+        interp_toward_b: DensityFloat<V>,
     ) -> VertexIndex;
 
     /// Called by the extraction algorithm when a triangle is to be created, using 3 pre-created vertices.
@@ -0,0 +1,741 @@
+/*!
+Writing and reading [Mesh] to and from common 3D file formats: Wavefront OBJ, PLY (ASCII or binary) and binary STL
+
+What is written depends on what the format supports:
+
+| Format | Normals | Colors | Attributes |
+|--------|---------|--------|------------|
+| OBJ    | yes     | yes (r,g,b as extra `v` values, alpha is dropped) | no |
+| PLY    | yes     | yes (as `uchar` red, green, blue, alpha) | yes (as scalar vertex properties) |
+| STL    | one per triangle, computed from the positions | no | no |
+
+Secondary positions, near face masks and voxel data are never written.
+
+The readers are mostly meant to read back files written by this module (for example for tests), but they
+also accept the common variants of these formats written by other tools (polygon faces are triangulated as fans).
+Triangles read from STL files do not share vertices.
+
+```rust
+use transvoxel::prelude::*;
+use transvoxel::generic_mesh::GenericMeshBuilder;
+use transvoxel::mesh_io::{read_ply, write_ply, PlyFormat};
+
+let field = |x: f32, y: f32, z: f32| 5.0 - (x * x + y * y + z * z).sqrt();
+let block = Block::from([0.0, 0.0, 0.0], 10.0, 10);
+let mesh = extract_from_field(&field, &block, 0.0, transition_sides::no_side(), GenericMeshBuilder::new()).build();
+
+let mut file = Vec::new();
+write_ply(&mesh, PlyFormat::BinaryLittleEndian, &mut file).unwrap();
+let read_back = read_ply::<f32>(&file[..]).unwrap();
+assert_eq!(read_back.positions, mesh.positions);
+assert_eq!(read_back.triangle_indices, mesh.triangle_indices);
+```
+*/
+
+use std::fmt::Display;
+use std::io::{BufRead, Read, Write};
+use std::mem::size_of;
+
+use num::{Float, NumCast};
+
+use crate::generic_mesh::{face_normal, Mesh, VertexAttribute};
+
+/// Error when reading or writing a mesh file
+#[derive(Debug)]
+pub enum MeshIoError {
+    /// The underlying reader or writer failed
+    Io(std::io::Error),
+    /// The file content is not valid for the format
+    Parse(String),
+    /// The file is valid, but uses something these readers do not support
+    Unsupported(String),
+}
+
+impl Display for MeshIoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MeshIoError::Io(e) => write!(f, "I/O error: {}", e),
+            MeshIoError::Parse(message) => write!(f, "Invalid mesh file: {}", message),
+            MeshIoError::Unsupported(message) => {
+                write!(f, "Unsupported mesh file: {}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MeshIoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MeshIoError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for MeshIoError {
+    fn from(e: std::io::Error) -> Self {
+        MeshIoError::Io(e)
+    }
+}
+
+fn parse_error<T>(message: impl Into<String>) -> Result<T, MeshIoError> {
+    Err(MeshIoError::Parse(message.into()))
+}
+
+// Floats are written with the precision of F, so that text formats round-trip exactly
+fn is_double<F: Float>() -> bool {
+    size_of::<F>() > size_of::<f32>()
+}
+
+fn format_float<F: Float>(value: F) -> String {
+    if is_double::<F>() {
+        format!("{}", value.to_f64().unwrap_or(f64::NAN))
+    } else {
+        format!("{}", value.to_f32().unwrap_or(f32::NAN))
+    }
+}
+
+fn color_to_byte<F: Float>(value: F) -> u8 {
+    let value = value.to_f32().unwrap_or(0.0).clamp(0.0, 1.0);
+    (value * 255.0).round() as u8
+}
+
+fn convert<A: NumCast, B: NumCast>(value: A) -> B {
+    B::from(value).unwrap()
+}
+
+fn new_mesh<F: Float>(positions: Vec<F>, normals: Vec<F>, triangle_indices: Vec<usize>) -> Mesh<F> {
+    Mesh {
+        positions,
+        normals,
+        triangle_indices,
+        secondary_positions: None,
+        near_face_mask: None,
+        voxel_data: None,
+        colors: None,
+        attributes: vec![],
+    }
+}
+
+// Triangulate a polygon as a fan around its first vertex
+fn push_polygon(triangle_indices: &mut Vec<usize>, polygon: &[usize]) {
+    for i in 1..polygon.len().saturating_sub(1) {
+        triangle_indices.extend_from_slice(&[polygon[0], polygon[i], polygon[i + 1]]);
+    }
+}
+
+/// Write a mesh as Wavefront OBJ text. Colors, if any, are written as 3 extra values on `v` lines
+pub fn write_obj<F: Float, R>(mesh: &Mesh<F, R>, writer: impl Write) -> Result<(), MeshIoError> {
+    let mut writer = std::io::BufWriter::new(writer);
+    writeln!(writer, "# transvoxel mesh")?;
+    for (i, position) in mesh.positions.chunks_exact(3).enumerate() {
+        write!(
+            writer,
+            "v {} {} {}",
+            format_float(position[0]),
+            format_float(position[1]),
+            format_float(position[2])
+        )?;
+        if let Some(colors) = &mesh.colors {
+            let color = &colors[4 * i..4 * i + 3];
+            write!(
+                writer,
+                " {} {} {}",
+                format_float(color[0]),
+                format_float(color[1]),
+                format_float(color[2])
+            )?;
+        }
+        writeln!(writer)?;
+    }
+    for normal in mesh.normals.chunks_exact(3) {
+        writeln!(
+            writer,
+            "vn {} {} {}",
+            format_float(normal[0]),
+            format_float(normal[1]),
+            format_float(normal[2])
+        )?;
+    }
+    let with_normals = !mesh.normals.is_empty();
+    for tri in mesh.triangle_indices.chunks_exact(3) {
+        // OBJ indices start at 1
+        let (a, b, c) = (tri[0] + 1, tri[1] + 1, tri[2] + 1);
+        if with_normals {
+            writeln!(writer, "f {}//{} {}//{} {}//{}", a, a, b, b, c, c)?;
+        } else {
+            writeln!(writer, "f {} {} {}", a, b, c)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn parse_obj_floats<F: Float>(tokens: &[&str], line: usize) -> Result<Vec<F>, MeshIoError> {
+    tokens
+        .iter()
+        .map(|t| match t.parse::<f64>() {
+            Ok(v) => Ok(convert(v)),
+            Err(_) => parse_error(format!("line {}: invalid number {}", line, t)),
+        })
+        .collect()
+}
+
+// Resolve an OBJ index (1-based, or negative for relative to the end) to a 0-based one
+fn resolve_obj_index(token: &str, count: usize, line: usize) -> Result<usize, MeshIoError> {
+    let index: i64 = match token.parse() {
+        Ok(i) => i,
+        Err(_) => return parse_error(format!("line {}: invalid index {}", line, token)),
+    };
+    let resolved = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+    if resolved < 0 || resolved >= count as i64 {
+        return parse_error(format!("line {}: index {} out of range", line, token));
+    }
+    Ok(resolved as usize)
+}
+
+/**
+Read a mesh from Wavefront OBJ text
+
+Texture coordinates, groups and materials are ignored. Normals are assigned to the vertices from the faces using them,
+and kept only if every vertex got one. Colors are kept only if every `v` line has them (with an alpha of 1)
+*/
+pub fn read_obj<F: Float>(reader: impl BufRead) -> Result<Mesh<F>, MeshIoError> {
+    let mut positions = vec![];
+    let mut colors = vec![];
+    let mut all_colored = true;
+    let mut file_normals: Vec<F> = vec![];
+    let mut vertex_normals: Vec<Option<usize>> = vec![];
+    let mut triangle_indices = vec![];
+    for (line_index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_number = line_index + 1;
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.first() {
+            Some(&"v") => {
+                if tokens.len() < 4 {
+                    return parse_error(format!("line {}: not enough coordinates", line_number));
+                }
+                let values = parse_obj_floats::<F>(&tokens[1..], line_number)?;
+                positions.extend_from_slice(&values[0..3]);
+                vertex_normals.push(None);
+                if values.len() >= 6 {
+                    colors.extend_from_slice(&values[3..6]);
+                    colors.push(F::one());
+                } else {
+                    all_colored = false;
+                }
+            }
+            Some(&"vn") => {
+                if tokens.len() < 4 {
+                    return parse_error(format!("line {}: not enough coordinates", line_number));
+                }
+                let values = parse_obj_floats::<F>(&tokens[1..4], line_number)?;
+                file_normals.extend_from_slice(&values);
+            }
+            Some(&"f") => {
+                let mut polygon = vec![];
+                for token in &tokens[1..] {
+                    let mut parts = token.split('/');
+                    let vertex = resolve_obj_index(
+                        parts.next().unwrap(),
+                        vertex_normals.len(),
+                        line_number,
+                    )?;
+                    if let Some(normal) = parts.nth(1) {
+                        vertex_normals[vertex] = Some(resolve_obj_index(
+                            normal,
+                            file_normals.len() / 3,
+                            line_number,
+                        )?);
+                    }
+                    polygon.push(vertex);
+                }
+                if polygon.len() < 3 {
+                    return parse_error(format!(
+                        "line {}: face with less than 3 vertices",
+                        line_number
+                    ));
+                }
+                push_polygon(&mut triangle_indices, &polygon);
+            }
+            _ => {}
+        }
+    }
+    let normals = if vertex_normals.iter().all(Option::is_some) && !file_normals.is_empty() {
+        vertex_normals
+            .iter()
+            .flat_map(|n| {
+                let n = n.unwrap();
+                file_normals[3 * n..3 * n + 3].iter().copied()
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+    let mut mesh = new_mesh(positions, normals, triangle_indices);
+    if all_colored && !colors.is_empty() {
+        mesh.colors = Some(colors);
+    }
+    Ok(mesh)
+}
+
+/// Encoding of the body of a PLY file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlyFormat {
+    /// Human readable, one vertex or face per line
+    Ascii,
+    /// Compact binary encoding
+    BinaryLittleEndian,
+}
+
+/**
+Write a mesh as PLY
+
+Positions, normals and attributes are written as `float` properties for an f32 mesh, or `double` for an f64 mesh.
+Colors are written as `uchar` red, green, blue and alpha properties
+*/
+pub fn write_ply<F: Float, R>(
+    mesh: &Mesh<F, R>,
+    format: PlyFormat,
+    writer: impl Write,
+) -> Result<(), MeshIoError> {
+    let mut writer = std::io::BufWriter::new(writer);
+    let num_vertices = mesh.positions.len() / 3;
+    let float_type = if is_double::<F>() { "double" } else { "float" };
+    writeln!(writer, "ply")?;
+    match format {
+        PlyFormat::Ascii => writeln!(writer, "format ascii 1.0")?,
+        PlyFormat::BinaryLittleEndian => writeln!(writer, "format binary_little_endian 1.0")?,
+    }
+    writeln!(writer, "comment transvoxel mesh")?;
+    writeln!(writer, "element vertex {}", num_vertices)?;
+    let mut float_names = vec!["x", "y", "z"];
+    if !mesh.normals.is_empty() {
+        float_names.extend_from_slice(&["nx", "ny", "nz"]);
+    }
+    for name in float_names {
+        writeln!(writer, "property {} {}", float_type, name)?;
+    }
+    if mesh.colors.is_some() {
+        for name in ["red", "green", "blue", "alpha"] {
+            writeln!(writer, "property uchar {}", name)?;
+        }
+    }
+    for attribute in &mesh.attributes {
+        writeln!(writer, "property {} {}", float_type, attribute.name)?;
+    }
+    writeln!(writer, "element face {}", mesh.num_tris())?;
+    writeln!(writer, "property list uchar uint vertex_indices")?;
+    writeln!(writer, "end_header")?;
+    for i in 0..num_vertices {
+        let mut floats = mesh.positions[3 * i..3 * i + 3].to_vec();
+        if !mesh.normals.is_empty() {
+            floats.extend_from_slice(&mesh.normals[3 * i..3 * i + 3]);
+        }
+        let colors: Vec<u8> = match &mesh.colors {
+            Some(colors) => colors[4 * i..4 * i + 4]
+                .iter()
+                .map(|&c| color_to_byte(c))
+                .collect(),
+            None => vec![],
+        };
+        let attributes: Vec<F> = mesh.attributes.iter().map(|a| a.values[i]).collect();
+        match format {
+            PlyFormat::Ascii => {
+                let mut values: Vec<String> = floats.into_iter().map(format_float).collect();
+                values.extend(colors.iter().map(|c| c.to_string()));
+                values.extend(attributes.into_iter().map(format_float));
+                writeln!(writer, "{}", values.join(" "))?;
+            }
+            PlyFormat::BinaryLittleEndian => {
+                for value in floats {
+                    write_binary_float(&mut writer, value)?;
+                }
+                writer.write_all(&colors)?;
+                for value in attributes {
+                    write_binary_float(&mut writer, value)?;
+                }
+            }
+        }
+    }
+    for tri in mesh.triangle_indices.chunks_exact(3) {
+        match format {
+            PlyFormat::Ascii => writeln!(writer, "3 {} {} {}", tri[0], tri[1], tri[2])?,
+            PlyFormat::BinaryLittleEndian => {
+                writer.write_all(&[3])?;
+                for &index in tri {
+                    writer.write_all(&(index as u32).to_le_bytes())?;
+                }
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_binary_float<F: Float>(writer: &mut impl Write, value: F) -> Result<(), MeshIoError> {
+    if is_double::<F>() {
+        writer.write_all(&value.to_f64().unwrap_or(f64::NAN).to_le_bytes())?;
+    } else {
+        writer.write_all(&value.to_f32().unwrap_or(f32::NAN).to_le_bytes())?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlyType {
+    Char,
+    UChar,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Float,
+    Double,
+}
+
+impl PlyType {
+    fn parse(name: &str) -> Result<Self, MeshIoError> {
+        Ok(match name {
+            "char" | "int8" => PlyType::Char,
+            "uchar" | "uint8" => PlyType::UChar,
+            "short" | "int16" => PlyType::Short,
+            "ushort" | "uint16" => PlyType::UShort,
+            "int" | "int32" => PlyType::Int,
+            "uint" | "uint32" => PlyType::UInt,
+            "float" | "float32" => PlyType::Float,
+            "double" | "float64" => PlyType::Double,
+            _ => return parse_error(format!("unknown property type {}", name)),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            PlyType::Char | PlyType::UChar => 1,
+            PlyType::Short | PlyType::UShort => 2,
+            PlyType::Int | PlyType::UInt | PlyType::Float => 4,
+            PlyType::Double => 8,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum PlyProperty {
+    Scalar(PlyType, String),
+    List(PlyType, PlyType, String),
+}
+
+#[derive(Debug)]
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>,
+}
+
+// Sequential access to the values of a PLY body, whatever its encoding
+enum PlyBody<'a> {
+    Ascii(std::str::SplitAsciiWhitespace<'a>),
+    Binary { data: &'a [u8], big_endian: bool },
+}
+
+impl<'a> PlyBody<'a> {
+    fn next(&mut self, value_type: PlyType) -> Result<f64, MeshIoError> {
+        match self {
+            PlyBody::Ascii(tokens) => match tokens.next().map(str::parse::<f64>) {
+                Some(Ok(value)) => Ok(value),
+                Some(Err(_)) => parse_error("invalid number in body"),
+                None => parse_error("unexpected end of body"),
+            },
+            PlyBody::Binary { data, big_endian } => {
+                let size = value_type.size();
+                if data.len() < size {
+                    return parse_error("unexpected end of body");
+                }
+                let mut bytes = [0u8; 8];
+                bytes[..size].copy_from_slice(&data[..size]);
+                if *big_endian {
+                    bytes[..size].reverse();
+                }
+                *data = &data[size..];
+                Ok(match value_type {
+                    PlyType::Char => bytes[0] as i8 as f64,
+                    PlyType::UChar => bytes[0] as f64,
+                    PlyType::Short => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    PlyType::UShort => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    PlyType::Int => {
+                        i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    PlyType::UInt => {
+                        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    PlyType::Float => {
+                        f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    PlyType::Double => f64::from_le_bytes(bytes),
+                })
+            }
+        }
+    }
+}
+
+fn read_ply_header(reader: &mut impl BufRead) -> Result<(String, Vec<PlyElement>), MeshIoError> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if line.trim() != "ply" {
+        return parse_error("missing ply magic number");
+    }
+    let mut format = None;
+    let mut elements: Vec<PlyElement> = vec![];
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return parse_error("missing end_header");
+        }
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["end_header"] => break,
+            ["format", name, _version] => format = Some(name.to_string()),
+            ["element", name, count] => elements.push(PlyElement {
+                name: name.to_string(),
+                count: match count.parse() {
+                    Ok(count) => count,
+                    Err(_) => return parse_error(format!("invalid element count {}", count)),
+                },
+                properties: vec![],
+            }),
+            ["property", "list", count_type, item_type, name] => match elements.last_mut() {
+                Some(element) => element.properties.push(PlyProperty::List(
+                    PlyType::parse(count_type)?,
+                    PlyType::parse(item_type)?,
+                    name.to_string(),
+                )),
+                None => return parse_error("property before any element"),
+            },
+            ["property", value_type, name] => match elements.last_mut() {
+                Some(element) => element.properties.push(PlyProperty::Scalar(
+                    PlyType::parse(value_type)?,
+                    name.to_string(),
+                )),
+                None => return parse_error("property before any element"),
+            },
+            _ => {}
+        }
+    }
+    match format {
+        Some(format) => Ok((format, elements)),
+        None => parse_error("missing format"),
+    }
+}
+
+/**
+Read a mesh from a PLY file, in any of its three encodings
+
+`x`, `y` and `z` vertex properties are required. `nx`, `ny`, `nz` are read as normals, and `red`, `green`, `blue`
+(and optionally `alpha`) as colors (divided by 255 when stored as `uchar`). Other scalar vertex properties are read as attributes.
+Faces are read from the `vertex_indices` (or `vertex_index`) list of the `face` element. Other elements are skipped
+*/
+pub fn read_ply<F: Float>(mut reader: impl BufRead) -> Result<Mesh<F>, MeshIoError> {
+    let (format, elements) = read_ply_header(&mut reader)?;
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+    let text;
+    let mut body = match format.as_str() {
+        "ascii" => {
+            text = match std::str::from_utf8(&data) {
+                Ok(text) => text,
+                Err(_) => return parse_error("non UTF-8 ascii body"),
+            };
+            PlyBody::Ascii(text.split_ascii_whitespace())
+        }
+        "binary_little_endian" => PlyBody::Binary {
+            data: &data,
+            big_endian: false,
+        },
+        "binary_big_endian" => PlyBody::Binary {
+            data: &data,
+            big_endian: true,
+        },
+        _ => return Err(MeshIoError::Unsupported(format!("format {}", format))),
+    };
+    let mut vertex_properties: Vec<(String, PlyType, Vec<f64>)> = vec![];
+    let mut triangle_indices = vec![];
+    for element in &elements {
+        let is_vertex = element.name == "vertex";
+        let is_face = element.name == "face";
+        if is_vertex {
+            for property in &element.properties {
+                if let PlyProperty::Scalar(value_type, name) = property {
+                    // Counts from the file are not trusted for allocations: vectors grow as values are actually read
+                    vertex_properties.push((name.clone(), *value_type, vec![]));
+                }
+            }
+        }
+        if element.properties.is_empty() {
+            continue;
+        }
+        for _ in 0..element.count {
+            let mut scalar_index = 0;
+            for property in &element.properties {
+                match property {
+                    PlyProperty::Scalar(value_type, _) => {
+                        let value = body.next(*value_type)?;
+                        if is_vertex {
+                            vertex_properties[scalar_index].2.push(value);
+                            scalar_index += 1;
+                        }
+                    }
+                    PlyProperty::List(count_type, item_type, name) => {
+                        let count = body.next(*count_type)? as usize;
+                        let mut polygon = vec![];
+                        for _ in 0..count {
+                            polygon.push(body.next(*item_type)? as usize);
+                        }
+                        if is_face && (name == "vertex_indices" || name == "vertex_index") {
+                            push_polygon(&mut triangle_indices, &polygon);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    let take = |properties: &mut Vec<(String, PlyType, Vec<f64>)>, name: &str| {
+        properties
+            .iter()
+            .position(|p| p.0 == name)
+            .map(|i| properties.remove(i))
+    };
+    let interleave = |columns: Vec<Vec<f64>>| -> Vec<F> {
+        let count = columns.first().map_or(0, Vec::len);
+        (0..count)
+            .flat_map(|i| columns.iter().map(move |c| convert(c[i])))
+            .collect()
+    };
+    let mut positions = vec![];
+    for name in ["x", "y", "z"] {
+        match take(&mut vertex_properties, name) {
+            Some((_, _, values)) => positions.push(values),
+            None => return parse_error(format!("missing vertex property {}", name)),
+        }
+    }
+    let positions = interleave(positions);
+    let num_vertices = positions.len() / 3;
+    if triangle_indices.iter().any(|&i| i >= num_vertices) {
+        return parse_error("face index out of range");
+    }
+    let normals: Option<Vec<_>> = ["nx", "ny", "nz"]
+        .iter()
+        .map(|name| take(&mut vertex_properties, name).map(|p| p.2))
+        .collect();
+    let normals = normals.map(interleave).unwrap_or_default();
+    let rgb: Option<Vec<_>> = ["red", "green", "blue"]
+        .iter()
+        .map(|name| take(&mut vertex_properties, name))
+        .collect();
+    let colors = rgb.map(|rgb| {
+        let alpha = take(&mut vertex_properties, "alpha");
+        let mut columns: Vec<Vec<f64>> =
+            rgb.into_iter().chain(alpha).map(normalize_color).collect();
+        if columns.len() < 4 {
+            columns.push(vec![1.0; num_vertices]);
+        }
+        interleave(columns)
+    });
+    let mut mesh = new_mesh(positions, normals, triangle_indices);
+    mesh.colors = colors;
+    mesh.attributes = vertex_properties
+        .into_iter()
+        .map(|(name, _, values)| VertexAttribute {
+            name,
+            values: values.into_iter().map(convert).collect(),
+        })
+        .collect();
+    Ok(mesh)
+}
+
+fn normalize_color((_, value_type, values): (String, PlyType, Vec<f64>)) -> Vec<f64> {
+    match value_type {
+        PlyType::UChar => values.into_iter().map(|v| v / 255.0).collect(),
+        PlyType::UShort => values.into_iter().map(|v| v / 65535.0).collect(),
+        _ => values,
+    }
+}
+
+const STL_HEADER_SIZE: usize = 80;
+const STL_TRIANGLE_SIZE: usize = 50;
+
+/// Write a mesh as binary STL. Each triangle gets its geometric normal: the vertex normals are not used
+pub fn write_stl<F: Float, R>(mesh: &Mesh<F, R>, writer: impl Write) -> Result<(), MeshIoError> {
+    let mut writer = std::io::BufWriter::new(writer);
+    let mut header = [0u8; STL_HEADER_SIZE];
+    let title = b"transvoxel mesh";
+    header[..title.len()].copy_from_slice(title);
+    writer.write_all(&header)?;
+    writer.write_all(&(mesh.num_tris() as u32).to_le_bytes())?;
+    for tri in mesh.triangle_indices.chunks_exact(3) {
+        let corners: Vec<[F; 3]> = tri
+            .iter()
+            .map(|&i| {
+                [
+                    mesh.positions[3 * i],
+                    mesh.positions[3 * i + 1],
+                    mesh.positions[3 * i + 2],
+                ]
+            })
+            .collect();
+        let normal = face_normal(&corners[0], &corners[1], &corners[2]);
+        for value in normal.iter().chain(corners.iter().flatten()) {
+            writer.write_all(&value.to_f32().unwrap_or(f32::NAN).to_le_bytes())?;
+        }
+        // Attribute byte count
+        writer.write_all(&[0, 0])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read a mesh from a binary STL file. Each triangle gets its own 3 vertices, with the triangle normal
+pub fn read_stl<F: Float>(mut reader: impl Read) -> Result<Mesh<F>, MeshIoError> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+    if data.starts_with(b"solid") && data.len() < STL_HEADER_SIZE + 4 {
+        return Err(MeshIoError::Unsupported("ASCII STL".to_string()));
+    }
+    if data.len() < STL_HEADER_SIZE + 4 {
+        return parse_error("truncated STL header");
+    }
+    let count_bytes = &data[STL_HEADER_SIZE..STL_HEADER_SIZE + 4];
+    let num_tris = u32::from_le_bytes([
+        count_bytes[0],
+        count_bytes[1],
+        count_bytes[2],
+        count_bytes[3],
+    ]) as usize;
+    let body = &data[STL_HEADER_SIZE + 4..];
+    if body.len() < num_tris * STL_TRIANGLE_SIZE {
+        if data.starts_with(b"solid") {
+            return Err(MeshIoError::Unsupported("ASCII STL".to_string()));
+        }
+        return parse_error(format!(
+            "expected {} triangles, but the file is too short",
+            num_tris
+        ));
+    }
+    let mut positions = Vec::with_capacity(9 * num_tris);
+    let mut normals = Vec::with_capacity(9 * num_tris);
+    for tri in body.chunks_exact(STL_TRIANGLE_SIZE).take(num_tris) {
+        let values: Vec<F> = tri[..48]
+            .chunks_exact(4)
+            .map(|b| convert(f32::from_le_bytes([b[0], b[1], b[2], b[3]])))
+            .collect();
+        for _ in 0..3 {
+            normals.extend_from_slice(&values[0..3]);
+        }
+        positions.extend_from_slice(&values[3..12]);
+    }
+    Ok(new_mesh(positions, normals, (0..3 * num_tris).collect()))
+}
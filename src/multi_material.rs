@@ -0,0 +1,261 @@
+/*!
+A [MeshBuilder] splitting the triangles of a mesh per material, with one shared vertex buffer
+
+The material of each vertex is the material of the "inside" grid point of the cell edge it lies on
+(see [Density::inside]), as given by a function of the voxel data you provide.
+The material of each triangle is then chosen from its 3 vertices materials, by a [TriangleVote].
+
+```rust
+use transvoxel::prelude::*;
+use transvoxel::multi_material::MultiMaterialMeshBuilder;
+use transvoxel::traits::VoxelData;
+
+#[derive(Clone, Copy, Default)]
+struct Voxel {
+    density: f32,
+    material: u8,
+}
+
+impl VoxelData for Voxel {
+    type Density = f32;
+    fn density(&self) -> f32 {
+        self.density
+    }
+}
+
+// Rock below y=5, dirt above
+let field = |x: f32, y: f32, z: f32| Voxel {
+    density: 4.0 - ((x - 5.0).powi(2) + (y - 5.0).powi(2) + (z - 5.0).powi(2)).sqrt(),
+    material: if y < 5.0 { 0 } else { 1 },
+};
+let block = Block::from([0.0, 0.0, 0.0], 10.0, 10);
+let builder = MultiMaterialMeshBuilder::new(0.0, |voxel: &Voxel| voxel.material);
+let mesh = extract_from_field(&field, &block, 0.0, transition_sides::no_side(), builder).build();
+
+assert_eq!(mesh.materials().collect::<Vec<_>>(), vec![0, 1]);
+let rock_triangles = mesh.submesh(0).len() / 3;
+let dirt_triangles = mesh.submesh(1).len() / 3;
+assert_eq!(rock_triangles + dirt_triangles, mesh.mesh.num_tris());
+```
+
+[MeshBuilder]: crate::mesh_builder::MeshBuilder
+[Density::inside]: crate::traits::Density::inside
+*/
+
+use std::collections::BTreeMap;
+
+use crate::generic_mesh::{GenericMeshBuilder, Mesh};
+use crate::mesh_builder::{GridPoint, MeshBuilder, NormalMode, VertexIndex, VertexPlacement};
+use crate::traits::{Coordinate, Density, DensityFloat, VoxelData};
+
+/// How the material of a triangle is chosen from the materials of its 3 vertices
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TriangleVote {
+    /// The material of at least 2 of the vertices, or of the first vertex if they all differ
+    #[default]
+    Majority,
+    /// The material of the first vertex
+    FirstVertex,
+    /// The lowest material (for the `Ord` of the materials type)
+    Lowest,
+    /// The highest material (for the `Ord` of the materials type)
+    Highest,
+}
+
+impl TriangleVote {
+    /// The material of a triangle whose vertices have the given materials
+    pub fn choose<M: Copy + Ord>(&self, materials: [M; 3]) -> M {
+        let [m1, m2, m3] = materials;
+        match self {
+            TriangleVote::Majority => {
+                if m2 == m3 {
+                    m2
+                } else {
+                    m1
+                }
+            }
+            TriangleVote::FirstVertex => m1,
+            TriangleVote::Lowest => m1.min(m2).min(m3),
+            TriangleVote::Highest => m1.max(m2).max(m3),
+        }
+    }
+}
+
+/// Mesh built by [MultiMaterialMeshBuilder]
+#[derive(Debug)]
+pub struct MultiMaterialMesh<F, M>
+where
+    F: Coordinate,
+{
+    /// The vertices, shared by the submeshes. Its `triangle_indices` contains all the triangles, in extraction order
+    pub mesh: Mesh<F>,
+    /// Material of each vertex
+    pub vertex_materials: Vec<M>,
+    /// Triangle indices for each material present in the mesh. Each consecutive i,j,k define one triangle, referring to the vertices of `mesh`
+    pub submeshes: BTreeMap<M, Vec<usize>>,
+}
+
+impl<F, M> MultiMaterialMesh<F, M>
+where
+    F: Coordinate,
+    M: Copy + Ord,
+{
+    /// The materials present in the mesh, in increasing order
+    pub fn materials(&self) -> impl Iterator<Item = M> + '_ {
+        self.submeshes.keys().copied()
+    }
+
+    /// Triangle indices for one material. Empty if the material is not present
+    pub fn submesh(&self, material: M) -> &[usize] {
+        self.submeshes
+            .get(&material)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+}
+
+/// A MeshBuilder that builds [MultiMaterialMesh]
+pub struct MultiMaterialMeshBuilder<V, F, M>
+where
+    V: VoxelData,
+    F: Coordinate,
+{
+    vertices: GenericMeshBuilder<F>,
+    threshold: V::Density,
+    material: Box<dyn Fn(&V) -> M + Send + Sync>,
+    vote: TriangleVote,
+    vertex_materials: Vec<M>,
+    triangle_materials: Vec<M>,
+}
+
+impl<V, F, M> MultiMaterialMeshBuilder<V, F, M>
+where
+    V: VoxelData,
+    F: Coordinate,
+    M: Copy + Ord,
+{
+    /// Create a fresh builder, getting the material of voxels with `material`.
+    /// `threshold` must be the one the extraction uses, to know which grid point of each edge is inside
+    pub fn new(threshold: V::Density, material: impl Fn(&V) -> M + Send + Sync + 'static) -> Self {
+        Self {
+            vertices: GenericMeshBuilder::new(),
+            threshold,
+            material: Box::new(material),
+            vote: TriangleVote::default(),
+            vertex_materials: vec![],
+            triangle_materials: vec![],
+        }
+    }
+
+    /// Choose how the material of each triangle is decided
+    pub fn with_vote(mut self, vote: TriangleVote) -> Self {
+        self.vote = vote;
+        self
+    }
+
+    /// Choose which normals are produced. With [NormalMode::Face], each triangle gets its own copies of its 3 vertices
+    pub fn with_normal_mode(mut self, normal_mode: NormalMode) -> Self {
+        self.vertices = self.vertices.with_normal_mode(normal_mode);
+        self
+    }
+
+    /// Choose how vertices are placed on cell edges
+    pub fn with_vertex_placement(mut self, vertex_placement: VertexPlacement) -> Self {
+        self.vertices = self.vertices.with_vertex_placement(vertex_placement);
+        self
+    }
+
+    /// Also output secondary positions and near faces masks (see [GenericMeshBuilder::with_secondary_positions])
+    pub fn with_secondary_positions(mut self) -> Self {
+        self.vertices = self.vertices.with_secondary_positions();
+        self
+    }
+
+    /// Build the mesh
+    pub fn build(self) -> MultiMaterialMesh<F, M> {
+        let face_normals = MeshBuilder::<V, F>::normal_mode(&self.vertices) == NormalMode::Face;
+        let vertex_materials = self.vertex_materials;
+        let mesh = self.vertices.build_indexed();
+        let mut submeshes: BTreeMap<M, Vec<usize>> = BTreeMap::new();
+        for (t, (tri, material)) in mesh
+            .triangle_indices
+            .chunks_exact(3)
+            .zip(self.triangle_materials.iter())
+            .enumerate()
+        {
+            let indices = submeshes.entry(*material).or_default();
+            if face_normals {
+                indices.extend(3 * t..3 * t + 3);
+            } else {
+                indices.extend_from_slice(tri);
+            }
+        }
+        if !face_normals {
+            return MultiMaterialMesh {
+                mesh,
+                vertex_materials,
+                submeshes,
+            };
+        }
+        // Each triangle has its own copies of its vertices
+        let vertex_materials = mesh
+            .triangle_indices
+            .iter()
+            .map(|i| vertex_materials[*i])
+            .collect();
+        MultiMaterialMesh {
+            mesh: mesh.with_face_normals(),
+            vertex_materials,
+            submeshes,
+        }
+    }
+}
+
+impl<V, F, M> MeshBuilder<V, F> for MultiMaterialMeshBuilder<V, F, M>
+where
+    V: VoxelData,
+    F: Coordinate,
+    M: Copy + Ord,
+{
+    fn normal_mode(&self) -> NormalMode {
+        MeshBuilder::<V, F>::normal_mode(&self.vertices)
+    }
+
+    fn vertex_placement(&self) -> VertexPlacement {
+        MeshBuilder::<V, F>::vertex_placement(&self.vertices)
+    }
+
+    fn add_vertex_between(
+        &mut self,
+        point_a: GridPoint<V, F>,
+        point_b: GridPoint<V, F>,
+        interp_toward_b: DensityFloat<V>,
+    ) -> VertexIndex {
+        // Exactly one of the points is inside the volume
+        let inside = if point_a.voxel_data.density().inside(&self.threshold) {
+            &point_a.voxel_data
+        } else {
+            &point_b.voxel_data
+        };
+        self.vertex_materials.push((self.material)(inside));
+        self.vertices
+            .add_vertex_between(point_a, point_b, interp_toward_b)
+    }
+
+    fn add_triangle(
+        &mut self,
+        vertex_1_index: VertexIndex,
+        vertex_2_index: VertexIndex,
+        vertex_3_index: VertexIndex,
+    ) {
+        let indices = [vertex_1_index.0, vertex_2_index.0, vertex_3_index.0];
+        let material = self.vote.choose(indices.map(|i| self.vertex_materials[i]));
+        self.triangle_materials.push(material);
+        MeshBuilder::<V, F>::add_triangle(
+            &mut self.vertices,
+            vertex_1_index,
+            vertex_2_index,
+            vertex_3_index,
+        );
+    }
+}
@@ -0,0 +1,256 @@
+/*!
+Adapters to extract meshes directly from [ndarray] 3D arrays of densities (requires the `ndarray` feature)
+
+The array is seen as samples on a regular grid in the world: sample `[i, j, k]` is at `origin + spacing * (i, j, k)`.
+Arrays are borrowed as [ArrayView3], so there is no copy of the data.
+
+ * [NdarrayField] is a [DataField] usable for any block: it interpolates (trilinear) between samples
+ * [NdarrayVoxelSource] is a [VoxelSource] for one block whose voxels coincide with samples. It reads regular voxels directly,
+   and only interpolates transition voxels falling between samples
+
+Points out of the array are clamped to its border.
+
+[DataField]: crate::voxel_source::DataField
+[VoxelSource]: crate::voxel_source::VoxelSource
+*/
+
+use std::fmt::Display;
+
+use ndarray::{s, ArrayView3};
+use num::{Float, NumCast, Zero};
+
+use crate::traits::{to_whole, Coordinate, Density};
+use crate::voxel_coordinates::{HighResolutionVoxelIndex, RegularVoxelIndex};
+use crate::voxel_source::{density_bounds_of, Block, DataField, VoxelSource};
+
+/// Error when a [NdarrayVoxelSource] cannot be built for a block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NdarrayError {
+    /// The block voxels do not coincide with array samples
+    NotAligned,
+    /// The block is not entirely within the array
+    OutOfBounds,
+}
+
+impl Display for NdarrayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NdarrayError::NotAligned => {
+                write!(f, "block voxels do not coincide with array samples")
+            }
+            NdarrayError::OutOfBounds => write!(f, "block is not entirely within the array"),
+        }
+    }
+}
+
+impl std::error::Error for NdarrayError {}
+
+/// A [DataField] reading an array, with trilinear interpolation between samples
+///
+/// [DataField]: crate::voxel_source::DataField
+pub struct NdarrayField<'a, D, C>
+where
+    D: Density,
+    C: Coordinate,
+{
+    data: ArrayView3<'a, D>,
+    origin: [C; 3],
+    spacing: C,
+}
+
+impl<'a, D, C> NdarrayField<'a, D, C>
+where
+    D: Density,
+    C: Coordinate,
+{
+    /// Sample `[i, j, k]` of `data` is at `origin + spacing * (i, j, k)` in the world
+    pub fn new(data: ArrayView3<'a, D>, origin: [C; 3], spacing: C) -> Self {
+        Self {
+            data,
+            origin,
+            spacing,
+        }
+    }
+}
+
+impl<D, C> DataField<D, C> for NdarrayField<'_, D, C>
+where
+    D: Density,
+    C: Coordinate,
+{
+    fn get_data(&mut self, x: C, y: C, z: C) -> D {
+        DataField::get_data(&mut &*self, x, y, z)
+    }
+
+    fn density_bounds(&self, min: [C; 3], max: [C; 3]) -> Option<(D, D)> {
+        DataField::density_bounds(&self, min, max)
+    }
+}
+
+// Reading does not modify the field, so it can also be shared (for example between threads)
+impl<D, C> DataField<D, C> for &NdarrayField<'_, D, C>
+where
+    D: Density,
+    C: Coordinate,
+{
+    fn get_data(&mut self, x: C, y: C, z: C) -> D {
+        let index =
+            |p: C, axis: usize| -> D::Float { cast((p - self.origin[axis]) / self.spacing) };
+        trilinear(&self.data, [index(x, 0), index(y, 1), index(z, 2)])
+    }
+
+    fn density_bounds(&self, min: [C; 3], max: [C; 3]) -> Option<(D, D)> {
+        let index =
+            |p: C, axis: usize| -> D::Float { cast((p - self.origin[axis]) / self.spacing) };
+        let low = |axis: usize| cast::<D::Float, isize>(index(min[axis], axis).floor());
+        let high = |axis: usize| cast::<D::Float, isize>(index(max[axis], axis).ceil());
+        samples_bounds(
+            &self.data,
+            [low(0), low(1), low(2)],
+            [high(0), high(1), high(2)],
+        )
+    }
+}
+
+/**
+A [VoxelSource] reading an array, for a block whose voxels all coincide with array samples
+(the block base is on a sample, and its cell size is a whole number of samples)
+
+[VoxelSource]: crate::voxel_source::VoxelSource
+*/
+pub struct NdarrayVoxelSource<'a, D>
+where
+    D: Density,
+{
+    data: ArrayView3<'a, D>,
+    base: [isize; 3],
+    step: isize,
+    subdivisions: usize,
+}
+
+impl<'a, D> NdarrayVoxelSource<'a, D>
+where
+    D: Density,
+{
+    /// Sample `[i, j, k]` of `data` is at `origin + spacing * (i, j, k)` in the world
+    pub fn new<C: Coordinate>(
+        data: ArrayView3<'a, D>,
+        origin: [C; 3],
+        spacing: C,
+        block: &Block<C>,
+    ) -> Result<Self, NdarrayError> {
+        let step = to_whole(block.dims.size / C::from(block.subdivisions).unwrap() / spacing)
+            .ok_or(NdarrayError::NotAligned)?;
+        if step <= 0 {
+            return Err(NdarrayError::NotAligned);
+        }
+        let mut base = [0isize; 3];
+        for axis in 0..3 {
+            base[axis] = to_whole((block.dims.base[axis] - origin[axis]) / spacing)
+                .ok_or(NdarrayError::NotAligned)?;
+            let end = base[axis] + step * block.subdivisions as isize;
+            if base[axis] < 0 || end >= data.shape()[axis] as isize {
+                return Err(NdarrayError::OutOfBounds);
+            }
+        }
+        Ok(Self {
+            data,
+            base,
+            step,
+            subdivisions: block.subdivisions,
+        })
+    }
+}
+
+impl<D> VoxelSource<D> for NdarrayVoxelSource<'_, D>
+where
+    D: Density,
+{
+    fn get_regular_voxel(&mut self, voxel_index: &RegularVoxelIndex) -> D {
+        let i = clamp_index(&self.data, 0, self.base[0] + self.step * voxel_index.x);
+        let j = clamp_index(&self.data, 1, self.base[1] + self.step * voxel_index.y);
+        let k = clamp_index(&self.data, 2, self.base[2] + self.step * voxel_index.z);
+        self.data[[i, j, k]]
+    }
+
+    fn get_transition_voxel(&mut self, voxel_index: &HighResolutionVoxelIndex) -> D {
+        let index = voxel_index.to_double_resolution_index(self.subdivisions);
+        // In doubled array indices, to stay exact when possible
+        let doubled = [
+            2 * self.base[0] + self.step * index.x,
+            2 * self.base[1] + self.step * index.y,
+            2 * self.base[2] + self.step * index.z,
+        ];
+        if doubled.iter().all(|d| d % 2 == 0) {
+            let i = clamp_index(&self.data, 0, doubled[0] / 2);
+            let j = clamp_index(&self.data, 1, doubled[1] / 2);
+            let k = clamp_index(&self.data, 2, doubled[2] / 2);
+            self.data[[i, j, k]]
+        } else {
+            let half = |d: isize| cast::<isize, D::Float>(d) / cast(2);
+            trilinear(
+                &self.data,
+                [half(doubled[0]), half(doubled[1]), half(doubled[2])],
+            )
+        }
+    }
+
+    fn density_bounds(&self) -> Option<(D, D)> {
+        let extent = self.step * self.subdivisions as isize;
+        let high = [
+            self.base[0] + extent,
+            self.base[1] + extent,
+            self.base[2] + extent,
+        ];
+        samples_bounds(&self.data, self.base, high)
+    }
+}
+
+fn cast<A: NumCast, B: NumCast>(a: A) -> B {
+    B::from(a).unwrap()
+}
+
+// Bounds of the samples between two indices (included), clamped to the array
+fn samples_bounds<D: Density>(
+    data: &ArrayView3<D>,
+    low: [isize; 3],
+    high: [isize; 3],
+) -> Option<(D, D)> {
+    let range =
+        |axis: usize| clamp_index(data, axis, low[axis])..=clamp_index(data, axis, high[axis]);
+    density_bounds_of(data.slice(s![range(0), range(1), range(2)]).iter().copied())
+}
+
+fn clamp_index<D>(data: &ArrayView3<D>, axis: usize, i: isize) -> usize {
+    i.clamp(0, data.shape()[axis] as isize - 1) as usize
+}
+
+// Interpolates between the 8 samples around a fractional index
+fn trilinear<D: Density>(data: &ArrayView3<D>, index: [D::Float; 3]) -> D {
+    let mut lows = [0usize; 3];
+    let mut highs = [0usize; 3];
+    let mut ratios = [D::Float::zero(); 3];
+    for axis in 0..3 {
+        let floor = index[axis].floor();
+        let low: isize = cast(floor);
+        lows[axis] = clamp_index(data, axis, low);
+        highs[axis] = clamp_index(data, axis, low + 1);
+        ratios[axis] = if lows[axis] == highs[axis] {
+            D::Float::zero()
+        } else {
+            index[axis] - floor
+        };
+    }
+    let lerp = |a: D::Float, b: D::Float, t: D::Float| a + t * (b - a);
+    let sample = |i: usize, j: usize, k: usize| data[[i, j, k]].to_float();
+    let [x0, y0, z0] = lows;
+    let [x1, y1, z1] = highs;
+    let [tx, ty, tz] = ratios;
+    let c00 = lerp(sample(x0, y0, z0), sample(x1, y0, z0), tx);
+    let c01 = lerp(sample(x0, y0, z1), sample(x1, y0, z1), tx);
+    let c10 = lerp(sample(x0, y1, z0), sample(x1, y1, z0), tx);
+    let c11 = lerp(sample(x0, y1, z1), sample(x1, y1, z1), tx);
+    let c0 = lerp(c00, c10, ty);
+    let c1 = lerp(c01, c11, ty);
+    D::from_float(lerp(c0, c1, tz))
+}
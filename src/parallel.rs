@@ -0,0 +1,63 @@
+/*!
+Parallel extraction of many blocks, using rayon (requires the `rayon` feature)
+*/
+
+use rayon::prelude::*;
+
+use super::extraction::{extract_from_field_with_context, ExtractionContext};
+use super::mesh_builder::*;
+use super::traits::*;
+use super::transition_sides::TransitionSides;
+use super::voxel_source::*;
+
+/**
+Extracts iso-surface meshes for many blocks of a [DataField], in parallel
+
+Arguments:
+ * `field`: the voxel data field, shared by all threads. A shared reference to it must be a [DataField], as is the
+   case for closures (`Fn`), [sdf](crate::sdf) shapes and [NdarrayField](crate::ndarray_source::NdarrayField)s
+ * `jobs`: the blocks to extract, each with its set of transition sides
+ * `threshold`: density value defining the iso-surface
+ * `new_builder`: called to get a fresh builder for each block
+ * The builders are returned in the same order as the jobs.
+
+The jobs are split in as many consecutive chunks as rayon has threads, and each chunk is extracted with one
+[ExtractionContext], instead of reallocating the extraction storage for every block.
+*/
+pub fn extract_batch<C, V, FIELD, M, FM>(
+    field: &FIELD,
+    jobs: &[(Block<C>, TransitionSides)],
+    threshold: V::Density,
+    new_builder: FM,
+) -> Vec<M>
+where
+    C: Coordinate + Sync,
+    V: VoxelData,
+    V::Density: Sync,
+    FIELD: Sync + ?Sized,
+    for<'f> &'f FIELD: DataField<V, C>,
+    M: MeshBuilder<V, C> + Send,
+    FM: Fn() -> M + Sync,
+{
+    let chunk_size = jobs.len().div_ceil(rayon::current_num_threads()).max(1);
+    let chunks: Vec<Vec<M>> = jobs
+        .par_chunks(chunk_size)
+        .map(|chunk| {
+            let mut context = ExtractionContext::new();
+            chunk
+                .iter()
+                .map(|(block, transition_sides)| {
+                    extract_from_field_with_context(
+                        &mut context,
+                        field,
+                        block,
+                        threshold,
+                        *transition_sides,
+                        new_builder(),
+                    )
+                })
+                .collect()
+        })
+        .collect();
+    chunks.into_iter().flatten().collect()
+}
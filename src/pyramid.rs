@@ -0,0 +1,389 @@
+/*!
+Multi-level voxel pyramids, to feed blocks of every level of detail from one finest-level grid
+
+A [VoxelPyramid] is built from a dense grid of density samples: sample `[i, j, k]` is at `origin + spacing * (i, j, k)`.
+Each level halves the resolution of the previous one: sample `i` of level `l` is at the same position as sample `2i` of level `l - 1`
+(and sample `i * 2^l` of the finest level 0).
+
+[VoxelPyramid::source] gives a [VoxelSource] for a block with one cell per sample of a level. Following the [lod](crate::lod) conventions,
+a block twice as big needs one more level to keep the same subdivisions. Transition voxels are read from the finer level.
+
+[VoxelSource]: crate::voxel_source::VoxelSource
+*/
+
+use std::fmt::Display;
+
+use num::{NumCast, Zero};
+
+use crate::traits::{to_whole, Coordinate, Density};
+use crate::voxel_coordinates::{HighResolutionVoxelIndex, RegularVoxelIndex};
+use crate::voxel_source::{density_bounds_of, Block, VoxelSource};
+
+/// How samples of a level are computed from the samples of the finer level
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DownsampleFilter<D>
+where
+    D: Density,
+{
+    /// Take the coinciding finer sample. This gives exactly the samples a
+    /// [WorldMappingVoxelSource](crate::voxel_source::WorldMappingVoxelSource) would get from the original field
+    PointSample,
+    /// Weighted average of the 27 finer samples around (weights 1/4, 1/2, 1/4 in each direction). Smooths out small details
+    Average,
+    /**
+    Keep the side of the surface of the coinciding finer sample, with the most extreme value of the 27 finer samples around:
+    the maximum when inside (for the given threshold), the minimum otherwise. Thin features are kept, but grow
+    */
+    MinMax(D),
+}
+
+/// Error when building a [VoxelPyramid], or getting a source from it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PyramidError {
+    /// The samples count does not match the grid dimensions
+    SamplesCount {
+        /// Expected count
+        expected: usize,
+        /// Provided count
+        actual: usize,
+    },
+    /// The grid is too small for the requested number of levels (each level needs at least 2 samples in each direction)
+    TooManyLevels,
+    /// The requested level does not exist
+    NoSuchLevel,
+    /// The block voxels do not coincide with the samples of the level
+    NotAligned,
+    /// The block is not entirely within the grid
+    OutOfBounds,
+}
+
+impl Display for PyramidError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PyramidError::SamplesCount { expected, actual } => write!(
+                f,
+                "wrong samples count: expected {}, got {}",
+                expected, actual
+            ),
+            PyramidError::TooManyLevels => write!(f, "grid too small for the number of levels"),
+            PyramidError::NoSuchLevel => write!(f, "no such level"),
+            PyramidError::NotAligned => {
+                write!(f, "block voxels do not coincide with the level samples")
+            }
+            PyramidError::OutOfBounds => write!(f, "block is not entirely within the grid"),
+        }
+    }
+}
+
+impl std::error::Error for PyramidError {}
+
+// Cells per side of the bricks whose density bounds are kept for each level
+const BRICK_SIZE: isize = 4;
+
+struct Level<D> {
+    dims: [usize; 3],
+    samples: Vec<D>,
+    // Number of bricks in each direction
+    brick_dims: [usize; 3],
+    // Density bounds of the samples of each brick (including the ones on its faces), in x-major order
+    brick_bounds: Vec<(D, D)>,
+}
+
+impl<D: Density> Level<D> {
+    fn new(dims: [usize; 3], samples: Vec<D>) -> Self {
+        let brick_dims =
+            dims.map(|d| ((d as isize - 1 + BRICK_SIZE - 1) / BRICK_SIZE).max(1) as usize);
+        let mut level = Level {
+            dims,
+            samples,
+            brick_dims,
+            brick_bounds: Vec::with_capacity(brick_dims[0] * brick_dims[1] * brick_dims[2]),
+        };
+        for x in 0..brick_dims[0] as isize {
+            for y in 0..brick_dims[1] as isize {
+                for z in 0..brick_dims[2] as isize {
+                    let low = [x, y, z].map(|b| b * BRICK_SIZE);
+                    let high = low.map(|l| l + BRICK_SIZE);
+                    let bounds = level.samples_bounds(low, high).unwrap();
+                    level.brick_bounds.push(bounds);
+                }
+            }
+        }
+        level
+    }
+
+    // Conservative bounds of the samples between two indices (included): bounds of the bricks containing them
+    fn bounds(&self, low: [isize; 3], high: [isize; 3]) -> Option<(D, D)> {
+        let range = move |axis: usize| {
+            let clamped = |i: isize| i.clamp(0, self.dims[axis] as isize - 1);
+            let last = self.brick_dims[axis] as isize - 1;
+            let first = (clamped(low[axis]) / BRICK_SIZE).min(last);
+            // A sample on the low face of a brick is also on the high face of the previous one
+            let end = ((clamped(high[axis]) - 1).max(0) / BRICK_SIZE).min(last);
+            first..=end.max(first)
+        };
+        let index = move |x: isize, y: isize, z: isize| {
+            (x as usize * self.brick_dims[1] + y as usize) * self.brick_dims[2] + z as usize
+        };
+        density_bounds_of(
+            range(0)
+                .flat_map(move |x| {
+                    range(1)
+                        .flat_map(move |y| range(2).map(move |z| self.brick_bounds[index(x, y, z)]))
+                })
+                .flat_map(|(min, max)| [min, max]),
+        )
+    }
+
+    // Exact bounds of the samples between two indices (included)
+    fn samples_bounds(&self, low: [isize; 3], high: [isize; 3]) -> Option<(D, D)> {
+        let range = move |axis: usize| low[axis]..=high[axis];
+        density_bounds_of(range(0).flat_map(move |x| {
+            range(1).flat_map(move |y| range(2).map(move |z| self.sample([x, y, z])))
+        }))
+    }
+
+    // Out of the grid indices are clamped to its border
+    fn sample(&self, index: [isize; 3]) -> D {
+        let clamped = |axis: usize| index[axis].clamp(0, self.dims[axis] as isize - 1) as usize;
+        let (x, y, z) = (clamped(0), clamped(1), clamped(2));
+        self.samples[x * self.dims[1] * self.dims[2] + y * self.dims[2] + z]
+    }
+
+    fn downsample(&self, filter: DownsampleFilter<D>) -> Level<D> {
+        let dims = [
+            (self.dims[0] - 1) / 2 + 1,
+            (self.dims[1] - 1) / 2 + 1,
+            (self.dims[2] - 1) / 2 + 1,
+        ];
+        let mut samples = Vec::with_capacity(dims[0] * dims[1] * dims[2]);
+        for x in 0..dims[0] as isize {
+            for y in 0..dims[1] as isize {
+                for z in 0..dims[2] as isize {
+                    samples.push(self.filtered([2 * x, 2 * y, 2 * z], filter));
+                }
+            }
+        }
+        Level::new(dims, samples)
+    }
+
+    fn filtered(&self, center: [isize; 3], filter: DownsampleFilter<D>) -> D {
+        let point = self.sample(center);
+        let half = <D::Float as NumCast>::from(0.5).unwrap();
+        let quarter = half * half;
+        let weight = |delta: isize| if delta == 0 { half } else { quarter };
+        let mut average = D::Float::zero();
+        let mut min = point;
+        let mut max = point;
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let s = self.sample([center[0] + dx, center[1] + dy, center[2] + dz]);
+                    average = average + weight(dx) * weight(dy) * weight(dz) * s.to_float();
+                    if s < min {
+                        min = s;
+                    }
+                    if s > max {
+                        max = s;
+                    }
+                }
+            }
+        }
+        match filter {
+            DownsampleFilter::PointSample => point,
+            DownsampleFilter::Average => D::from_float(average),
+            DownsampleFilter::MinMax(threshold) => {
+                if point.inside(&threshold) {
+                    max
+                } else {
+                    min
+                }
+            }
+        }
+    }
+}
+
+/// Density samples at several resolutions. See the [module](self) documentation
+pub struct VoxelPyramid<D, C>
+where
+    D: Density,
+    C: Coordinate,
+{
+    origin: [C; 3],
+    spacing: C,
+    levels: Vec<Level<D>>,
+}
+
+impl<D, C> VoxelPyramid<D, C>
+where
+    D: Density,
+    C: Coordinate,
+{
+    /**
+    Build `levels` levels (including the finest one) from the finest samples.
+
+    Arguments:
+     * `samples`: the finest grid samples, in x-major order
+     * `dims`: the number of samples in each direction
+     * `origin`, `spacing`: sample `[i, j, k]` is at `origin + spacing * (i, j, k)` in the world
+     * `levels`: how many levels to build, including the finest one
+     * `filter`: how to compute the coarser levels
+    */
+    pub fn build(
+        samples: Vec<D>,
+        dims: [usize; 3],
+        origin: [C; 3],
+        spacing: C,
+        levels: usize,
+        filter: DownsampleFilter<D>,
+    ) -> Result<Self, PyramidError> {
+        let expected = dims[0] * dims[1] * dims[2];
+        if samples.len() != expected {
+            return Err(PyramidError::SamplesCount {
+                expected,
+                actual: samples.len(),
+            });
+        }
+        let mut built = vec![Level::new(dims, samples)];
+        while built.len() < levels {
+            let finer = built.last().unwrap();
+            if finer.dims.iter().any(|d| *d < 3) {
+                return Err(PyramidError::TooManyLevels);
+            }
+            built.push(finer.downsample(filter));
+        }
+        Ok(Self {
+            origin,
+            spacing,
+            levels: built,
+        })
+    }
+
+    /// Number of levels
+    pub fn levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Number of samples in each direction for a level
+    pub fn level_dims(&self, level: usize) -> [usize; 3] {
+        self.levels[level].dims
+    }
+
+    /// Sample of a level. Out of the grid indices are clamped to its border
+    pub fn sample(&self, level: usize, index: [isize; 3]) -> D {
+        self.levels[level].sample(index)
+    }
+
+    /**
+    A [VoxelSource] for a block whose cells are exactly the cells between the samples of `level`
+    (the block's cell size must be the level's spacing, and its base must be on a sample)
+
+    [VoxelSource]: crate::voxel_source::VoxelSource
+    */
+    pub fn source(
+        &self,
+        level: usize,
+        block: &Block<C>,
+    ) -> Result<PyramidVoxelSource<'_, D, C>, PyramidError> {
+        if level >= self.levels.len() {
+            return Err(PyramidError::NoSuchLevel);
+        }
+        let level_spacing = self.spacing * C::from_ratio(1 << level, 1);
+        let cell_size = block.dims.size * C::from_ratio(1, block.subdivisions);
+        if to_whole(cell_size / level_spacing) != Some(1) {
+            return Err(PyramidError::NotAligned);
+        }
+        let mut base = [0isize; 3];
+        for (axis, axis_base) in base.iter_mut().enumerate() {
+            *axis_base = to_whole((block.dims.base[axis] - self.origin[axis]) / level_spacing)
+                .ok_or(PyramidError::NotAligned)?;
+            let end = *axis_base + block.subdivisions as isize;
+            if *axis_base < 0 || end >= self.levels[level].dims[axis] as isize {
+                return Err(PyramidError::OutOfBounds);
+            }
+        }
+        Ok(PyramidVoxelSource {
+            pyramid: self,
+            level,
+            base,
+            subdivisions: block.subdivisions,
+        })
+    }
+}
+
+/// [VoxelSource](crate::voxel_source::VoxelSource) for one block of a [VoxelPyramid] level, obtained with [VoxelPyramid::source]
+pub struct PyramidVoxelSource<'p, D, C>
+where
+    D: Density,
+    C: Coordinate,
+{
+    pyramid: &'p VoxelPyramid<D, C>,
+    level: usize,
+    base: [isize; 3],
+    subdivisions: usize,
+}
+
+impl<D, C> VoxelSource<D> for PyramidVoxelSource<'_, D, C>
+where
+    D: Density,
+    C: Coordinate,
+{
+    fn get_regular_voxel(&mut self, voxel_index: &RegularVoxelIndex) -> D {
+        self.pyramid.sample(
+            self.level,
+            [
+                self.base[0] + voxel_index.x,
+                self.base[1] + voxel_index.y,
+                self.base[2] + voxel_index.z,
+            ],
+        )
+    }
+
+    fn get_transition_voxel(&mut self, index: &HighResolutionVoxelIndex) -> D {
+        let double = index.to_double_resolution_index(self.subdivisions);
+        let double = [
+            2 * self.base[0] + double.x,
+            2 * self.base[1] + double.y,
+            2 * self.base[2] + double.z,
+        ];
+        if self.level > 0 {
+            self.pyramid.sample(self.level - 1, double)
+        } else {
+            // No finer level: use the coinciding sample, or the one just below
+            self.pyramid.sample(
+                0,
+                [
+                    double[0].div_euclid(2),
+                    double[1].div_euclid(2),
+                    double[2].div_euclid(2),
+                ],
+            )
+        }
+    }
+
+    fn density_bounds(&self) -> Option<(D, D)> {
+        let n = self.subdivisions as isize;
+        let high = [self.base[0] + n, self.base[1] + n, self.base[2] + n];
+        let regular = self.pyramid.levels[self.level].bounds(self.base, high);
+        if self.level == 0 {
+            return regular;
+        }
+        // Transition voxels are read from the finer level, on the faces of the block
+        let finer = &self.pyramid.levels[self.level - 1];
+        let (finer_low, finer_high) = (self.base.map(|i| 2 * i), high.map(|i| 2 * i));
+        let faces = (0..3).flat_map(|axis| {
+            [finer_low[axis], finer_high[axis]].map(|w| {
+                let (mut low, mut high) = (finer_low, finer_high);
+                low[axis] = w;
+                high[axis] = w;
+                finer.bounds(low, high)
+            })
+        });
+        density_bounds_of(
+            regular
+                .into_iter()
+                .chain(faces.flatten())
+                .flat_map(|(min, max)| [min, max]),
+        )
+    }
+}
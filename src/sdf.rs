@@ -0,0 +1,781 @@
+/*!
+Signed distance field primitives and combinators, usable as [DataField]s
+
+Shapes are described by their signed distance (negative inside), and its gradient (a unit vector pointing out of the shape).
+As [DataField]s, they give a density of `-distance`, so they should be extracted with a threshold of 0.
+They also give the exact density gradient, so the extraction does not need to estimate it from neighbouring voxels.
+
+Every provided type implements [DataField] directly. For your own [Sdf] implementations, wrap them in a [SdfField].
+
+```
+# use transvoxel::prelude::*;
+# use transvoxel::generic_mesh::GenericMeshBuilder;
+use transvoxel::sdf::*;
+let shape = Sphere::new([5.0, 5.0, 5.0], 3.0)
+    .smooth_union(Cuboid::new([5.0, 2.0, 5.0], [4.0, 1.0, 4.0]), 1.0)
+    .subtraction(Cylinder::new([5.0, 5.0, 5.0], 1.0, 10.0));
+let block = Block::from([0.0, 0.0, 0.0], 10.0, 16);
+let mesh = extract_from_field(shape, &block, 0.0, transition_sides::no_side(), GenericMeshBuilder::new()).build();
+assert!(mesh.num_tris() > 0);
+```
+
+All shapes and combinators here are 1-Lipschitz (the distance changes at most as fast as the position), which is used to
+give conservative [density bounds](DataField::density_bounds), so that extraction can skip blocks far from the surface.
+
+[DataField]: crate::voxel_source::DataField
+*/
+
+use crate::traits::{Coordinate, Density};
+use crate::voxel_source::DataField;
+
+/// A vector, or a point
+pub type Vec3<C> = [C; 3];
+
+/**
+A signed distance field
+*/
+pub trait Sdf<C: Coordinate> {
+    /// Signed distance from `p` to the surface: negative inside the shape, positive outside
+    fn distance(&self, p: Vec3<C>) -> C;
+
+    /// Gradient of the distance at `p`: normally a unit vector, pointing out of the shape
+    fn gradient(&self, p: Vec3<C>) -> Vec3<C>;
+
+    /**
+    Conservative (min, max) bounds of the distance within the box from `min` to `max`.
+    The default implementation relies on the field being 1-Lipschitz
+    */
+    fn distance_bounds(&self, min: Vec3<C>, max: Vec3<C>) -> (C, C) {
+        let half = C::from(0.5).unwrap();
+        let center = scale(add(min, max), half);
+        let radius = length(scale(sub(max, min), half));
+        let d = self.distance(center);
+        (d - radius, d + radius)
+    }
+
+    /// Union of this shape and another
+    fn union<B: Sdf<C>>(self, other: B) -> Union<Self, B>
+    where
+        Self: Sized,
+    {
+        Union { a: self, b: other }
+    }
+
+    /// Intersection of this shape and another
+    fn intersection<B: Sdf<C>>(self, other: B) -> Intersection<Self, B>
+    where
+        Self: Sized,
+    {
+        Intersection { a: self, b: other }
+    }
+
+    /// This shape, minus another
+    fn subtraction<B: Sdf<C>>(self, other: B) -> Subtraction<Self, B>
+    where
+        Self: Sized,
+    {
+        Subtraction { a: self, b: other }
+    }
+
+    /// Union of this shape and another, blended over a distance of about `k`
+    fn smooth_union<B: Sdf<C>>(self, other: B, k: C) -> SmoothUnion<Self, B, C>
+    where
+        Self: Sized,
+    {
+        SmoothUnion {
+            a: self,
+            b: other,
+            k,
+        }
+    }
+
+    /// This shape, moved by `offset`
+    fn translate(self, offset: Vec3<C>) -> Translate<Self, C>
+    where
+        Self: Sized,
+    {
+        Translate { sdf: self, offset }
+    }
+
+    /// This shape, rotated around the origin by `angle` radians around `axis`
+    fn rotate(self, axis: Vec3<C>, angle: C) -> Rotate<Self, C>
+    where
+        Self: Sized,
+    {
+        Rotate::around_axis(self, axis, angle)
+    }
+
+    /// This shape, scaled by `factor` from the origin
+    fn scale(self, factor: C) -> Scale<Self, C>
+    where
+        Self: Sized,
+    {
+        Scale { sdf: self, factor }
+    }
+
+    /// This shape, repeated infinitely with the given period in each direction (0 for no repetition along an axis)
+    fn repeat(self, period: Vec3<C>) -> Repeat<Self, C>
+    where
+        Self: Sized,
+    {
+        Repeat { sdf: self, period }
+    }
+}
+
+/// Sphere
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sphere<C: Coordinate> {
+    /// Center
+    pub center: Vec3<C>,
+    /// Radius
+    pub radius: C,
+}
+
+impl<C: Coordinate> Sphere<C> {
+    /// Shorthand constructor
+    pub fn new(center: Vec3<C>, radius: C) -> Self {
+        Self { center, radius }
+    }
+}
+
+impl<C: Coordinate> Sdf<C> for Sphere<C> {
+    fn distance(&self, p: Vec3<C>) -> C {
+        length(sub(p, self.center)) - self.radius
+    }
+
+    fn gradient(&self, p: Vec3<C>) -> Vec3<C> {
+        normalize(sub(p, self.center))
+    }
+}
+
+/// Axis-aligned box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cuboid<C: Coordinate> {
+    /// Center
+    pub center: Vec3<C>,
+    /// Half of the size along each axis
+    pub half_extents: Vec3<C>,
+}
+
+impl<C: Coordinate> Cuboid<C> {
+    /// Shorthand constructor
+    pub fn new(center: Vec3<C>, half_extents: Vec3<C>) -> Self {
+        Self {
+            center,
+            half_extents,
+        }
+    }
+}
+
+impl<C: Coordinate> Sdf<C> for Cuboid<C> {
+    fn distance(&self, p: Vec3<C>) -> C {
+        box_distance(sub(p, self.center), self.half_extents)
+    }
+
+    fn gradient(&self, p: Vec3<C>) -> Vec3<C> {
+        box_gradient(sub(p, self.center), self.half_extents)
+    }
+}
+
+/// Axis-aligned box with rounded edges and corners
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoundedBox<C: Coordinate> {
+    /// Center
+    pub center: Vec3<C>,
+    /// Half of the size along each axis (including the rounding)
+    pub half_extents: Vec3<C>,
+    /// Rounding radius
+    pub radius: C,
+}
+
+impl<C: Coordinate> RoundedBox<C> {
+    /// Shorthand constructor
+    pub fn new(center: Vec3<C>, half_extents: Vec3<C>, radius: C) -> Self {
+        Self {
+            center,
+            half_extents,
+            radius,
+        }
+    }
+
+    fn inner_extents(&self) -> Vec3<C> {
+        let r = self.radius;
+        let h = self.half_extents;
+        [h[0] - r, h[1] - r, h[2] - r]
+    }
+}
+
+impl<C: Coordinate> Sdf<C> for RoundedBox<C> {
+    fn distance(&self, p: Vec3<C>) -> C {
+        box_distance(sub(p, self.center), self.inner_extents()) - self.radius
+    }
+
+    fn gradient(&self, p: Vec3<C>) -> Vec3<C> {
+        box_gradient(sub(p, self.center), self.inner_extents())
+    }
+}
+
+/// Segment with a radius
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capsule<C: Coordinate> {
+    /// One end of the segment
+    pub a: Vec3<C>,
+    /// The other end of the segment
+    pub b: Vec3<C>,
+    /// Radius
+    pub radius: C,
+}
+
+impl<C: Coordinate> Capsule<C> {
+    /// Shorthand constructor
+    pub fn new(a: Vec3<C>, b: Vec3<C>, radius: C) -> Self {
+        Self { a, b, radius }
+    }
+
+    fn offset_from_segment(&self, p: Vec3<C>) -> Vec3<C> {
+        let pa = sub(p, self.a);
+        let ba = sub(self.b, self.a);
+        let ba_squared = dot(ba, ba);
+        let t = if ba_squared > C::zero() {
+            (dot(pa, ba) / ba_squared).max(C::zero()).min(C::one())
+        } else {
+            C::zero()
+        };
+        sub(pa, scale(ba, t))
+    }
+}
+
+impl<C: Coordinate> Sdf<C> for Capsule<C> {
+    fn distance(&self, p: Vec3<C>) -> C {
+        length(self.offset_from_segment(p)) - self.radius
+    }
+
+    fn gradient(&self, p: Vec3<C>) -> Vec3<C> {
+        normalize(self.offset_from_segment(p))
+    }
+}
+
+/// Capped cylinder, along the Y axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cylinder<C: Coordinate> {
+    /// Center
+    pub center: Vec3<C>,
+    /// Radius
+    pub radius: C,
+    /// Half of the height
+    pub half_height: C,
+}
+
+impl<C: Coordinate> Cylinder<C> {
+    /// Shorthand constructor
+    pub fn new(center: Vec3<C>, radius: C, half_height: C) -> Self {
+        Self {
+            center,
+            radius,
+            half_height,
+        }
+    }
+
+    // Distances out of the side and out of the caps, with the directions going out of them
+    fn components(&self, p: Vec3<C>) -> (C, C, Vec3<C>, Vec3<C>) {
+        let q = sub(p, self.center);
+        let radial = [q[0], C::zero(), q[2]];
+        let dr = length(radial) - self.radius;
+        let dy = q[1].abs() - self.half_height;
+        let axial = [C::zero(), q[1].signum(), C::zero()];
+        (dr, dy, normalize(radial), axial)
+    }
+}
+
+impl<C: Coordinate> Sdf<C> for Cylinder<C> {
+    fn distance(&self, p: Vec3<C>) -> C {
+        let (dr, dy, _, _) = self.components(p);
+        let outside = [dr.max(C::zero()), dy.max(C::zero()), C::zero()];
+        dr.max(dy).min(C::zero()) + length(outside)
+    }
+
+    fn gradient(&self, p: Vec3<C>) -> Vec3<C> {
+        let (dr, dy, radial, axial) = self.components(p);
+        if dr > C::zero() && dy > C::zero() {
+            normalize(add(scale(radial, dr), scale(axial, dy)))
+        } else if dr > dy {
+            radial
+        } else {
+            axial
+        }
+    }
+}
+
+/// Torus, around the Y axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Torus<C: Coordinate> {
+    /// Center
+    pub center: Vec3<C>,
+    /// Distance from the center to the center of the tube
+    pub major_radius: C,
+    /// Radius of the tube
+    pub minor_radius: C,
+}
+
+impl<C: Coordinate> Torus<C> {
+    /// Shorthand constructor
+    pub fn new(center: Vec3<C>, major_radius: C, minor_radius: C) -> Self {
+        Self {
+            center,
+            major_radius,
+            minor_radius,
+        }
+    }
+
+    // Vector from the closest point of the tube's center circle (any point of it, on the axis)
+    fn offset_from_circle(&self, p: Vec3<C>) -> Vec3<C> {
+        let q = sub(p, self.center);
+        let radial = [q[0], C::zero(), q[2]];
+        let from_circle = length(radial) - self.major_radius;
+        add(
+            scale(normalize(radial), from_circle),
+            [C::zero(), q[1], C::zero()],
+        )
+    }
+}
+
+impl<C: Coordinate> Sdf<C> for Torus<C> {
+    fn distance(&self, p: Vec3<C>) -> C {
+        let q = sub(p, self.center);
+        let from_circle = length([q[0], C::zero(), q[2]]) - self.major_radius;
+        length([from_circle, q[1], C::zero()]) - self.minor_radius
+    }
+
+    fn gradient(&self, p: Vec3<C>) -> Vec3<C> {
+        normalize(self.offset_from_circle(p))
+    }
+}
+
+/// Infinite plane. The inside is the half-space opposite to the normal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane<C: Coordinate> {
+    /// Unit normal
+    pub normal: Vec3<C>,
+    /// Distance of the plane from the origin, along the normal
+    pub offset: C,
+}
+
+impl<C: Coordinate> Plane<C> {
+    /// Plane with the given normal (normalized here), going through `point`
+    pub fn new(normal: Vec3<C>, point: Vec3<C>) -> Self {
+        let normal = normalize(normal);
+        Self {
+            normal,
+            offset: dot(normal, point),
+        }
+    }
+}
+
+impl<C: Coordinate> Sdf<C> for Plane<C> {
+    fn distance(&self, p: Vec3<C>) -> C {
+        dot(p, self.normal) - self.offset
+    }
+
+    fn gradient(&self, _p: Vec3<C>) -> Vec3<C> {
+        self.normal
+    }
+}
+
+/// Union of two shapes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Union<A, B> {
+    /// First shape
+    pub a: A,
+    /// Second shape
+    pub b: B,
+}
+
+impl<C: Coordinate, A: Sdf<C>, B: Sdf<C>> Sdf<C> for Union<A, B> {
+    fn distance(&self, p: Vec3<C>) -> C {
+        self.a.distance(p).min(self.b.distance(p))
+    }
+
+    fn gradient(&self, p: Vec3<C>) -> Vec3<C> {
+        if self.a.distance(p) < self.b.distance(p) {
+            self.a.gradient(p)
+        } else {
+            self.b.gradient(p)
+        }
+    }
+
+    fn distance_bounds(&self, min: Vec3<C>, max: Vec3<C>) -> (C, C) {
+        let (a_min, a_max) = self.a.distance_bounds(min, max);
+        let (b_min, b_max) = self.b.distance_bounds(min, max);
+        (a_min.min(b_min), a_max.min(b_max))
+    }
+}
+
+/// Intersection of two shapes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Intersection<A, B> {
+    /// First shape
+    pub a: A,
+    /// Second shape
+    pub b: B,
+}
+
+impl<C: Coordinate, A: Sdf<C>, B: Sdf<C>> Sdf<C> for Intersection<A, B> {
+    fn distance(&self, p: Vec3<C>) -> C {
+        self.a.distance(p).max(self.b.distance(p))
+    }
+
+    fn gradient(&self, p: Vec3<C>) -> Vec3<C> {
+        if self.a.distance(p) > self.b.distance(p) {
+            self.a.gradient(p)
+        } else {
+            self.b.gradient(p)
+        }
+    }
+
+    fn distance_bounds(&self, min: Vec3<C>, max: Vec3<C>) -> (C, C) {
+        let (a_min, a_max) = self.a.distance_bounds(min, max);
+        let (b_min, b_max) = self.b.distance_bounds(min, max);
+        (a_min.max(b_min), a_max.max(b_max))
+    }
+}
+
+/// First shape, minus the second one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Subtraction<A, B> {
+    /// Shape to subtract from
+    pub a: A,
+    /// Shape subtracted
+    pub b: B,
+}
+
+impl<C: Coordinate, A: Sdf<C>, B: Sdf<C>> Sdf<C> for Subtraction<A, B> {
+    fn distance(&self, p: Vec3<C>) -> C {
+        self.a.distance(p).max(-self.b.distance(p))
+    }
+
+    fn gradient(&self, p: Vec3<C>) -> Vec3<C> {
+        if self.a.distance(p) > -self.b.distance(p) {
+            self.a.gradient(p)
+        } else {
+            scale(self.b.gradient(p), -C::one())
+        }
+    }
+
+    fn distance_bounds(&self, min: Vec3<C>, max: Vec3<C>) -> (C, C) {
+        let (a_min, a_max) = self.a.distance_bounds(min, max);
+        let (b_min, b_max) = self.b.distance_bounds(min, max);
+        (a_min.max(-b_max), a_max.max(-b_min))
+    }
+}
+
+/// Union of two shapes, blended smoothly (polynomial smooth minimum)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmoothUnion<A, B, C> {
+    /// First shape
+    pub a: A,
+    /// Second shape
+    pub b: B,
+    /// Blending distance
+    pub k: C,
+}
+
+impl<C: Coordinate, A: Sdf<C>, B: Sdf<C>> SmoothUnion<A, B, C> {
+    // Blending factor: 1 for only `a`, 0 for only `b`
+    fn blend(&self, a: C, b: C) -> C {
+        let half = C::from(0.5).unwrap();
+        (half + half * (b - a) / self.k)
+            .max(C::zero())
+            .min(C::one())
+    }
+}
+
+impl<C: Coordinate, A: Sdf<C>, B: Sdf<C>> Sdf<C> for SmoothUnion<A, B, C> {
+    fn distance(&self, p: Vec3<C>) -> C {
+        let a = self.a.distance(p);
+        let b = self.b.distance(p);
+        let h = self.blend(a, b);
+        b + h * (a - b) - self.k * h * (C::one() - h)
+    }
+
+    fn gradient(&self, p: Vec3<C>) -> Vec3<C> {
+        let h = self.blend(self.a.distance(p), self.b.distance(p));
+        // Not normalized: it is shorter than 1 in the blending region
+        add(
+            scale(self.a.gradient(p), h),
+            scale(self.b.gradient(p), C::one() - h),
+        )
+    }
+
+    // The smooth minimum is between min(a, b) - k/4 and min(a, b)
+    fn distance_bounds(&self, min: Vec3<C>, max: Vec3<C>) -> (C, C) {
+        let (a_min, a_max) = self.a.distance_bounds(min, max);
+        let (b_min, b_max) = self.b.distance_bounds(min, max);
+        let quarter = C::from(0.25).unwrap();
+        (a_min.min(b_min) - quarter * self.k, a_max.min(b_max))
+    }
+}
+
+/// A shape moved by an offset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Translate<S, C> {
+    /// The shape
+    pub sdf: S,
+    /// Translation
+    pub offset: Vec3<C>,
+}
+
+impl<C: Coordinate, S: Sdf<C>> Sdf<C> for Translate<S, C> {
+    fn distance(&self, p: Vec3<C>) -> C {
+        self.sdf.distance(sub(p, self.offset))
+    }
+
+    fn gradient(&self, p: Vec3<C>) -> Vec3<C> {
+        self.sdf.gradient(sub(p, self.offset))
+    }
+
+    fn distance_bounds(&self, min: Vec3<C>, max: Vec3<C>) -> (C, C) {
+        self.sdf
+            .distance_bounds(sub(min, self.offset), sub(max, self.offset))
+    }
+}
+
+/// A shape rotated around the origin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rotate<S, C> {
+    /// The shape
+    pub sdf: S,
+    /// Rotation matrix (rows), applied to the shape
+    pub matrix: [Vec3<C>; 3],
+}
+
+impl<C: Coordinate, S: Sdf<C>> Rotate<S, C> {
+    /// Rotation by `angle` radians around `axis` (normalized here), following the right-hand rule
+    pub fn around_axis(sdf: S, axis: Vec3<C>, angle: C) -> Self {
+        let [x, y, z] = normalize(axis);
+        let (s, c) = angle.sin_cos();
+        let t = C::one() - c;
+        let matrix = [
+            [t * x * x + c, t * x * y - s * z, t * x * z + s * y],
+            [t * x * y + s * z, t * y * y + c, t * y * z - s * x],
+            [t * x * z - s * y, t * y * z + s * x, t * z * z + c],
+        ];
+        Self { sdf, matrix }
+    }
+
+    fn to_local(&self, p: Vec3<C>) -> Vec3<C> {
+        let m = &self.matrix;
+        [
+            m[0][0] * p[0] + m[1][0] * p[1] + m[2][0] * p[2],
+            m[0][1] * p[0] + m[1][1] * p[1] + m[2][1] * p[2],
+            m[0][2] * p[0] + m[1][2] * p[1] + m[2][2] * p[2],
+        ]
+    }
+
+    fn to_world(&self, v: Vec3<C>) -> Vec3<C> {
+        let m = &self.matrix;
+        [dot(m[0], v), dot(m[1], v), dot(m[2], v)]
+    }
+}
+
+impl<C: Coordinate, S: Sdf<C>> Sdf<C> for Rotate<S, C> {
+    fn distance(&self, p: Vec3<C>) -> C {
+        self.sdf.distance(self.to_local(p))
+    }
+
+    fn gradient(&self, p: Vec3<C>) -> Vec3<C> {
+        self.to_world(self.sdf.gradient(self.to_local(p)))
+    }
+}
+
+/// A shape scaled (uniformly) from the origin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scale<S, C> {
+    /// The shape
+    pub sdf: S,
+    /// Scaling factor
+    pub factor: C,
+}
+
+impl<C: Coordinate, S: Sdf<C>> Sdf<C> for Scale<S, C> {
+    fn distance(&self, p: Vec3<C>) -> C {
+        self.sdf.distance(scale(p, C::one() / self.factor)) * self.factor
+    }
+
+    fn gradient(&self, p: Vec3<C>) -> Vec3<C> {
+        self.sdf.gradient(scale(p, C::one() / self.factor))
+    }
+}
+
+/**
+A shape repeated infinitely in space.
+
+The shape should fit in one period (centered on the origin), otherwise the distance is not correct anymore
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Repeat<S, C> {
+    /// The shape
+    pub sdf: S,
+    /// Period along each axis. 0 for no repetition along an axis
+    pub period: Vec3<C>,
+}
+
+impl<C: Coordinate, S: Sdf<C>> Repeat<S, C> {
+    fn to_local(&self, p: Vec3<C>) -> Vec3<C> {
+        let mut local = p;
+        for (l, period) in local.iter_mut().zip(self.period.iter()) {
+            if *period > C::zero() {
+                *l = *l - *period * (*l / *period).round();
+            }
+        }
+        local
+    }
+}
+
+impl<C: Coordinate, S: Sdf<C>> Sdf<C> for Repeat<S, C> {
+    fn distance(&self, p: Vec3<C>) -> C {
+        self.sdf.distance(self.to_local(p))
+    }
+
+    fn gradient(&self, p: Vec3<C>) -> Vec3<C> {
+        self.sdf.gradient(self.to_local(p))
+    }
+}
+
+/// Wrapper making a [DataField] out of any [Sdf] (the provided shapes already are [DataField]s)
+///
+/// [DataField]: crate::voxel_source::DataField
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SdfField<S>(pub S);
+
+impl<C: Coordinate, S: Sdf<C>> Sdf<C> for SdfField<S> {
+    fn distance(&self, p: Vec3<C>) -> C {
+        self.0.distance(p)
+    }
+
+    fn gradient(&self, p: Vec3<C>) -> Vec3<C> {
+        self.0.gradient(p)
+    }
+
+    fn distance_bounds(&self, min: Vec3<C>, max: Vec3<C>) -> (C, C) {
+        self.0.distance_bounds(min, max)
+    }
+}
+
+macro_rules! sdf_impl_data_field {
+    ($T:ty, $($G:ident),*) => {
+        sdf_impl_data_field!(@impl $T, $T, $($G),*);
+        // Shared references too, as distances do not need to modify the shape
+        sdf_impl_data_field!(@impl &$T, $T, $($G),*);
+    };
+    (@impl $F:ty, $T:ty, $($G:ident),*) => {
+        impl<$($G),*> DataField<C, C> for $F
+        where
+            C: Coordinate + Density<Float = C>,
+            $T: Sdf<C>,
+        {
+            fn get_data(&mut self, x: C, y: C, z: C) -> C {
+                -self.distance([x, y, z])
+            }
+
+            fn density_bounds(&self, min: [C; 3], max: [C; 3]) -> Option<(C, C)> {
+                let (min_distance, max_distance) = self.distance_bounds(min, max);
+                Some((-max_distance, -min_distance))
+            }
+
+            fn get_gradient(&mut self, x: C, y: C, z: C) -> Option<[C; 3]> {
+                let [gx, gy, gz] = <$T as Sdf<C>>::gradient(self, [x, y, z]);
+                Some([-gx, -gy, -gz])
+            }
+        }
+    };
+}
+
+sdf_impl_data_field!(Sphere<C>, C);
+sdf_impl_data_field!(Cuboid<C>, C);
+sdf_impl_data_field!(RoundedBox<C>, C);
+sdf_impl_data_field!(Capsule<C>, C);
+sdf_impl_data_field!(Cylinder<C>, C);
+sdf_impl_data_field!(Torus<C>, C);
+sdf_impl_data_field!(Plane<C>, C);
+sdf_impl_data_field!(Union<A, B>, C, A, B);
+sdf_impl_data_field!(Intersection<A, B>, C, A, B);
+sdf_impl_data_field!(Subtraction<A, B>, C, A, B);
+sdf_impl_data_field!(SmoothUnion<A, B, C>, C, A, B);
+sdf_impl_data_field!(Translate<S, C>, C, S);
+sdf_impl_data_field!(Rotate<S, C>, C, S);
+sdf_impl_data_field!(Scale<S, C>, C, S);
+sdf_impl_data_field!(Repeat<S, C>, C, S);
+sdf_impl_data_field!(SdfField<S>, C, S);
+
+fn add<C: Coordinate>(a: Vec3<C>, b: Vec3<C>) -> Vec3<C> {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub<C: Coordinate>(a: Vec3<C>, b: Vec3<C>) -> Vec3<C> {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale<C: Coordinate>(a: Vec3<C>, f: C) -> Vec3<C> {
+    [a[0] * f, a[1] * f, a[2] * f]
+}
+
+fn dot<C: Coordinate>(a: Vec3<C>, b: Vec3<C>) -> C {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn length<C: Coordinate>(a: Vec3<C>) -> C {
+    dot(a, a).sqrt()
+}
+
+// Zero for a zero vector
+fn normalize<C: Coordinate>(a: Vec3<C>) -> Vec3<C> {
+    let l = length(a);
+    if l > C::zero() {
+        scale(a, C::one() / l)
+    } else {
+        [C::zero(); 3]
+    }
+}
+
+fn box_distance<C: Coordinate>(q: Vec3<C>, half_extents: Vec3<C>) -> C {
+    let d = [
+        q[0].abs() - half_extents[0],
+        q[1].abs() - half_extents[1],
+        q[2].abs() - half_extents[2],
+    ];
+    let outside = [
+        d[0].max(C::zero()),
+        d[1].max(C::zero()),
+        d[2].max(C::zero()),
+    ];
+    length(outside) + d[0].max(d[1]).max(d[2]).min(C::zero())
+}
+
+fn box_gradient<C: Coordinate>(q: Vec3<C>, half_extents: Vec3<C>) -> Vec3<C> {
+    let d = [
+        q[0].abs() - half_extents[0],
+        q[1].abs() - half_extents[1],
+        q[2].abs() - half_extents[2],
+    ];
+    let signs = [q[0].signum(), q[1].signum(), q[2].signum()];
+    if d.iter().any(|di| *di > C::zero()) {
+        let outside = [
+            d[0].max(C::zero()) * signs[0],
+            d[1].max(C::zero()) * signs[1],
+            d[2].max(C::zero()) * signs[2],
+        ];
+        normalize(outside)
+    } else {
+        // Inside: toward the closest face
+        let axis = if d[0] >= d[1] && d[0] >= d[2] {
+            0
+        } else if d[1] >= d[2] {
+            1
+        } else {
+            2
+        };
+        let mut gradient = [C::zero(); 3];
+        gradient[axis] = signs[axis];
+        gradient
+    }
+}
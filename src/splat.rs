@@ -0,0 +1,261 @@
+/*!
+A [MeshBuilder] computing, for each vertex, blend weights for up to four materials (for texture splatting)
+
+The weights come from the voxels around each vertex:
+ * the two grid points of the edge the vertex lies on, weighted by their proximity to the vertex (they share a total weight of 1)
+ * the other corners of the 4 cells sharing this edge: the 4 neighbours of each grid point of the edge, perpendicularly to it
+   (see [GridPoint::neighbours]). They share a total weight you can configure (0.5 by default), also split by proximity
+
+The material of each voxel is given by a function you provide, which can return `None` for voxels that should not
+contribute (typically air). The 4 materials with the highest total weights are kept, and their weights normalized so that
+they sum to 1 (unless no voxel contributed at all, in which case they are all 0).
+
+The result is a [Mesh] with 8 additional [attributes](crate::generic_mesh::VertexAttribute): the material indices
+([MATERIAL_INDEX_ATTRIBUTES]), and the corresponding weights ([MATERIAL_WEIGHT_ATTRIBUTES]), sorted by decreasing weight.
+Unused slots have material 0 and weight 0.
+
+All these voxels only depend on the edge, not on the cell creating the vertex: a vertex on a face shared by two blocks
+of the same resolution gets the same weights in both blocks.
+
+```rust
+use transvoxel::prelude::*;
+use transvoxel::splat::{SplatMeshBuilder, MATERIAL_WEIGHT_ATTRIBUTES};
+use transvoxel::traits::VoxelData;
+
+#[derive(Clone, Copy, Default)]
+struct Voxel {
+    density: f32,
+    material: u32,
+}
+
+impl VoxelData for Voxel {
+    type Density = f32;
+    fn density(&self) -> f32 {
+        self.density
+    }
+}
+
+let field = |x: f32, y: f32, z: f32| Voxel {
+    density: 4.0 - ((x - 5.0).powi(2) + (y - 5.0).powi(2) + (z - 5.0).powi(2)).sqrt(),
+    material: if x < 5.0 { 1 } else { 2 },
+};
+let block = Block::from([0.0, 0.0, 0.0], 10.0, 10);
+let builder = SplatMeshBuilder::new(|voxel: &Voxel| Some(voxel.material));
+let mesh = extract_from_field(&field, &block, 0.0, transition_sides::no_side(), builder).build();
+
+let first_weights = &mesh.attributes.iter().find(|a| a.name == MATERIAL_WEIGHT_ATTRIBUTES[0]).unwrap().values;
+assert!(first_weights.iter().all(|w| *w >= 0.5 && *w <= 1.0));
+```
+
+[MeshBuilder]: crate::mesh_builder::MeshBuilder
+[GridPoint::neighbours]: crate::mesh_builder::GridPoint::neighbours
+*/
+
+use num::NumCast;
+
+use crate::generic_mesh::{GenericMeshBuilder, Mesh, VertexAttribute};
+use crate::mesh_builder::{GridPoint, MeshBuilder, NormalMode, VertexIndex, VertexPlacement};
+use crate::traits::{Coordinate, DensityFloat, VoxelData};
+
+/// Names of the material index attributes, in decreasing weight order
+pub const MATERIAL_INDEX_ATTRIBUTES: [&str; 4] = [
+    "material_index_0",
+    "material_index_1",
+    "material_index_2",
+    "material_index_3",
+];
+
+/// Names of the material weight attributes, in decreasing weight order
+pub const MATERIAL_WEIGHT_ATTRIBUTES: [&str; 4] = [
+    "material_weight_0",
+    "material_weight_1",
+    "material_weight_2",
+    "material_weight_3",
+];
+
+// Material of a voxel, if it contributes
+type MaterialFn<V> = Box<dyn Fn(&V) -> Option<u32> + Send + Sync>;
+
+/// A MeshBuilder that builds a [Mesh] with material blend weights
+pub struct SplatMeshBuilder<V, F>
+where
+    F: Coordinate,
+{
+    vertices: GenericMeshBuilder<F>,
+    material: MaterialFn<V>,
+    corner_weight: F,
+    material_indices: [Vec<F>; 4],
+    material_weights: [Vec<F>; 4],
+}
+
+impl<V, F> SplatMeshBuilder<V, F>
+where
+    V: VoxelData,
+    F: Coordinate,
+{
+    /// Create a fresh builder, getting the material of voxels with `material`
+    pub fn new(material: impl Fn(&V) -> Option<u32> + Send + Sync + 'static) -> Self {
+        Self {
+            vertices: GenericMeshBuilder::new(),
+            material: Box::new(material),
+            corner_weight: F::from(0.5).unwrap(),
+            material_indices: Default::default(),
+            material_weights: Default::default(),
+        }
+    }
+
+    /// Total weight shared by the other corners of the cells around the edge of each vertex (0.5 by default).
+    /// With 0, only the edge endpoints are used
+    pub fn with_corner_weight(mut self, corner_weight: F) -> Self {
+        self.corner_weight = corner_weight;
+        self
+    }
+
+    /// Choose which normals are produced. With [NormalMode::Face], each triangle gets its own copies of its 3 vertices
+    pub fn with_normal_mode(mut self, normal_mode: NormalMode) -> Self {
+        self.vertices = self.vertices.with_normal_mode(normal_mode);
+        self
+    }
+
+    /// Choose how vertices are placed on cell edges
+    pub fn with_vertex_placement(mut self, vertex_placement: VertexPlacement) -> Self {
+        self.vertices = self.vertices.with_vertex_placement(vertex_placement);
+        self
+    }
+
+    /// Also output secondary positions and near faces masks (see [GenericMeshBuilder::with_secondary_positions])
+    pub fn with_secondary_positions(mut self) -> Self {
+        self.vertices = self.vertices.with_secondary_positions();
+        self
+    }
+
+    /// Build the mesh
+    pub fn build(self) -> Mesh<F> {
+        let face_normals = MeshBuilder::<V, F>::normal_mode(&self.vertices) == NormalMode::Face;
+        let mut mesh = self.vertices.build_indexed();
+        for (name, values) in MATERIAL_INDEX_ATTRIBUTES
+            .iter()
+            .zip(self.material_indices)
+            .chain(MATERIAL_WEIGHT_ATTRIBUTES.iter().zip(self.material_weights))
+        {
+            mesh.attributes.push(VertexAttribute {
+                name: name.to_string(),
+                values,
+            });
+        }
+        if face_normals {
+            mesh.with_face_normals()
+        } else {
+            mesh
+        }
+    }
+
+    fn blend(&mut self, point_a: &GridPoint<V, F>, point_b: &GridPoint<V, F>, interp_toward_b: F) {
+        let mut totals: Vec<(u32, F)> = Vec::with_capacity(4);
+        let mut add = |material: Option<u32>, weight: F| {
+            if let Some(material) = material {
+                match totals.iter_mut().find(|(m, _)| *m == material) {
+                    Some((_, total)) => *total = *total + weight,
+                    None => totals.push((material, weight)),
+                }
+            }
+        };
+        add(
+            (self.material)(&point_a.voxel_data),
+            F::one() - interp_toward_b,
+        );
+        add((self.material)(&point_b.voxel_data), interp_toward_b);
+        if self.corner_weight > F::zero() {
+            let axis = edge_axis(point_a, point_b);
+            let weight = self.corner_weight / F::from(4).unwrap();
+            for (point, proximity) in [
+                (point_a, F::one() - interp_toward_b),
+                (point_b, interp_toward_b),
+            ] {
+                // Skip the neighbours along the edge
+                for (i, voxel) in point.neighbours.iter().enumerate() {
+                    if i / 2 != axis {
+                        add((self.material)(voxel), weight * proximity);
+                    }
+                }
+            }
+        }
+        // Highest weights first, then lowest materials
+        totals.sort_by(|(m1, w1), (m2, w2)| w2.partial_cmp(w1).unwrap().then(m1.cmp(m2)));
+        totals.truncate(4);
+        let sum = totals.iter().fold(F::zero(), |sum, (_, w)| sum + *w);
+        for slot in 0..4 {
+            let (material, weight) = match totals.get(slot) {
+                Some(&(material, weight)) if sum > F::zero() => (material, weight / sum),
+                _ => (0, F::zero()),
+            };
+            self.material_indices[slot].push(F::from(material).unwrap());
+            self.material_weights[slot].push(weight);
+        }
+    }
+}
+
+impl<V, F> MeshBuilder<V, F> for SplatMeshBuilder<V, F>
+where
+    V: VoxelData,
+    F: Coordinate,
+{
+    fn normal_mode(&self) -> NormalMode {
+        MeshBuilder::<V, F>::normal_mode(&self.vertices)
+    }
+
+    fn vertex_placement(&self) -> VertexPlacement {
+        MeshBuilder::<V, F>::vertex_placement(&self.vertices)
+    }
+
+    fn needs_neighbours(&self) -> bool {
+        self.corner_weight > F::zero()
+    }
+
+    fn add_vertex_between(
+        &mut self,
+        point_a: GridPoint<V, F>,
+        point_b: GridPoint<V, F>,
+        interp_toward_b: DensityFloat<V>,
+    ) -> VertexIndex {
+        self.blend(
+            &point_a,
+            &point_b,
+            <F as NumCast>::from(interp_toward_b).unwrap(),
+        );
+        self.vertices
+            .add_vertex_between(point_a, point_b, interp_toward_b)
+    }
+
+    fn add_triangle(
+        &mut self,
+        vertex_1_index: VertexIndex,
+        vertex_2_index: VertexIndex,
+        vertex_3_index: VertexIndex,
+    ) {
+        MeshBuilder::<V, F>::add_triangle(
+            &mut self.vertices,
+            vertex_1_index,
+            vertex_2_index,
+            vertex_3_index,
+        );
+    }
+}
+
+// Axis (0 for x, 1 for y, 2 for z) of the edge between 2 grid points. Unshrunk positions are used, as shrinking can
+// move grid points along the other axes
+fn edge_axis<V: VoxelData, F: Coordinate>(
+    point_a: &GridPoint<V, F>,
+    point_b: &GridPoint<V, F>,
+) -> usize {
+    let a = &point_a.unshrunk_position;
+    let b = &point_b.unshrunk_position;
+    let deltas = [(b.x - a.x).abs(), (b.y - a.y).abs(), (b.z - a.z).abs()];
+    if deltas[0] >= deltas[1] && deltas[0] >= deltas[2] {
+        0
+    } else if deltas[1] >= deltas[2] {
+        1
+    } else {
+        2
+    }
+}
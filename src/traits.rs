@@ -2,7 +2,9 @@
    Traits and storage facilities used by the extraction algorithm
 */
 
-use num::Float;
+use std::fmt::Debug;
+
+use num::{Float, NumCast, Zero};
 
 /**
 Trait that must be implemented for coordinates (x/y/z)
@@ -26,8 +28,18 @@ pub trait Coordinate: Float {
     fn shrink_factor() -> Self;
 }
 
+// The whole number a coordinate is (allowing for rounding errors), or None if it is not one
+pub(crate) fn to_whole<C: Coordinate>(c: C) -> Option<isize> {
+    let rounded = c.round();
+    if (c - rounded).abs() > C::from(1e-4).unwrap() {
+        None
+    } else {
+        <isize as NumCast>::from(rounded)
+    }
+}
+
 /// Trait that a type must implement to be used as voxel data (return values of a [VoxelSource], used as input by the algorithm).
-/// Anything can be stored, but the type has to provide a float density for the algorithm to work with.
+/// Anything can be stored, but the type has to provide a density for the algorithm to work with.
 /// Any additional data stored can be used by a custom [MeshBuilder].
 ///
 /// [MeshBuilder]: crate::mesh_builder::MeshBuilder
@@ -39,6 +51,9 @@ pub trait VoxelData: Default + Clone + Copy {
     fn density(&self) -> Self::Density;
 }
 
+/// The float type used for interpolation factors and gradients, for some voxel data
+pub type DensityFloat<V> = <<V as VoxelData>::Density as Density>::Float;
+
 impl<F: Density> VoxelData for F {
     type Density = Self;
     fn density(&self) -> Self::Density {
@@ -48,42 +63,54 @@ impl<F: Density> VoxelData for F {
 
 /**
 Trait that must be implemented for a type to be used as a density by the algorithm
+
+Densities only need to be ordered: interpolation factors and gradients are computed in an associated float type.
+This allows compact integer densities, like the signed 8 bit distances of Lengyel's reference implementation.
+Implementations are provided for `f32`, `f64` (computing in the same type), and for `i8`, `i16` and `u8` (computing in `f32`).
+`u8` densities typically use an offset (for example 128 for the surface), which then has to be the threshold.
 */
-pub trait Density: Default + Clone + Copy + Float {
+pub trait Density: Default + Clone + Copy + PartialOrd {
+    /// The float type used for interpolation factors and gradients
+    type Float: Float + Default + Debug;
+
     /// How to determine whether a point with a given density is inside or outside the mesh
     fn inside(&self, threshold: &Self) -> bool {
         self > threshold
     }
 
-    /// Epsilon value (use for float comparisons)
-    const EPSILON: Self;
-    /// Value for 0.5
-    const HALF: Self;
-    /// Value for 0
-    const ZERO: Self;
+    /// Conversion to the float type
+    fn to_float(self) -> Self::Float;
+
+    /// Conversion from the float type (rounded and saturated for integer types)
+    fn from_float(f: Self::Float) -> Self;
 
     /// Interpolate to determine where between A and B the threshold is crossed
-    fn interp(a: Self, b: Self, threshold: Self) -> Self {
-        if (b - a).abs() > Self::EPSILON {
+    fn interp(a: Self, b: Self, threshold: Self) -> Self::Float {
+        let (a, b, threshold) = (a.to_float(), b.to_float(), threshold.to_float());
+        if (b - a).abs() > Self::Float::epsilon() {
             (threshold - a) / (b - a)
         } else {
-            Self::HALF
+            <Self::Float as NumCast>::from(0.5).unwrap()
         }
     }
 
     /// Subtraction
-    fn diff(&self, other: Self) -> Self {
-        *self - other
+    fn diff(&self, other: Self) -> Self::Float {
+        self.to_float() - other.to_float()
     }
 
     /// Convert 3 directional gradients of the density to a vector orthogonal to the surface, and pointing out
-    fn gradients_to_normal(x_gradient: Self, y_gradient: Self, z_gradient: Self) -> [Self; 3] {
+    fn gradients_to_normal(
+        x_gradient: Self::Float,
+        y_gradient: Self::Float,
+        z_gradient: Self::Float,
+    ) -> [Self::Float; 3] {
         let norm =
             (x_gradient * x_gradient + y_gradient * y_gradient + z_gradient * z_gradient).sqrt();
-        if norm > Self::EPSILON {
+        if norm > Self::Float::epsilon() {
             [-x_gradient / norm, -y_gradient / norm, -z_gradient / norm]
         } else {
-            [Self::ZERO, Self::ZERO, Self::ZERO]
+            [Self::Float::zero(); 3]
         }
     }
 }
@@ -112,12 +139,39 @@ float_impl_coordinate!(f64);
 macro_rules! float_impl_density {
     ($T:ident) => {
         impl Density for $T {
-            const EPSILON: Self = $T::EPSILON;
-            const HALF: Self = 0.5;
-            const ZERO: Self = 0.0;
+            type Float = $T;
+
+            fn to_float(self) -> Self::Float {
+                self
+            }
+
+            fn from_float(f: Self::Float) -> Self {
+                f
+            }
         }
     };
 }
 
 float_impl_density!(f32);
 float_impl_density!(f64);
+
+macro_rules! integer_impl_density {
+    ($T:ident) => {
+        impl Density for $T {
+            type Float = f32;
+
+            fn to_float(self) -> Self::Float {
+                self as f32
+            }
+
+            fn from_float(f: Self::Float) -> Self {
+                // `as` saturates
+                f.round() as $T
+            }
+        }
+    };
+}
+
+integer_impl_density!(i8);
+integer_impl_density!(i16);
+integer_impl_density!(u8);
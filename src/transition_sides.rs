@@ -46,3 +46,8 @@ pub type TransitionSides = FlagSet<TransitionSide>;
 pub fn no_side() -> TransitionSides {
     FlagSet::<TransitionSide>::default()
 }
+
+/// Set of all the 6 sides
+pub fn all_sides() -> TransitionSides {
+    FlagSet::<TransitionSide>::full()
+}
@@ -0,0 +1,195 @@
+/*!
+World space triplanar texture coordinates and tangent frames
+
+Each vertex gets 3 UV sets, one per projection axis (the surface seen along x, along y and along z), and for each of them
+a tangent compatible with the MikkTSpace convention used by glTF and most PBR pipelines: 4 values x,y,z,w, with the
+bitangent being `w * cross(normal, tangent)`. Blend weights tell how much each projection should contribute.
+
+All of these only depend on the world position and normal of the vertex, so they match exactly across adjacent blocks
+(as long as normals do too: prefer gradient normals, which are computed from the density field).
+
+UVs are not mirrored: the U direction of each projection is flipped depending on which side the normal faces.
+
+```rust
+use transvoxel::prelude::*;
+use transvoxel::generic_mesh::GenericMeshBuilder;
+use transvoxel::triplanar::Triplanar;
+
+let field = |x: f32, y: f32, z: f32| 5.0 - (x * x + y * y + z * z).sqrt();
+let block = Block::from([0.0, 0.0, 0.0], 10.0, 10);
+let mesh = extract_from_field(&field, &block, 0.0, transition_sides::no_side(), GenericMeshBuilder::new()).build();
+
+// One texture repetition every 4 world units
+let triplanar = Triplanar::new(0.25).compute(&mesh);
+assert_eq!(triplanar.uvs[0].len(), 2 * mesh.positions.len() / 3);
+assert_eq!(triplanar.tangents[0].len(), 4 * mesh.positions.len() / 3);
+```
+*/
+
+use num::Float;
+
+use crate::generic_mesh::{averaged_normals, Mesh};
+
+/// Triplanar attributes of one vertex
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TriplanarVertex<F> {
+    /// UVs for the projections along x, y and z
+    pub uvs: [[F; 2]; 3],
+    /// Tangents (x,y,z,w) for the projections along x, y and z
+    pub tangents: [[F; 4]; 3],
+    /// Blend weights of the projections along x, y and z. They sum to 1
+    pub weights: [F; 3],
+}
+
+impl<F: Float> TriplanarVertex<F> {
+    /// The projection with the highest weight (0 for x, 1 for y, 2 for z)
+    pub fn dominant_axis(&self) -> usize {
+        let [x, y, z] = self.weights;
+        if x >= y && x >= z {
+            0
+        } else if y >= z {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+/// Triplanar attributes of all the vertices of a mesh
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriplanarAttributes<F> {
+    /// UVs for the projections along x, y and z. Each consecutive two floats define u,v for one vertex
+    pub uvs: [Vec<F>; 3],
+    /// Tangents for the projections along x, y and z. Each consecutive four floats define x,y,z,w for one vertex
+    pub tangents: [Vec<F>; 3],
+    /// Blend weights. Each consecutive three floats define the weights of the x, y and z projections for one vertex
+    pub weights: Vec<F>,
+}
+
+/// Triplanar mapping settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Triplanar<F> {
+    scale: F,
+    sharpness: F,
+}
+
+impl<F: Float> Triplanar<F> {
+    /// Mapping with UVs being world coordinates multiplied by `scale`, and a blend sharpness of 4
+    pub fn new(scale: F) -> Self {
+        Self {
+            scale,
+            sharpness: F::from(4).unwrap(),
+        }
+    }
+
+    /// Blend weights are proportional to the normal components raised to this power: higher values give narrower transitions between projections
+    pub fn with_sharpness(mut self, sharpness: F) -> Self {
+        self.sharpness = sharpness;
+        self
+    }
+
+    /// Triplanar attributes of a vertex. `normal` must be normalized
+    pub fn project(&self, position: [F; 3], normal: [F; 3]) -> TriplanarVertex<F> {
+        let zero = F::zero();
+        let one = F::one();
+        let mut uvs = [[zero; 2]; 3];
+        let mut tangents = [[zero; 4]; 3];
+        for axis in 0..3 {
+            let sign = if normal[axis] >= zero { one } else { -one };
+            // U and V directions on the projection plane, chosen so that cross(U, V) points along the normal side
+            let (u_dir, v_dir) = match axis {
+                0 => ([zero, zero, -sign], [zero, one, zero]),
+                1 => ([sign, zero, zero], [zero, zero, -one]),
+                _ => ([sign, zero, zero], [zero, one, zero]),
+            };
+            uvs[axis] = [
+                dot(&position, &u_dir) * self.scale,
+                dot(&position, &v_dir) * self.scale,
+            ];
+            tangents[axis] = tangent(&normal, &u_dir, &v_dir);
+        }
+        let powered = normal.map(|n| n.abs().powf(self.sharpness));
+        let total = powered[0] + powered[1] + powered[2];
+        let weights = if total > zero {
+            powered.map(|p| p / total)
+        } else {
+            [one, zero, zero]
+        };
+        TriplanarVertex {
+            uvs,
+            tangents,
+            weights,
+        }
+    }
+
+    /**
+    Triplanar attributes of all the vertices of a mesh.
+    If the mesh has no normals, vertex normals are computed by averaging the normals of the triangles around each vertex
+    (which does not give matching results across blocks)
+    */
+    pub fn compute<R>(&self, mesh: &Mesh<F, R>) -> TriplanarAttributes<F> {
+        let num_vertices = mesh.positions.len() / 3;
+        let averaged;
+        let normals = if mesh.normals.is_empty() {
+            averaged = averaged_normals(mesh);
+            &averaged
+        } else {
+            &mesh.normals
+        };
+        let mut attributes = TriplanarAttributes {
+            uvs: Default::default(),
+            tangents: Default::default(),
+            weights: Vec::with_capacity(3 * num_vertices),
+        };
+        for axis in 0..3 {
+            attributes.uvs[axis].reserve(2 * num_vertices);
+            attributes.tangents[axis].reserve(4 * num_vertices);
+        }
+        for (position, normal) in mesh.positions.chunks_exact(3).zip(normals.chunks_exact(3)) {
+            let vertex = self.project(
+                [position[0], position[1], position[2]],
+                [normal[0], normal[1], normal[2]],
+            );
+            for axis in 0..3 {
+                attributes.uvs[axis].extend_from_slice(&vertex.uvs[axis]);
+                attributes.tangents[axis].extend_from_slice(&vertex.tangents[axis]);
+            }
+            attributes.weights.extend_from_slice(&vertex.weights);
+        }
+        attributes
+    }
+}
+
+fn dot<F: Float>(a: &[F; 3], b: &[F; 3]) -> F {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross<F: Float>(a: &[F; 3], b: &[F; 3]) -> [F; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalized<F: Float>(v: [F; 3]) -> Option<[F; 3]> {
+    let norm = dot(&v, &v).sqrt();
+    if norm > F::epsilon() {
+        Some(v.map(|c| c / norm))
+    } else {
+        None
+    }
+}
+
+// Tangent along U, orthogonal to the normal, with the handedness making the bitangent follow V
+fn tangent<F: Float>(normal: &[F; 3], u_dir: &[F; 3], v_dir: &[F; 3]) -> [F; 4] {
+    // cross(V, N) is U on the projection plane itself. It vanishes when the normal is along V,
+    // where U is already orthogonal to the normal (and this projection has no weight anyway)
+    let t = normalized(cross(v_dir, normal)).unwrap_or(*u_dir);
+    let w = if dot(&cross(normal, &t), v_dir) >= F::zero() {
+        F::one()
+    } else {
+        -F::one()
+    };
+    [t[0], t[1], t[2], w]
+}
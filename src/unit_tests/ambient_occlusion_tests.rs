@@ -0,0 +1,138 @@
+use crate::ambient_occlusion::*;
+use crate::extraction::extract_from_field;
+use crate::generic_mesh::*;
+use crate::mesh_builder::NormalMode;
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::default_block;
+use crate::voxel_source::{Block, DataField};
+use hamcrest2::prelude::*;
+
+// Ground at y=2, with a wall along x=5
+fn ground_and_wall(x: f32, y: f32, _z: f32) -> f32 {
+    let ground = 2.0 - y;
+    let wall = 1.0 - (x - 5.0).abs();
+    ground.max(wall)
+}
+
+fn extract(
+    field: fn(f32, f32, f32) -> f32,
+    block: &Block<f32>,
+    normal_mode: NormalMode,
+) -> Mesh<f32> {
+    extract_from_field(
+        field,
+        block,
+        0.0,
+        no_side(),
+        GenericMeshBuilder::new().with_normal_mode(normal_mode),
+    )
+    .build()
+}
+
+#[test]
+fn open_surfaces_are_not_occluded() {
+    let plane = |_: f32, y: f32, _: f32| 5.0 - y;
+    let block = default_block(10);
+    let mut mesh = extract(plane, &block, NormalMode::Gradient);
+    AmbientOcclusion::new(4.0).apply(&mut plane.clone(), 0.0, &mut mesh);
+    assert_that!(mesh.attributes.len(), equal_to(1));
+    assert_that!(
+        &mesh.attributes[0].name,
+        equal_to(AMBIENT_OCCLUSION_ATTRIBUTE)
+    );
+    assert_that!(
+        mesh.attributes[0].values.len(),
+        equal_to(mesh.positions.len() / 3)
+    );
+    assert_that!(
+        mesh.attributes[0].values.iter().all(|ao| *ao == 1.0),
+        is(true)
+    );
+}
+
+#[test]
+fn corners_are_occluded() {
+    let block = default_block(10);
+    let mesh = extract(ground_and_wall, &block, NormalMode::Gradient);
+    let occlusion = AmbientOcclusion::new(3.0).compute(&mut ground_and_wall, 0.0, &mesh);
+    let mut near_wall = vec![];
+    let mut far_from_wall = vec![];
+    for (i, ao) in occlusion.iter().enumerate() {
+        assert_that!(*ao, greater_than_or_equal_to(0.0));
+        assert_that!(*ao, less_than_or_equal_to(1.0));
+        let (x, y) = (mesh.positions[3 * i], mesh.positions[3 * i + 1]);
+        if y != 2.0 {
+            continue;
+        }
+        let distance_to_wall = (x - 5.0).abs() - 1.0;
+        if distance_to_wall < 1.0 {
+            near_wall.push(*ao);
+        } else if distance_to_wall > 3.0 {
+            far_from_wall.push(*ao);
+        }
+    }
+    assert_that!(near_wall.is_empty(), is(false));
+    assert_that!(far_from_wall.is_empty(), is(false));
+    assert_that!(near_wall.iter().all(|ao| *ao < 0.9), is(true));
+    assert_that!(far_from_wall.iter().all(|ao| *ao == 1.0), is(true));
+    // More samples give the same kind of result, deterministically
+    let settings = AmbientOcclusion::new(3.0).with_rays(64).with_steps(8);
+    let fine = settings.compute(&mut ground_and_wall, 0.0, &mesh);
+    let again = settings.compute(&mut ground_and_wall, 0.0, &mesh);
+    assert_that!(&fine, equal_to(&again));
+    assert_that!(fine.iter().any(|ao| *ao < 0.9), is(true));
+}
+
+// Ground at y=2, with a wall along x=6.5
+fn shifted_wall(x: f32, y: f32, z: f32) -> f32 {
+    ground_and_wall(x - 1.5, y, z)
+}
+
+// Records the sampled points
+struct RecordingField {
+    samples: Vec<[f32; 3]>,
+}
+
+impl DataField<f32, f32> for RecordingField {
+    fn get_data(&mut self, x: f32, y: f32, z: f32) -> f32 {
+        self.samples.push([x, y, z]);
+        shifted_wall(x, y, z)
+    }
+}
+
+#[test]
+fn consistent_across_blocks() {
+    let low = Block::from([0.0, 0.0, 0.0], 5.0, 10);
+    let high = Block::from([5.0, 0.0, 0.0], 5.0, 10);
+    let settings = AmbientOcclusion::new(3.0);
+    let mut field = RecordingField { samples: vec![] };
+    let low_mesh = extract(shifted_wall, &low, NormalMode::Gradient);
+    let high_mesh = extract(shifted_wall, &high, NormalMode::Gradient);
+    let low_occlusion = settings.compute(&mut field, 0.0, &low_mesh);
+    // The world field is sampled, outside of the block
+    assert_that!(field.samples.iter().any(|s| s[0] > 5.0), is(true));
+    let high_occlusion = settings.compute(&mut field, 0.0, &high_mesh);
+    let mut shared = 0;
+    for (i, low_ao) in low_occlusion.iter().enumerate() {
+        if low_mesh.positions[3 * i] != 5.0 {
+            continue;
+        }
+        let j = (0..high_mesh.positions.len() / 3)
+            .find(|j| high_mesh.positions[3 * j..3 * j + 3] == low_mesh.positions[3 * i..3 * i + 3])
+            .unwrap();
+        assert_that!(high_occlusion[j], equal_to(*low_ao));
+        assert_that!(*low_ao, less_than(1.0));
+        shared += 1;
+    }
+    assert_that!(shared, greater_than(0));
+}
+
+#[test]
+fn meshes_without_normals() {
+    let block = default_block(10);
+    let mesh = extract(ground_and_wall, &block, NormalMode::None);
+    let occlusion = AmbientOcclusion::new(3.0).compute(&mut ground_and_wall, 0.0, &mesh);
+    assert_that!(occlusion.len(), equal_to(mesh.positions.len() / 3));
+    assert_that!(occlusion.iter().any(|ao| *ao < 0.9), is(true));
+    assert_that!(occlusion.contains(&1.0), is(true));
+}
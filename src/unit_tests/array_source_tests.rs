@@ -0,0 +1,114 @@
+use crate::array_source::*;
+use crate::extraction::{extract, extract_from_field};
+use crate::generic_mesh::*;
+use crate::traits::Coordinate;
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::{default_block, sphere_density};
+use crate::voxel_source::Block;
+use flagset::Flags;
+use hamcrest2::prelude::*;
+
+fn sphere(x: f32, y: f32, z: f32) -> f32 {
+    sphere_density([2.0, 3.0, 4.0], 6.0, x, y, z)
+}
+
+fn block() -> Block<f32> {
+    default_block(5)
+}
+
+fn regular_samples(block: &Block<f32>) -> Vec<f32> {
+    let n = block.subdivisions as isize;
+    let mut samples = vec![];
+    for x in -1..=n + 1 {
+        for y in -1..=n + 1 {
+            for z in -1..=n + 1 {
+                let at = |i: isize| block.dims.size * f32::from_ratio(i, block.subdivisions);
+                samples.push(sphere(at(x), at(y), at(z)));
+            }
+        }
+    }
+    samples
+}
+
+fn transition_samples(block: &Block<f32>, side: TransitionSide) -> Vec<f32> {
+    let [xs, ys, zs] = transition_samples_ranges(block.subdivisions, side);
+    let at = |i: isize| block.dims.size * (f32::half(i) * f32::from_ratio(1, block.subdivisions));
+    let mut samples = vec![];
+    for x in xs {
+        for y in ys.clone() {
+            for z in zs.clone() {
+                samples.push(sphere(at(x), at(y), at(z)));
+            }
+        }
+    }
+    samples
+}
+
+fn assert_same_as_field(mesh: Mesh<f32>, sides: TransitionSides) {
+    let expected =
+        extract_from_field(&sphere, &block(), 0.0, sides, GenericMeshBuilder::new()).build();
+    assert_that!(mesh.num_tris(), greater_than(0));
+    assert_that!(mesh.positions, equal_to(expected.positions));
+    assert_that!(mesh.normals, equal_to(expected.normals));
+    assert_that!(mesh.triangle_indices, equal_to(expected.triangle_indices));
+}
+
+#[test]
+fn regular_extraction_from_array() {
+    let block = block();
+    let samples = regular_samples(&block);
+    let source = ArrayVoxelSource::new(&block, &samples[..]).unwrap();
+    let mesh = extract(source, &block, 0.0, no_side(), GenericMeshBuilder::new()).build();
+    assert_same_as_field(mesh, no_side());
+}
+
+#[test]
+fn transition_extraction_from_array() {
+    let block = block();
+    let mut source = ArrayVoxelSource::new(&block, regular_samples(&block)).unwrap();
+    for side in TransitionSide::LIST.iter() {
+        source = source
+            .with_transition_samples(*side, transition_samples(&block, *side))
+            .unwrap();
+    }
+    let mesh = extract(source, &block, 0.0, all_sides(), GenericMeshBuilder::new()).build();
+    assert_same_as_field(mesh, all_sides());
+}
+
+#[test]
+fn missing_transition_samples_fall_back_to_regular_ones() {
+    let block = block();
+    let samples = regular_samples(&block);
+    let source = ArrayVoxelSource::new(&block, &samples[..]).unwrap();
+    let mesh = extract(source, &block, 0.0, all_sides(), GenericMeshBuilder::new()).build();
+    let regular_only =
+        extract_from_field(&sphere, &block, 0.0, all_sides(), GenericMeshBuilder::new()).build();
+    assert_that!(mesh.num_tris(), greater_than(0));
+    assert_that!(
+        mesh.num_tris(),
+        less_than_or_equal_to(regular_only.num_tris())
+    );
+}
+
+#[test]
+fn wrong_sizes_are_rejected() {
+    let block = block();
+    let result = ArrayVoxelSource::new(&block, vec![0f32; 10]);
+    assert_that!(
+        result.err(),
+        equal_to(Some(ArrayVoxelSourceError::RegularSamplesCount {
+            expected: 512,
+            actual: 10
+        }))
+    );
+    let source = ArrayVoxelSource::new(&block, vec![0f32; 512]).unwrap();
+    let result = source.with_transition_samples(TransitionSide::HighY, vec![0f32; 3]);
+    assert_that!(
+        result.err(),
+        equal_to(Some(ArrayVoxelSourceError::TransitionSamplesCount {
+            side: TransitionSide::HighY,
+            expected: 507,
+            actual: 3
+        }))
+    );
+}
@@ -0,0 +1,159 @@
+use crate::bevy_support::mesh::BevyMeshBuilder;
+use crate::bevy_support::plugin::*;
+use crate::extraction::extract_from_field;
+use crate::generic_mesh::GenericMeshBuilder;
+use crate::sdf::Sphere;
+use crate::transition_sides::TransitionSide::{LowX, LowY};
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::{default_block, sphere_density};
+use crate::voxel_source::Block;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use hamcrest2::prelude::*;
+
+fn sphere(x: f32, y: f32, z: f32) -> f32 {
+    sphere_density([0.0; 3], 5.0, x, y, z)
+}
+
+fn generic_tris(block: &Block<f32>, sides: TransitionSides) -> usize {
+    extract_from_field(&sphere, block, 0.0, sides, GenericMeshBuilder::new())
+        .build()
+        .num_tris()
+}
+
+fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .init_asset::<Mesh>()
+        .add_plugins(TransvoxelPlugin::new(sphere, 0.0));
+    app
+}
+
+// Update until the entity got a mesh for the given chunk, and return that mesh
+fn wait_for_mesh(app: &mut App, entity: Entity, chunk: Chunk) -> Handle<Mesh> {
+    for _ in 0..1000 {
+        app.update();
+        let e = app.world().entity(entity);
+        if e.get::<ExtractedChunk>() == Some(&ExtractedChunk(chunk)) {
+            return e.get::<Mesh3d>().unwrap().0.clone();
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    panic!("Extraction did not finish");
+}
+
+fn tris(app: &App, handle: &Handle<Mesh>) -> usize {
+    let mesh = app.world().resource::<Assets<Mesh>>().get(handle).unwrap();
+    mesh.indices().unwrap().len() / 3
+}
+
+#[test]
+fn builder_solid_and_wireframe() {
+    let block = default_block(10);
+    let solid = extract_from_field(
+        &sphere,
+        &block,
+        0.0,
+        LowX.into(),
+        BevyMeshBuilder::default(),
+    )
+    .build();
+    assert_that!(
+        solid.primitive_topology(),
+        equal_to(PrimitiveTopology::TriangleList)
+    );
+    let expected = generic_tris(&block, LowX.into());
+    assert_that!(solid.indices().unwrap().len(), equal_to(3 * expected));
+    let wireframe = extract_from_field(
+        &sphere,
+        &block,
+        0.0,
+        LowX.into(),
+        BevyMeshBuilder::default(),
+    )
+    .build_wireframe();
+    assert_that!(
+        wireframe.primitive_topology(),
+        equal_to(PrimitiveTopology::LineList)
+    );
+    match wireframe.indices().unwrap() {
+        Indices::U32(indices) => assert_that!(indices.len(), equal_to(6 * expected)),
+        Indices::U16(_) => panic!("Expected u32 indices"),
+    }
+    assert_that!(wireframe.count_vertices(), equal_to(solid.count_vertices()));
+}
+
+#[test]
+fn plugin_extracts_and_swaps_meshes() {
+    let mut app = headless_app();
+    let block = default_block(8);
+    let chunk = Chunk::new(block, no_side());
+    let entity = app.world_mut().spawn(chunk).id();
+    let first = wait_for_mesh(&mut app, entity, chunk);
+    assert_that!(
+        tris(&app, &first),
+        equal_to(generic_tris(&block, no_side()))
+    );
+
+    // New transition sides
+    let with_sides = Chunk::new(block, LowX | LowY);
+    *app.world_mut().get_mut::<Chunk>(entity).unwrap() = with_sides;
+    let second = wait_for_mesh(&mut app, entity, with_sides);
+    assert_that!(second == first, is(false));
+    assert_that!(
+        tris(&app, &second),
+        equal_to(generic_tris(&block, LowX | LowY))
+    );
+    // The previous mesh was removed
+    assert_that!(app.world().resource::<Assets<Mesh>>().len(), equal_to(1));
+
+    // New subdivisions
+    let finer = Chunk::new(default_block(16), LowX | LowY);
+    *app.world_mut().get_mut::<Chunk>(entity).unwrap() = finer;
+    let third = wait_for_mesh(&mut app, entity, finer);
+    assert_that!(
+        tris(&app, &third),
+        equal_to(generic_tris(&finer.block, LowX | LowY))
+    );
+
+    let events = app.world().resource::<Events<ChunkMeshReady>>();
+    let mut cursor = events.get_cursor();
+    assert_that!(cursor.read(events).all(|e| e.0 == entity), is(true));
+}
+
+#[test]
+fn unchanged_chunks_are_not_extracted_again() {
+    let mut app = headless_app();
+    let chunk = Chunk::new(default_block(8), no_side());
+    let entity = app.world_mut().spawn(chunk).id();
+    let first = wait_for_mesh(&mut app, entity, chunk);
+    // Touch the component without changing it
+    app.world_mut().get_mut::<Chunk>(entity).unwrap().wireframe = false;
+    for _ in 0..5 {
+        app.update();
+    }
+    let current = app
+        .world()
+        .entity(entity)
+        .get::<Mesh3d>()
+        .unwrap()
+        .0
+        .clone();
+    assert_that!(current == first, is(true));
+}
+
+#[test]
+fn plugin_extracts_from_any_data_field() {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .init_asset::<Mesh>()
+        .add_plugins(TransvoxelPlugin::new(Sphere::new([0.0; 3], 5.0), 0.0));
+    let block = default_block(8);
+    let chunk = Chunk::new(block, LowX.into());
+    let entity = app.world_mut().spawn(chunk).id();
+    let mesh = wait_for_mesh(&mut app, entity, chunk);
+    assert_that!(
+        tris(&app, &mesh),
+        equal_to(generic_tris(&block, LowX.into()))
+    );
+}
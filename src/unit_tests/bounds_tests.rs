@@ -0,0 +1,232 @@
+use crate::array_source::*;
+use crate::extraction::*;
+use crate::generic_mesh::*;
+use crate::pyramid::*;
+use crate::traits::Density;
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::{default_block, sphere_density};
+use crate::voxel_source::{Block, DataField, WorldMappingVoxelSource};
+use hamcrest2::prelude::*;
+
+// A plane at x = 5, counting the queries, and giving (exact) bounds
+struct CountingPlane {
+    queries: usize,
+}
+
+impl DataField<f32, f32> for CountingPlane {
+    fn get_data(&mut self, x: f32, _y: f32, _z: f32) -> f32 {
+        self.queries += 1;
+        5.0 - x
+    }
+
+    fn density_bounds(&self, min: [f32; 3], max: [f32; 3]) -> Option<(f32, f32)> {
+        Some((5.0 - max[0], 5.0 - min[0]))
+    }
+}
+
+fn extract_plane(block: &Block<f32>) -> (usize, usize) {
+    let mut field = CountingPlane { queries: 0 };
+    let mesh = extract_from_field(
+        &mut field as &mut dyn DataField<f32, f32>,
+        block,
+        0.0,
+        all_sides(),
+        GenericMeshBuilder::new(),
+    )
+    .build();
+    (mesh.num_tris(), field.queries)
+}
+
+#[test]
+fn empty_and_full_blocks_are_skipped() {
+    let (tris, queries) = extract_plane(&Block::from([10.0, 0.0, 0.0], 10.0, 4));
+    assert_that!(tris, equal_to(0));
+    assert_that!(queries, equal_to(0));
+    let (tris, queries) = extract_plane(&Block::from([-10.0, 0.0, 0.0], 10.0, 4));
+    assert_that!(tris, equal_to(0));
+    assert_that!(queries, equal_to(0));
+    let (tris, queries) = extract_plane(&default_block(4));
+    assert_that!(tris, greater_than(0));
+    assert_that!(queries, greater_than(0));
+}
+
+#[test]
+fn separated_extraction_of_empty_block() {
+    let block = Block::from([10.0, 0.0, 0.0], 10.0, 4);
+    let mut field = CountingPlane { queries: 0 };
+    let sides = TransitionSide::LowX | TransitionSide::HighZ;
+    let separated = extract_separated_from_field(
+        &mut field as &mut dyn DataField<f32, f32>,
+        &block,
+        0.0,
+        sides,
+        GenericMeshBuilder::new,
+    );
+    assert_that!(separated.regular.build().num_tris(), equal_to(0));
+    for side in sides {
+        assert_that!(separated.transitions[side as usize].is_some(), is(true));
+    }
+    assert_that!(field.queries, equal_to(0));
+}
+
+#[test]
+fn block_emptiness() {
+    let block = default_block(4);
+    let source = WorldMappingVoxelSource {
+        field: CountingPlane { queries: 0 },
+        block: &block,
+    };
+    assert_that!(is_block_empty(&source, 0.0), is(false));
+    assert_that!(is_block_empty(&source, 6.0), is(true));
+    assert_that!(is_block_empty(&source, -6.0), is(true));
+    // No bounds: never known to be empty
+    let source = WorldMappingVoxelSource {
+        field: |_x: f32, _y: f32, _z: f32| 1f32,
+        block: &block,
+    };
+    assert_that!(is_block_empty(&source, 0.0), is(false));
+}
+
+// A density where lower values are inside
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+struct Depth(f32);
+
+impl Density for Depth {
+    type Float = f32;
+
+    fn inside(&self, threshold: &Self) -> bool {
+        self < threshold
+    }
+
+    fn to_float(self) -> f32 {
+        self.0
+    }
+
+    fn from_float(f: f32) -> Self {
+        Depth(f)
+    }
+}
+
+// The same plane, with depths increasing along x
+struct DepthPlane;
+
+impl DataField<Depth, f32> for DepthPlane {
+    fn get_data(&mut self, x: f32, _y: f32, _z: f32) -> Depth {
+        Depth(x - 5.0)
+    }
+
+    fn density_bounds(&self, min: [f32; 3], max: [f32; 3]) -> Option<(Depth, Depth)> {
+        Some((Depth(min[0] - 5.0), Depth(max[0] - 5.0)))
+    }
+}
+
+#[test]
+fn block_emptiness_with_inverted_inside() {
+    let block = default_block(4);
+    let source = WorldMappingVoxelSource {
+        field: DepthPlane,
+        block: &block,
+    };
+    assert_that!(is_block_empty(&source, Depth(0.0)), is(false));
+    assert_that!(is_block_empty(&source, Depth(6.0)), is(true));
+    assert_that!(is_block_empty(&source, Depth(-6.0)), is(true));
+    let mesh = extract_from_field(
+        &mut DepthPlane as &mut dyn DataField<Depth, f32>,
+        &block,
+        Depth(0.0),
+        no_side(),
+        GenericMeshBuilder::new(),
+    )
+    .build();
+    assert_that!(mesh.num_tris(), greater_than(0));
+}
+
+#[test]
+fn array_sources_bounds() {
+    let block = default_block(2);
+    // Only the apron is inside
+    let mut samples = vec![-1f32; regular_samples_count(2)];
+    samples[0] = 1.0;
+    let source = ArrayVoxelSource::new(&block, samples.clone()).unwrap();
+    assert_that!(is_block_empty(&source, 0.0), is(true));
+    let source = source
+        .with_transition_samples(TransitionSide::LowX, vec![1.0; transition_samples_count(2)])
+        .unwrap();
+    assert_that!(is_block_empty(&source, 0.0), is(false));
+    // Inside at the block corner
+    samples[(2 + 1) * 25 + (2 + 1) * 5 + 2 + 1] = 1.0;
+    let source = ArrayVoxelSource::new(&block, samples).unwrap();
+    assert_that!(is_block_empty(&source, 0.0), is(false));
+
+    let mut samples = vec![-1f32; 9 * 9 * 9];
+    samples[9 * 9 * 8 + 9 * 8 + 7] = 1.0;
+    let pyramid = VoxelPyramid::build(
+        samples,
+        [9; 3],
+        [0.0; 3],
+        1.0,
+        2,
+        DownsampleFilter::PointSample,
+    )
+    .unwrap();
+    let block = Block::from([4.0, 4.0, 4.0], 4.0, 2);
+    // Only in the finer level, where transition voxels come from
+    assert_that!(
+        is_block_empty(&pyramid.source(1, &block).unwrap(), 0.0),
+        is(false)
+    );
+    let block = Block::from([0.0, 0.0, 0.0], 4.0, 2);
+    assert_that!(
+        is_block_empty(&pyramid.source(1, &block).unwrap(), 0.0),
+        is(true)
+    );
+}
+
+#[test]
+fn pyramid_bounds_are_conservative() {
+    let sphere = |x: f32, y: f32, z: f32| sphere_density([16.0, 13.0, 18.0], 11.0, x, y, z);
+    let mut samples = vec![];
+    for x in 0..33 {
+        for y in 0..33 {
+            for z in 0..33 {
+                samples.push(sphere(x as f32, y as f32, z as f32));
+            }
+        }
+    }
+    let pyramid = VoxelPyramid::build(
+        samples,
+        [33; 3],
+        [0.0; 3],
+        1.0,
+        3,
+        DownsampleFilter::PointSample,
+    )
+    .unwrap();
+    let mut empty_blocks = 0;
+    for level in 1..3 {
+        let cell = (1 << level) as f32;
+        for i in 0..4 {
+            for j in 0..4 {
+                for k in 0..4 {
+                    let base = [
+                        i as f32 * 2.0 * cell,
+                        j as f32 * 2.0 * cell,
+                        k as f32 * 2.0 * cell,
+                    ];
+                    let block = Block::from(base, 3.0 * cell, 3);
+                    let source = match pyramid.source(level, &block) {
+                        Ok(source) => source,
+                        Err(_) => continue,
+                    };
+                    if !is_block_empty(&source, 0.0) {
+                        continue;
+                    }
+                    empty_blocks += 1;
+                    let mesh = extract(source, &block, 0.0, all_sides(), GenericMeshBuilder::new());
+                    assert_that!(mesh.build().num_tris(), equal_to(0));
+                }
+            }
+        }
+    }
+    assert_that!(empty_blocks, greater_than(0));
+}
@@ -0,0 +1,97 @@
+use crate::chunked_world::*;
+use crate::extraction::{extract, extract_from_field};
+use crate::generic_mesh::*;
+use crate::lod::NodeKey;
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::sphere_density;
+use crate::voxel_source::Block;
+use hamcrest2::prelude::*;
+
+const SUBDIVISIONS: usize = 8;
+
+// Crosses the high X face of the level 1 block at the origin
+fn sphere(x: f32, y: f32, z: f32) -> f32 {
+    sphere_density([12.0, 8.0, 8.0], 6.0, x, y, z)
+}
+
+// Level 0 voxels are 1 unit apart, level 1 voxels 2 units
+fn world() -> ChunkedVoxelWorld<f32> {
+    let mut world = ChunkedVoxelWorld::new(SUBDIVISIONS);
+    for (lod, range) in [(0usize, -1..=4), (1, -1..=2)].iter() {
+        let spacing = (1 << lod) as f32;
+        for cx in range.clone() {
+            for cy in range.clone() {
+                for cz in range.clone() {
+                    let n = SUBDIVISIONS as i32;
+                    let mut voxels = vec![];
+                    for x in 0..n {
+                        for y in 0..n {
+                            for z in 0..n {
+                                voxels.push(sphere(
+                                    spacing * (cx * n + x) as f32,
+                                    spacing * (cy * n + y) as f32,
+                                    spacing * (cz * n + z) as f32,
+                                ));
+                            }
+                        }
+                    }
+                    let key = NodeKey::from(*lod, cx, cy, cz);
+                    assert_that!(world.insert_chunk(key, voxels), equal_to(Ok(None)));
+                }
+            }
+        }
+    }
+    world
+}
+
+#[test]
+fn voxels_across_chunks() {
+    let world = world();
+    assert_that!(
+        world.voxel(1, [-1, 3, 16]),
+        equal_to(sphere(-2.0, 6.0, 32.0))
+    );
+    assert_that!(world.voxel(0, [17, 8, 0]), equal_to(sphere(17.0, 8.0, 0.0)));
+    // Not stored
+    assert_that!(world.voxel(1, [100, 0, 0]), equal_to(0.0));
+    assert_that!(world.voxel(2, [0, 0, 0]), equal_to(0.0));
+}
+
+#[test]
+fn extraction_matches_field() {
+    let world = world();
+    let block = Block::from([0.0, 0.0, 0.0], 16.0, SUBDIVISIONS);
+    let sides = TransitionSide::HighX | TransitionSide::LowY;
+    let source = world.source(NodeKey::from(1, 0, 0, 0));
+    let mesh = extract(source, &block, 0.0, sides, GenericMeshBuilder::new()).build();
+    let expected =
+        extract_from_field(&sphere, &block, 0.0, sides, GenericMeshBuilder::new()).build();
+    let without_transitions =
+        extract_from_field(&sphere, &block, 0.0, no_side(), GenericMeshBuilder::new()).build();
+    assert_that!(
+        mesh.num_tris(),
+        greater_than(without_transitions.num_tris())
+    );
+    assert_that!(mesh.positions, equal_to(expected.positions));
+    assert_that!(mesh.normals, equal_to(expected.normals));
+    assert_that!(mesh.triangle_indices, equal_to(expected.triangle_indices));
+}
+
+#[test]
+fn chunk_management() {
+    let mut world = ChunkedVoxelWorld::<f32>::new(2);
+    let key = NodeKey::from(0, 1, 2, 3);
+    assert_that!(
+        world.insert_chunk(key, vec![1.0; 7]),
+        equal_to(Err(ChunkSizeError {
+            expected: 8,
+            actual: 7
+        }))
+    );
+    world.insert_chunk(key, vec![1.0; 8]).unwrap();
+    world.chunk_mut(&key).unwrap()[7] = 2.0;
+    assert_that!(world.voxel(0, [3, 5, 7]), equal_to(2.0));
+    assert_that!(world.keys().count(), equal_to(1));
+    assert_that!(world.remove_chunk(&key).unwrap().len(), equal_to(8));
+    assert_that!(world.chunk(&key).is_none(), is(true));
+}
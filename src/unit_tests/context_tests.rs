@@ -0,0 +1,66 @@
+use crate::extraction::*;
+use crate::generic_mesh::*;
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::{default_block, sphere_density};
+use crate::voxel_source::Block;
+use hamcrest2::prelude::*;
+
+fn sphere(x: f32, y: f32, z: f32) -> f32 {
+    sphere_density([5.0; 3], 6.0, x, y, z)
+}
+
+fn assert_same_mesh(mesh: Mesh<f32>, expected: Mesh<f32>) {
+    assert_that!(mesh.num_tris(), greater_than(0));
+    assert_that!(mesh.positions, equal_to(expected.positions));
+    assert_that!(mesh.normals, equal_to(expected.normals));
+    assert_that!(mesh.triangle_indices, equal_to(expected.triangle_indices));
+}
+
+#[test]
+fn reused_context_gives_same_meshes() {
+    let mut context = ExtractionContext::new();
+    let blocks = [
+        (default_block(8), all_sides()),
+        (default_block(12), no_side()),
+        (
+            Block::from([5.0, 0.0, 0.0], 5.0, 2),
+            TransitionSide::LowX.into(),
+        ),
+        (default_block(8), all_sides()),
+    ];
+    for (block, sides) in blocks.iter() {
+        let mesh = extract_from_field_with_context(
+            &mut context,
+            &sphere,
+            block,
+            0.0,
+            *sides,
+            GenericMeshBuilder::new(),
+        )
+        .build();
+        let expected =
+            extract_from_field(&sphere, block, 0.0, *sides, GenericMeshBuilder::new()).build();
+        assert_same_mesh(mesh, expected);
+    }
+    assert_that!(context.capacity(), equal_to(12));
+}
+
+#[test]
+fn context_with_capacity() {
+    let mut context = ExtractionContext::with_capacity(10);
+    assert_that!(context.capacity(), equal_to(10));
+    let block = default_block(6);
+    let mesh = extract_from_fn_with_context(
+        &mut context,
+        sphere,
+        &block,
+        0.0,
+        all_sides(),
+        GenericMeshBuilder::new(),
+    )
+    .build();
+    let expected =
+        extract_from_fn(sphere, &block, 0.0, all_sides(), GenericMeshBuilder::new()).build();
+    assert_same_mesh(mesh, expected);
+    assert_that!(context.capacity(), equal_to(10));
+}
@@ -0,0 +1,112 @@
+use crate::extraction::{extract_from_field, extract_from_fn};
+use crate::generic_mesh::*;
+use crate::mesh_builder::NormalMode;
+use crate::traits::VoxelData;
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::{default_block, sphere_density};
+use crate::voxel_source::Block;
+use hamcrest2::prelude::*;
+
+fn sphere(x: f64, y: f64, z: f64) -> f64 {
+    sphere_density([5.0; 3], 4.0, x, y, z)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct ColoredVoxel {
+    density: f32,
+    color: u8,
+}
+
+impl VoxelData for ColoredVoxel {
+    type Density = f32;
+
+    fn density(&self) -> Self::Density {
+        self.density
+    }
+}
+
+fn colored_sphere(x: f32, y: f32, z: f32) -> ColoredVoxel {
+    ColoredVoxel {
+        density: sphere(x as f64, y as f64, z as f64) as f32,
+        color: if x < 5.0 { 1 } else { 2 },
+    }
+}
+
+#[test]
+fn f64_coordinates() {
+    let block = Block::from([0.0f64, 0.0, 0.0], 10.0, 10);
+    let mesh: Mesh<f64> =
+        extract_from_fn(sphere, &block, 0.0, all_sides(), GenericMeshBuilder::new()).build();
+    let block_32 = Block::from([0.0f32, 0.0, 0.0], 10.0, 10);
+    let sphere_32 = |x: f32, y: f32, z: f32| sphere(x as f64, y as f64, z as f64) as f32;
+    let mesh_32 = extract_from_fn(
+        sphere_32,
+        &block_32,
+        0.0,
+        all_sides(),
+        GenericMeshBuilder::new(),
+    )
+    .build();
+    assert_that!(&mesh.triangle_indices, equal_to(&mesh_32.triangle_indices));
+    for (p, q) in mesh.positions.iter().zip(mesh_32.positions.iter()) {
+        assert_that!(*p, close_to(*q as f64, 1e-4));
+    }
+}
+
+#[test]
+fn custom_voxel_data() {
+    let block = default_block(10);
+    let mesh = extract_from_fn(
+        colored_sphere,
+        &block,
+        0.0,
+        all_sides(),
+        GenericMeshBuilder::new(),
+    )
+    .build();
+    let reference = extract_from_fn(
+        |x: f32, y: f32, z: f32| colored_sphere(x, y, z).density,
+        &block,
+        0.0,
+        all_sides(),
+        GenericMeshBuilder::new(),
+    )
+    .build();
+    assert_that!(&mesh.positions, equal_to(&reference.positions));
+    assert_that!(&mesh.normals, equal_to(&reference.normals));
+    assert_that!(mesh.voxel_data.is_none(), is(true));
+}
+
+#[test]
+fn recorded_voxel_data() {
+    let block = default_block(10);
+    let builder = GenericMeshBuilder::new().with_voxel_data::<ColoredVoxel>();
+    let mesh = extract_from_field(colored_sphere, &block, 0.0, all_sides(), builder).build();
+    let voxel_data = mesh.voxel_data.as_ref().unwrap();
+    assert_that!(voxel_data.len(), equal_to(mesh.positions.len() / 3));
+    for (data, position) in voxel_data.iter().zip(mesh.positions.chunks_exact(3)) {
+        // One end inside, the other outside
+        assert_that!(data.a.density * data.b.density, less_than_or_equal_to(0.0));
+        assert_that!(data.interp_toward_b, greater_than_or_equal_to(0.0));
+        assert_that!(data.interp_toward_b, less_than_or_equal_to(1.0));
+        if position[0] < 4.5 {
+            assert_that!(data.a.color, equal_to(1));
+        }
+        if position[0] > 5.5 {
+            assert_that!(data.b.color, equal_to(2));
+        }
+    }
+}
+
+#[test]
+fn recorded_voxel_data_with_face_normals() {
+    let block = default_block(10);
+    let builder = GenericMeshBuilder::new()
+        .with_normal_mode(NormalMode::Face)
+        .with_voxel_data::<ColoredVoxel>();
+    let mesh = extract_from_field(colored_sphere, &block, 0.0, no_side(), builder).build();
+    assert_that!(
+        mesh.voxel_data.as_ref().unwrap().len(),
+        equal_to(mesh.triangle_indices.len())
+    );
+}
@@ -0,0 +1,206 @@
+use crate::extraction::{extract_from_field, extract_separated_from_field};
+use crate::generic_mesh::*;
+use crate::gltf_export::*;
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::{default_block, sphere_density};
+use crate::voxel_source::Block;
+use hamcrest2::prelude::*;
+
+fn sphere(x: f32, y: f32, z: f32) -> f32 {
+    sphere_density([0.0; 3], 12.0, x, y, z)
+}
+
+fn extract(block: &Block<f32>) -> Mesh<f32> {
+    extract_from_field(&sphere, block, 0.0, no_side(), GenericMeshBuilder::new()).build()
+}
+
+struct ReadPrimitive {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    colors: Option<Vec<[f32; 4]>>,
+    indices: Vec<u32>,
+    material: Option<usize>,
+    extras: Option<String>,
+}
+
+// For each node: its translation, and its primitives
+fn read_glb(file: &[u8]) -> Vec<([f32; 3], Vec<ReadPrimitive>)> {
+    let gltf = gltf::Gltf::from_slice(file).unwrap();
+    let blob = gltf.blob.clone();
+    gltf.nodes()
+        .map(|node| {
+            let (translation, _, _) = node.transform().decomposed();
+            let primitives = node
+                .mesh()
+                .map(|mesh| {
+                    mesh.primitives()
+                        .map(|primitive| {
+                            let reader = primitive.reader(|_| blob.as_deref());
+                            ReadPrimitive {
+                                positions: reader.read_positions().unwrap().collect(),
+                                normals: reader
+                                    .read_normals()
+                                    .map(|n| n.collect())
+                                    .unwrap_or_default(),
+                                colors: reader.read_colors(0).map(|c| c.into_rgba_f32().collect()),
+                                indices: reader.read_indices().unwrap().into_u32().collect(),
+                                material: primitive.material().index(),
+                                extras: primitive.extras().as_ref().map(|e| e.get().to_string()),
+                            }
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            (translation, primitives)
+        })
+        .collect()
+}
+
+fn assert_same_mesh(primitive: &ReadPrimitive, mesh: &Mesh<f32>, base: [f32; 3]) {
+    assert_that!(
+        primitive.positions.len(),
+        equal_to(mesh.positions.len() / 3)
+    );
+    for (read, expected) in primitive
+        .positions
+        .iter()
+        .zip(mesh.positions.chunks_exact(3))
+    {
+        for axis in 0..3 {
+            assert_that!(read[axis] + base[axis], close_to(expected[axis], 1e-5));
+        }
+    }
+    let normals: Vec<f32> = primitive.normals.iter().flatten().copied().collect();
+    assert_that!(&normals, equal_to(&mesh.normals));
+    let indices: Vec<usize> = primitive.indices.iter().map(|&i| i as usize).collect();
+    assert_that!(&indices, equal_to(&mesh.triangle_indices));
+}
+
+#[test]
+fn blocks_are_nodes() {
+    let blocks = [
+        default_block(8),
+        Block::from([10.0, 0.0, 0.0], 10.0, 8),
+        Block::from([0.0, 0.0, 10.0], 10.0, 4),
+    ];
+    let meshes: Vec<Mesh<f32>> = blocks.iter().map(extract).collect();
+    let mut scene = GlbScene::new();
+    let rock = scene.add_material("rock", [0.5, 0.5, 0.5, 1.0]);
+    for (block, mesh) in blocks.iter().zip(meshes.iter()) {
+        scene.add_block(block, mesh, Some(rock));
+    }
+    let mut file = vec![];
+    scene.write_glb(&mut file).unwrap();
+    let nodes = read_glb(&file);
+    assert_that!(nodes.len(), equal_to(3));
+    for ((translation, primitives), (block, mesh)) in
+        nodes.iter().zip(blocks.iter().zip(meshes.iter()))
+    {
+        assert_that!(*translation, equal_to(block.dims.base));
+        assert_that!(primitives.len(), equal_to(1));
+        assert_same_mesh(&primitives[0], mesh, block.dims.base);
+        assert_that!(primitives[0].material, equal_to(Some(rock)));
+        assert_that!(primitives[0].colors.is_none(), is(true));
+    }
+}
+
+#[test]
+fn per_material_primitives_and_colors() {
+    let block = default_block(8);
+    let mesh = extract(&block);
+    let mut colored = extract(&block);
+    let num_vertices = colored.positions.len() / 3;
+    colored.colors = Some([0.0, 1.0, 0.0, 0.5].repeat(num_vertices));
+    let mut scene = GlbScene::new();
+    let grass = scene.add_material("grass \"green\"", [0.0, 1.0, 0.0, 1.0]);
+    let dirt = scene.add_material("dirt", [0.4, 0.2, 0.0, 1.0]);
+    scene.add_block_primitives(
+        &block,
+        vec![
+            GltfPrimitive {
+                mesh: &colored,
+                material: Some(grass),
+                transition_side: None,
+            },
+            GltfPrimitive {
+                mesh: &mesh,
+                material: Some(dirt),
+                transition_side: None,
+            },
+        ],
+    );
+    let mut file = vec![];
+    scene.write_glb(&mut file).unwrap();
+    let gltf = gltf::Gltf::from_slice(&file).unwrap();
+    let names: Vec<_> = gltf
+        .materials()
+        .map(|m| m.name().unwrap().to_string())
+        .collect();
+    assert_that!(
+        names,
+        equal_to(vec!["grass \"green\"".to_string(), "dirt".to_string()])
+    );
+    let nodes = read_glb(&file);
+    let primitives = &nodes[0].1;
+    assert_that!(primitives.len(), equal_to(2));
+    assert_that!(primitives[0].material, equal_to(Some(grass)));
+    assert_that!(primitives[1].material, equal_to(Some(dirt)));
+    let colors = primitives[0].colors.as_ref().unwrap();
+    assert_that!(colors.len(), equal_to(num_vertices));
+    assert_that!(colors[0], equal_to([0.0, 1.0, 0.0, 0.5]));
+    assert_that!(primitives[1].colors.is_none(), is(true));
+}
+
+#[test]
+fn transition_patches_are_separate_primitives() {
+    let block = default_block(8);
+    let sides = TransitionSide::LowX | TransitionSide::HighX;
+    let small_sphere = |x: f32, y: f32, z: f32| sphere(x, y, z) - 4.0;
+    let separated =
+        extract_separated_from_field(&small_sphere, &block, 0.0, sides, GenericMeshBuilder::new)
+            .map(|builder| builder.build());
+    let mut scene = GlbScene::new();
+    let rock = scene.add_material("rock", [0.5, 0.5, 0.5, 1.0]);
+    let seams = scene.add_material("seams", [1.0, 0.0, 0.0, 1.0]);
+    scene.add_separated(&block, &separated, Some(rock), Some(seams));
+    let mut file = vec![];
+    scene.write_glb(&mut file).unwrap();
+    let nodes = read_glb(&file);
+    let primitives = &nodes[0].1;
+    // The sphere does not reach the high X face: its empty patch is skipped
+    assert_that!(
+        separated
+            .transition(TransitionSide::HighX)
+            .unwrap()
+            .num_tris(),
+        equal_to(0)
+    );
+    assert_that!(primitives.len(), equal_to(2));
+    assert_same_mesh(&primitives[0], &separated.regular, [0.0; 3]);
+    assert_that!(primitives[0].material, equal_to(Some(rock)));
+    assert_that!(primitives[0].extras.is_none(), is(true));
+    assert_same_mesh(
+        &primitives[1],
+        separated.transition(TransitionSide::LowX).unwrap(),
+        [0.0; 3],
+    );
+    assert_that!(primitives[1].material, equal_to(Some(seams)));
+    assert_that!(
+        primitives[1].extras.as_deref(),
+        equal_to(Some(r#"{"transition_side":"LowX"}"#))
+    );
+}
+
+#[test]
+fn empty_blocks() {
+    let far = Block::from([100.0, 0.0, 0.0], 10.0, 8);
+    let mesh = extract(&far);
+    let mut scene = GlbScene::new();
+    scene.add_block(&far, &mesh, None);
+    let mut file = vec![];
+    scene.write_glb(&mut file).unwrap();
+    assert_that!(file.len() % 4, equal_to(0));
+    let nodes = read_glb(&file);
+    assert_that!(nodes.len(), equal_to(1));
+    assert_that!(nodes[0].1.is_empty(), is(true));
+}
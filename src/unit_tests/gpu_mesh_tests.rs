@@ -0,0 +1,221 @@
+use crate::extraction::extract_from_field;
+use crate::generic_mesh::*;
+use crate::gpu_mesh::*;
+use crate::traits::VoxelData;
+use crate::transition_sides::*;
+use crate::triplanar::Triplanar;
+use crate::unit_tests::test_utils::{default_block, sphere};
+use crate::voxel_source::Block;
+use bytemuck::{Pod, PodCastError, Zeroable};
+use hamcrest2::prelude::*;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct MaterialVoxel {
+    density: f32,
+    material: u32,
+}
+
+impl VoxelData for MaterialVoxel {
+    type Density = f32;
+
+    fn density(&self) -> Self::Density {
+        self.density
+    }
+}
+
+fn material_sphere(x: f32, y: f32, z: f32) -> MaterialVoxel {
+    MaterialVoxel {
+        density: sphere(x, y, z),
+        material: if y < 5.0 { 1 } else { 2 },
+    }
+}
+
+// The material of the grid point inside the volume
+fn inner_material(a: &MaterialVoxel, b: &MaterialVoxel, _: f32) -> u32 {
+    if a.density > b.density {
+        a.material
+    } else {
+        b.material
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct FullVertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    color: [u8; 4],
+    material: u32,
+    secondary_positions: [[f32; 3]; 3],
+    near_faces: u32,
+}
+
+unsafe impl Zeroable for FullVertex {}
+unsafe impl Pod for FullVertex {}
+
+fn u16_indices(mesh: &GpuMesh) -> Vec<usize> {
+    match &mesh.indices {
+        IndexBuffer::U16(indices) => indices.iter().map(|&i| i as usize).collect(),
+        IndexBuffer::U32(_) => panic!("Expected u16 indices"),
+    }
+}
+
+#[test]
+fn default_layout_matches_generic_mesh() {
+    let block = default_block(10);
+    let expected =
+        extract_from_field(&sphere, &block, 0.0, all_sides(), GenericMeshBuilder::new()).build();
+    let mesh = extract_from_field(&sphere, &block, 0.0, all_sides(), GpuMeshBuilder::new()).build();
+    assert_that!(mesh.layout, equal_to(VertexLayout::default()));
+    assert_that!(mesh.layout.stride(), equal_to(24));
+    assert_that!(mesh.vertex_count, equal_to(expected.positions.len() / 3));
+    let vertices: &[GpuVertex] = mesh.vertices().unwrap();
+    let positions: Vec<f32> = vertices.iter().flat_map(|v| v.position).collect();
+    let normals: Vec<f32> = vertices.iter().flat_map(|v| v.normal).collect();
+    assert_that!(positions, equal_to(expected.positions));
+    assert_that!(normals, equal_to(expected.normals));
+    assert_that!(u16_indices(&mesh), equal_to(expected.triangle_indices));
+    assert_that!(
+        mesh.indices.as_bytes().len(),
+        equal_to(2 * mesh.indices.len())
+    );
+}
+
+#[test]
+fn without_normals() {
+    let block = default_block(10);
+    let builder = GpuMeshBuilder::new().with_normals(false);
+    let mesh = extract_from_field(&sphere, &block, 0.0, no_side(), builder).build();
+    assert_that!(mesh.layout.stride(), equal_to(12));
+    assert_that!(mesh.layout.normal_offset(), equal_to(None));
+    let positions: &[[f32; 3]] = mesh.vertices().unwrap();
+    assert_that!(positions.len(), equal_to(mesh.vertex_count));
+    assert_that!(
+        mesh.vertices::<GpuVertex>().err(),
+        equal_to(Some(PodCastError::SizeMismatch))
+    );
+}
+
+#[test]
+fn all_attributes() {
+    let block = Block::from([2.0, 2.0, 2.0], 10.0, 10);
+    let expected = extract_from_field(
+        &sphere,
+        &block,
+        0.0,
+        all_sides(),
+        GenericMeshBuilder::new().with_secondary_positions(),
+    )
+    .build();
+    let builder = GpuMeshBuilder::new()
+        .with_color(|a: &MaterialVoxel, b: &MaterialVoxel, _| {
+            [a.material as u8, b.material as u8, 0, 255]
+        })
+        .with_material(inner_material)
+        .with_secondary_positions();
+    let layout = builder.layout();
+    assert_that!(layout.normal_offset(), equal_to(Some(12)));
+    assert_that!(layout.color_offset(), equal_to(Some(24)));
+    assert_that!(layout.material_offset(), equal_to(Some(28)));
+    assert_that!(layout.secondary_position_offset(), equal_to(Some(32)));
+    assert_that!(layout.stride(), equal_to(72));
+    let mesh = extract_from_field(&material_sphere, &block, 0.0, all_sides(), builder).build();
+    let vertices: &[FullVertex] = mesh.vertices().unwrap();
+    let secondary_positions = expected.secondary_positions.unwrap();
+    let near_face_mask = expected.near_face_mask.unwrap();
+    let mut materials = [0; 3];
+    for (i, vertex) in vertices.iter().enumerate() {
+        assert_that!(
+            vertex.position.to_vec(),
+            equal_to(expected.positions[3 * i..3 * i + 3].to_vec())
+        );
+        assert_that!(
+            vertex.secondary_positions.concat(),
+            equal_to(secondary_positions[9 * i..9 * i + 9].to_vec())
+        );
+        let near_faces = near_face_mask[2 * i] as u32 | (near_face_mask[2 * i + 1] as u32) << 8;
+        assert_that!(vertex.near_faces, equal_to(near_faces));
+        assert_that!(vertex.color[3], equal_to(255));
+        let expected_material = if vertex.position[1] < 4.0 {
+            1
+        } else if vertex.position[1] > 6.0 {
+            2
+        } else {
+            vertex.material
+        };
+        assert_that!(vertex.material, equal_to(expected_material));
+        materials[vertex.material as usize] += 1;
+    }
+    assert_that!(materials[1], greater_than(0));
+    assert_that!(materials[2], greater_than(0));
+}
+
+#[test]
+fn big_meshes_use_u32_indices() {
+    let block = Block::from([0.0, 0.0, 0.0], 40.0, 80);
+    let gyroid = |x: f32, y: f32, z: f32| x.sin() * y.cos() + y.sin() * z.cos() + z.sin() * x.cos();
+    let generic =
+        extract_from_field(&gyroid, &block, 0.0, no_side(), GenericMeshBuilder::new()).build();
+    let mesh = extract_from_field(&gyroid, &block, 0.0, no_side(), GpuMeshBuilder::new()).build();
+    assert_that!(mesh.vertex_count, greater_than(u16::MAX as usize + 1));
+    match &mesh.indices {
+        IndexBuffer::U32(indices) => {
+            let indices: Vec<usize> = indices.iter().map(|&i| i as usize).collect();
+            assert_that!(indices, equal_to(generic.triangle_indices));
+        }
+        IndexBuffer::U16(_) => panic!("Expected u32 indices"),
+    }
+    assert_that!(
+        mesh.indices.as_bytes().len(),
+        equal_to(4 * mesh.indices.len())
+    );
+}
+
+#[test]
+fn triplanar_attributes() {
+    let block = default_block(10);
+    let triplanar = Triplanar::new(0.5);
+    let expected =
+        extract_from_field(&sphere, &block, 0.0, no_side(), GenericMeshBuilder::new()).build();
+    let builder = GpuMeshBuilder::<f32>::new().with_triplanar(triplanar);
+    let layout = builder.layout();
+    assert_that!(layout.uv_offset(), equal_to(Some(24)));
+    assert_that!(layout.tangent_offset(), equal_to(Some(48)));
+    assert_that!(layout.weights_offset(), equal_to(Some(96)));
+    assert_that!(layout.stride(), equal_to(108));
+    let mesh = extract_from_field(&sphere, &block, 0.0, no_side(), builder).build();
+    let vertices: &[GpuTriplanarVertex] = mesh.vertices().unwrap();
+    for (i, vertex) in vertices.iter().enumerate() {
+        let position = [
+            expected.positions[3 * i],
+            expected.positions[3 * i + 1],
+            expected.positions[3 * i + 2],
+        ];
+        let normal = [
+            expected.normals[3 * i],
+            expected.normals[3 * i + 1],
+            expected.normals[3 * i + 2],
+        ];
+        let projected = triplanar.project(position, normal);
+        assert_that!(vertex.uvs, equal_to(projected.uvs));
+        assert_that!(vertex.tangents, equal_to(projected.tangents));
+        assert_that!(vertex.weights, equal_to(projected.weights));
+    }
+    // Normals are still computed for the tangents when not output
+    let builder = GpuMeshBuilder::<f32>::new()
+        .with_normals(false)
+        .with_triplanar(triplanar);
+    assert_that!(builder.layout().uv_offset(), equal_to(Some(12)));
+    let mesh = extract_from_field(&sphere, &block, 0.0, no_side(), builder).build();
+    let tangents_w: Vec<f32> = mesh
+        .vertex_bytes()
+        .chunks_exact(mesh.layout.stride())
+        .flat_map(|vertex| {
+            (0..3).map(move |axis| {
+                let w = 36 + 16 * axis + 12;
+                f32::from_ne_bytes([vertex[w], vertex[w + 1], vertex[w + 2], vertex[w + 3]])
+            })
+        })
+        .collect();
+    assert_that!(tangents_w.iter().all(|w| *w == 1.0 || *w == -1.0), is(true));
+}
@@ -0,0 +1,84 @@
+use crate::extraction::extract_from_field;
+use crate::generic_mesh::*;
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::{default_block, sphere};
+use crate::voxel_source::DataField;
+use hamcrest2::prelude::*;
+
+// A sphere of radius 4 centered in the block, recording the queried points
+struct RecordingSphere {
+    analytic: bool,
+    queried: Vec<[f32; 3]>,
+}
+
+impl DataField<f32, f32> for RecordingSphere {
+    fn get_data(&mut self, x: f32, y: f32, z: f32) -> f32 {
+        self.queried.push([x, y, z]);
+        sphere(x, y, z)
+    }
+
+    fn get_gradient(&mut self, x: f32, y: f32, z: f32) -> Option<[f32; 3]> {
+        if !self.analytic {
+            return None;
+        }
+        let d = ((x - 5.0) * (x - 5.0) + (y - 5.0) * (y - 5.0) + (z - 5.0) * (z - 5.0)).sqrt();
+        Some([(5.0 - x) / d, (5.0 - y) / d, (5.0 - z) / d])
+    }
+}
+
+fn extract_sphere(analytic: bool) -> (Mesh<f32>, Vec<[f32; 3]>) {
+    let block = default_block(10);
+    let mut field = RecordingSphere {
+        analytic,
+        queried: Vec::new(),
+    };
+    let mesh = extract_from_field(
+        &mut field as &mut dyn DataField<f32, f32>,
+        &block,
+        0.0,
+        all_sides(),
+        GenericMeshBuilder::new(),
+    )
+    .build();
+    (mesh, field.queried)
+}
+
+fn out_of_block(p: &[f32; 3]) -> bool {
+    p.iter().any(|c| *c < 0.0 || *c > 10.0)
+}
+
+#[test]
+fn analytic_gradients_avoid_out_of_block_queries() {
+    let (_, estimated_queries) = extract_sphere(false);
+    assert_that!(estimated_queries.iter().any(out_of_block), is(true));
+    let (_, analytic_queries) = extract_sphere(true);
+    assert_that!(analytic_queries.iter().any(out_of_block), is(false));
+    assert_that!(analytic_queries.len(), less_than(estimated_queries.len()));
+}
+
+#[test]
+fn analytic_gradients_give_the_same_geometry() {
+    let (estimated, _) = extract_sphere(false);
+    let (analytic, _) = extract_sphere(true);
+    assert_that!(&analytic.positions, equal_to(&estimated.positions));
+    assert_that!(
+        &analytic.triangle_indices,
+        equal_to(&estimated.triangle_indices)
+    );
+}
+
+#[test]
+fn analytic_normals_are_close_to_exact() {
+    let (mesh, _) = extract_sphere(true);
+    for (position, normal) in mesh
+        .positions
+        .chunks_exact(3)
+        .zip(mesh.normals.chunks_exact(3))
+    {
+        let radial = [position[0] - 5.0, position[1] - 5.0, position[2] - 5.0];
+        let length = (radial[0] * radial[0] + radial[1] * radial[1] + radial[2] * radial[2]).sqrt();
+        for axis in 0..3 {
+            assert_that!(normal[axis], close_to(radial[axis] / length, 0.05));
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::extraction::extract_from_fn;
+use crate::generic_mesh::*;
+use crate::mesh_builder::VertexPlacement;
+use crate::traits::Density;
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::{default_block, sphere};
+use hamcrest2::prelude::*;
+
+#[test]
+fn integer_conversions() {
+    assert_that!(i8::from_float(300.0), equal_to(127));
+    assert_that!(i8::from_float(-300.0), equal_to(-128));
+    assert_that!(i16::from_float(-2.6), equal_to(-3));
+    assert_that!(u8::from_float(-5.0), equal_to(0));
+    assert_that!(i8::interp(-10, 30, 0), close_to(0.25, 1e-6));
+    assert_that!(u8::interp(138, 98, 128), close_to(0.25, 1e-6));
+    assert_that!(i16::interp(7, 7, 0), close_to(0.5, 1e-6));
+    assert_that!(i8::diff(&-100, 100), close_to(-200.0, 1e-6));
+}
+
+#[test]
+fn i8_densities_match_float_densities() {
+    let block = default_block(10);
+    // 16 steps per unit of distance: the surface position is quantized to 1/16 of a cell
+    let quantized = |x, y, z| i8::from_float(16.0 * sphere(x, y, z));
+    let reference =
+        extract_from_fn(sphere, &block, 0.0, all_sides(), GenericMeshBuilder::new()).build();
+    let mesh =
+        extract_from_fn(quantized, &block, 0, all_sides(), GenericMeshBuilder::new()).build();
+    assert_that!(
+        &mesh.triangle_indices,
+        equal_to(&reference.triangle_indices)
+    );
+    for (p, q) in mesh.positions.iter().zip(reference.positions.iter()) {
+        assert_that!(*p, close_to(*q, 0.05));
+    }
+    for (n, m) in mesh.normals.iter().zip(reference.normals.iter()) {
+        assert_that!(*n, close_to(*m, 0.05));
+    }
+}
+
+#[test]
+fn u8_densities_with_offset() {
+    let block = default_block(10);
+    let offset = |x, y, z| u8::from_float(128.0 + 16.0 * sphere(x, y, z));
+    let reference =
+        extract_from_fn(sphere, &block, 0.0, no_side(), GenericMeshBuilder::new()).build();
+    let mesh = extract_from_fn(offset, &block, 128, no_side(), GenericMeshBuilder::new()).build();
+    assert_that!(
+        &mesh.triangle_indices,
+        equal_to(&reference.triangle_indices)
+    );
+    for (p, q) in mesh.positions.iter().zip(reference.positions.iter()) {
+        assert_that!(*p, close_to(*q, 0.05));
+    }
+}
+
+#[test]
+fn fixed_point_vertex_placement() {
+    let block = default_block(10);
+    let builder = GenericMeshBuilder::new().with_vertex_placement(VertexPlacement::FixedPoint);
+    let mesh = extract_from_fn(sphere, &block, 0.0, no_side(), builder).build();
+    let reference =
+        extract_from_fn(sphere, &block, 0.0, no_side(), GenericMeshBuilder::new()).build();
+    assert_that!(mesh.num_tris(), equal_to(reference.num_tris()));
+    // Cells are 1 unit wide: all coordinates fall on multiples of 1/256
+    for (p, q) in mesh.positions.iter().zip(reference.positions.iter()) {
+        assert_that!((*p * 256.0).fract(), close_to(0.0, 1e-3));
+        assert_that!(*p, close_to(*q, 1.0 / 512.0 + 1e-4));
+    }
+}
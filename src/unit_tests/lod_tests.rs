@@ -0,0 +1,141 @@
+use crate::lod::*;
+use crate::transition_sides::*;
+use flagset::Flags;
+use hamcrest2::prelude::*;
+
+fn settings() -> LodSettings<f32> {
+    LodSettings {
+        origin: [0.0, 0.0, 0.0],
+        leaf_size: 10.0,
+        subdivisions: 8,
+        lod_distances: vec![15.0, 30.0, 60.0],
+        roots: [2, 1, 2],
+    }
+}
+
+// The leaf covering the given finest-level position
+fn leaf_at(octree: &LodOctree<f32>, x: i32, y: i32, z: i32) -> Option<NodeKey> {
+    let mut key = NodeKey::from(0, x, y, z);
+    while key.lod <= octree.root_lod() {
+        if octree.transition_sides(&key).is_some() {
+            return Some(key);
+        }
+        key = key.parent();
+    }
+    None
+}
+
+// Checks, for every face between two finest-level positions, that the leaves covering them differ
+// by at most one level, and that the coarser has a transition side toward the finer
+fn assert_consistent(octree: &LodOctree<f32>) {
+    let per_root = 1 << octree.root_lod();
+    let extent = [2 * per_root, per_root, 2 * per_root];
+    for x in 0..extent[0] {
+        for y in 0..extent[1] {
+            for z in 0..extent[2] {
+                let leaf = leaf_at(octree, x, y, z).unwrap();
+                for side in TransitionSide::LIST.iter() {
+                    let n = NodeKey::from(0, x, y, z).neighbour(*side);
+                    let outside = (0..3).any(|a| n.coords[a] < 0 || n.coords[a] >= extent[a]);
+                    if outside {
+                        continue;
+                    }
+                    let other = leaf_at(octree, n.coords[0], n.coords[1], n.coords[2]).unwrap();
+                    if other == leaf {
+                        continue;
+                    }
+                    let diff = leaf.lod as isize - other.lod as isize;
+                    assert_that!(diff.abs(), less_than_or_equal_to(1));
+                    let sides = octree.transition_sides(&leaf).unwrap();
+                    assert_that!(sides.contains(*side), equal_to(diff == 1));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn far_camera_selects_roots() {
+    let mut octree = LodOctree::new(settings());
+    let diff = octree.update([1000.0, 1000.0, 1000.0]);
+    assert_that!(diff.is_empty(), is(true));
+    let blocks: Vec<LodBlock<f32>> = octree.blocks().collect();
+    assert_that!(blocks.len(), equal_to(4));
+    for b in blocks.iter() {
+        assert_that!(b.key.lod, equal_to(3));
+        assert_that!(b.block.dims.size, equal_to(80.0));
+        assert_that!(b.block.subdivisions, equal_to(8));
+        assert_that!(b.transition_sides, equal_to(no_side()));
+    }
+}
+
+#[test]
+fn near_camera_refines_and_balances() {
+    let mut octree = LodOctree::new(settings());
+    octree.update([1.0, 1.0, 1.0]);
+    let finest = leaf_at(&octree, 0, 0, 0).unwrap();
+    assert_that!(finest.lod, equal_to(0));
+    // The far corner of the world stays coarse
+    let coarsest = leaf_at(&octree, 15, 7, 15).unwrap();
+    assert_that!(coarsest.lod, equal_to(3));
+    assert_consistent(&octree);
+}
+
+#[test]
+fn balancing_splits_coarse_neighbours() {
+    // Distances that would produce a level 0 block directly next to a level 3 block without balancing
+    let mut s = settings();
+    s.lod_distances = vec![5.0, 5.0, 5.0];
+    let mut octree = LodOctree::new(s);
+    octree.update([60.0, 1.0, 1.0]);
+    assert_that!(leaf_at(&octree, 7, 0, 0).unwrap().lod, equal_to(0));
+    assert_that!(leaf_at(&octree, 8, 0, 0).unwrap().lod, equal_to(1));
+    assert_consistent(&octree);
+}
+
+#[test]
+fn diff_between_camera_positions() {
+    let mut octree = LodOctree::new(settings());
+    let diff = octree.update([1.0, 1.0, 1.0]);
+    let count = octree.blocks().count();
+    // Only the root containing the camera got split, the 3 others are kept
+    assert_that!(diff.removed, equal_to(vec![NodeKey::from(3, 0, 0, 0)]));
+    assert_that!(diff.added.len(), equal_to(count - 3));
+    // The 2 roots sharing a face with the split one now need a transition side
+    assert_that!(diff.re_extract.len(), equal_to(2));
+    // Same position: nothing to do
+    let diff = octree.update([1.0, 1.0, 1.0]);
+    assert_that!(diff.is_empty(), is(true));
+    // Move to the opposite corner
+    let diff = octree.update([159.0, 1.0, 159.0]);
+    assert_consistent(&octree);
+    for removed in diff.removed.iter() {
+        assert_that!(octree.transition_sides(removed), none());
+    }
+    for b in diff.added.iter().chain(diff.re_extract.iter()) {
+        assert_that!(
+            octree.transition_sides(&b.key),
+            equal_to(Some(b.transition_sides))
+        );
+        assert_that!(b.block, equal_to(octree.block(&b.key)));
+    }
+    assert_that!(leaf_at(&octree, 15, 0, 15).unwrap().lod, equal_to(0));
+    assert_that!(leaf_at(&octree, 0, 0, 0).unwrap().lod, greater_than(0));
+}
+
+#[test]
+fn node_key_relations() {
+    let key = NodeKey::from(2, -1, 0, 3);
+    assert_that!(key.parent(), equal_to(NodeKey::from(3, -1, 0, 1)));
+    for child in key.children().iter() {
+        assert_that!(child.parent(), equal_to(key));
+    }
+    assert_that!(
+        key.neighbour(TransitionSide::LowX),
+        equal_to(NodeKey::from(2, -2, 0, 3))
+    );
+    assert_that!(
+        key.neighbour(TransitionSide::HighZ),
+        equal_to(NodeKey::from(2, -1, 0, 4))
+    );
+}
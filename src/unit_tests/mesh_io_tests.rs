@@ -0,0 +1,202 @@
+use crate::extraction::extract_from_fn;
+use crate::generic_mesh::*;
+use crate::mesh_builder::NormalMode;
+use crate::mesh_io::*;
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::{default_block, sphere};
+use crate::voxel_source::Block;
+use hamcrest2::prelude::*;
+
+fn sphere_mesh() -> Mesh<f32> {
+    let block = default_block(6);
+    extract_from_fn(sphere, &block, 0.0, all_sides(), GenericMeshBuilder::new()).build()
+}
+
+// Colors and attributes derived from the positions
+fn decorate(mesh: &mut Mesh<f32>) {
+    let num_vertices = mesh.positions.len() / 3;
+    let mut colors = vec![];
+    for i in 0..num_vertices {
+        let red = if mesh.positions[3 * i] < 5.0 {
+            1.0
+        } else {
+            0.0
+        };
+        colors.extend_from_slice(&[red, 0.2, 1.0 - red, 1.0]);
+    }
+    mesh.colors = Some(colors);
+    mesh.attributes.push(VertexAttribute {
+        name: "height".to_string(),
+        values: (0..num_vertices)
+            .map(|i| mesh.positions[3 * i + 1])
+            .collect(),
+    });
+}
+
+fn assert_colors_close(actual: &[f32], expected: &[f32]) {
+    assert_that!(actual.len(), equal_to(expected.len()));
+    for (a, e) in actual.iter().zip(expected) {
+        assert_that!(*a, close_to(*e, 1.0 / 255.0));
+    }
+}
+
+#[test]
+fn obj_round_trip() {
+    let mut mesh = sphere_mesh();
+    decorate(&mut mesh);
+    let mut file = vec![];
+    write_obj(&mesh, &mut file).unwrap();
+    let read_back = read_obj::<f32>(&file[..]).unwrap();
+    assert_that!(&read_back.positions, equal_to(&mesh.positions));
+    assert_that!(&read_back.normals, equal_to(&mesh.normals));
+    assert_that!(
+        &read_back.triangle_indices,
+        equal_to(&mesh.triangle_indices)
+    );
+    assert_colors_close(&read_back.colors.unwrap(), &mesh.colors.unwrap());
+    // Not supported by the format
+    assert_that!(read_back.attributes.is_empty(), is(true));
+}
+
+#[test]
+fn obj_without_normals_and_with_polygons() {
+    let block = default_block(6);
+    let builder = GenericMeshBuilder::new().with_normal_mode(NormalMode::None);
+    let mesh = extract_from_fn(sphere, &block, 0.0, no_side(), builder).build();
+    let mut file = vec![];
+    write_obj(&mesh, &mut file).unwrap();
+    let read_back = read_obj::<f32>(&file[..]).unwrap();
+    assert_that!(read_back.normals.is_empty(), is(true));
+    assert_that!(read_back.colors.is_none(), is(true));
+    assert_that!(
+        &read_back.triangle_indices,
+        equal_to(&mesh.triangle_indices)
+    );
+    let quad = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nf 1/1 2/1 3/1 -1/1\n";
+    let read_quad = read_obj::<f64>(quad.as_bytes()).unwrap();
+    assert_that!(read_quad.triangle_indices, equal_to(vec![0, 1, 2, 0, 2, 3]));
+}
+
+#[test]
+fn obj_errors() {
+    assert_that!(read_obj::<f32>("v 1 2\n".as_bytes()).is_err(), is(true));
+    assert_that!(read_obj::<f32>("v 1 2 x\n".as_bytes()).is_err(), is(true));
+    let out_of_range = read_obj::<f32>("v 1 2 3\nf 1 2 3\n".as_bytes());
+    assert_that!(matches!(out_of_range, Err(MeshIoError::Parse(_))), is(true));
+}
+
+#[test]
+fn ply_round_trips() {
+    let mut mesh = sphere_mesh();
+    decorate(&mut mesh);
+    for format in [PlyFormat::Ascii, PlyFormat::BinaryLittleEndian] {
+        let mut file = vec![];
+        write_ply(&mesh, format, &mut file).unwrap();
+        let read_back = read_ply::<f32>(&file[..]).unwrap();
+        assert_that!(&read_back.positions, equal_to(&mesh.positions));
+        assert_that!(&read_back.normals, equal_to(&mesh.normals));
+        assert_that!(
+            &read_back.triangle_indices,
+            equal_to(&mesh.triangle_indices)
+        );
+        assert_colors_close(
+            read_back.colors.as_ref().unwrap(),
+            mesh.colors.as_ref().unwrap(),
+        );
+        assert_that!(&read_back.attributes, equal_to(&mesh.attributes));
+    }
+}
+
+#[test]
+fn ply_f64_is_written_as_double() {
+    let block = Block::from([0.0f64, 0.0, 0.0], 10.0, 6);
+    let field = |x: f64, y: f64, z: f64| sphere(x as f32, y as f32, z as f32) as f64 + 0.1;
+    let mesh: Mesh<f64> =
+        extract_from_fn(field, &block, 0.0, no_side(), GenericMeshBuilder::new()).build();
+    let mut file = vec![];
+    write_ply(&mesh, PlyFormat::BinaryLittleEndian, &mut file).unwrap();
+    let header = String::from_utf8_lossy(&file[..200]);
+    assert_that!(header.contains("property double x"), is(true));
+    let read_back = read_ply::<f64>(&file[..]).unwrap();
+    assert_that!(&read_back.positions, equal_to(&mesh.positions));
+    assert_that!(read_back.colors.is_none(), is(true));
+}
+
+#[test]
+fn ply_from_other_tools() {
+    // Big endian, extra element, no alpha, short indices
+    let mut file = b"ply\nformat binary_big_endian 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\nproperty uchar red\nproperty uchar green\nproperty uchar blue\nelement face 1\nproperty list uchar ushort vertex_indices\nelement edge 1\nproperty int vertex1\nproperty int vertex2\nend_header\n".to_vec();
+    for (i, p) in [[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]
+        .iter()
+        .enumerate()
+    {
+        for v in p {
+            file.extend_from_slice(&v.to_be_bytes());
+        }
+        file.extend_from_slice(&[255 * (i == 0) as u8, 0, 0]);
+    }
+    file.push(3);
+    for i in 0..3u16 {
+        file.extend_from_slice(&i.to_be_bytes());
+    }
+    file.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+    let mesh = read_ply::<f32>(&file[..]).unwrap();
+    assert_that!(
+        mesh.positions,
+        equal_to(vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0])
+    );
+    assert_that!(mesh.triangle_indices, equal_to(vec![0, 1, 2]));
+    let colors = mesh.colors.unwrap();
+    assert_that!(colors[0..4].to_vec(), equal_to(vec![1.0, 0.0, 0.0, 1.0]));
+    assert_that!(mesh.normals.is_empty(), is(true));
+}
+
+#[test]
+fn ply_errors() {
+    assert_that!(read_ply::<f32>("obj\n".as_bytes()).is_err(), is(true));
+    let truncated = "ply\nformat ascii 1.0\nelement vertex 2\nproperty float x\nproperty float y\nproperty float z\nend_header\n0 0 0\n";
+    assert_that!(read_ply::<f32>(truncated.as_bytes()).is_err(), is(true));
+    let no_z = "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\nend_header\n0 0\n";
+    assert_that!(read_ply::<f32>(no_z.as_bytes()).is_err(), is(true));
+    // Huge counts must not be trusted for allocations
+    let huge_vertex_count = "ply\nformat ascii 1.0\nelement vertex 99999999999999\nproperty float x\nproperty float y\nproperty float z\nend_header\n0 0 0\n";
+    assert_that!(
+        read_ply::<f32>(huge_vertex_count.as_bytes()).is_err(),
+        is(true)
+    );
+    let mut huge_face = b"ply\nformat binary_little_endian 1.0\nelement vertex 0\nproperty float x\nproperty float y\nproperty float z\nelement face 1\nproperty list uint uint vertex_indices\nend_header\n".to_vec();
+    huge_face.extend_from_slice(&u32::MAX.to_le_bytes());
+    huge_face.extend_from_slice(&[0; 12]);
+    assert_that!(read_ply::<f32>(&huge_face[..]).is_err(), is(true));
+    let empty_elements = "ply\nformat ascii 1.0\nelement nothing 99999999999999\nend_header\n";
+    assert_that!(
+        read_ply::<f32>(empty_elements.as_bytes()).is_err(),
+        is(true)
+    );
+}
+
+#[test]
+fn stl_round_trip() {
+    let mesh = sphere_mesh();
+    let mut file = vec![];
+    write_stl(&mesh, &mut file).unwrap();
+    assert_that!(file.len(), equal_to(84 + 50 * mesh.num_tris()));
+    let read_back = read_stl::<f32>(&file[..]).unwrap();
+    assert_that!(read_back.num_tris(), equal_to(mesh.num_tris()));
+    // Vertices are not shared anymore, but the triangles are the same
+    for (read_tri, tri) in read_back.tris().iter().zip(mesh.tris().iter()) {
+        for (read_vertex, vertex) in read_tri.vertices.iter().zip(tri.vertices.iter()) {
+            assert_that!(read_vertex.position, equal_to(vertex.position));
+        }
+    }
+    // Face normals are unit vectors, pointing roughly like the gradient normals
+    for (i, normal) in read_back.normals.chunks_exact(3).enumerate() {
+        let length = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+        assert_that!(length, close_to(1.0, 1e-5));
+        let original = &mesh.normals[3 * mesh.triangle_indices[i]..][..3];
+        let dot = normal[0] * original[0] + normal[1] * original[1] + normal[2] * original[2];
+        assert_that!(dot, greater_than(0.5));
+    }
+    file.truncate(file.len() - 10);
+    assert_that!(read_stl::<f32>(&file[..]).is_err(), is(true));
+}
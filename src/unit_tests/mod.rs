@@ -1,4 +1,32 @@
 #[macro_use]
 mod test_utils;
 
+mod ambient_occlusion_tests;
+mod array_source_tests;
+#[cfg(feature = "bevy")]
+mod bevy_tests;
+mod bounds_tests;
+mod chunked_world_tests;
+mod context_tests;
+mod generic_mesh_tests;
+mod gltf_export_tests;
+#[cfg(feature = "bytemuck")]
+mod gpu_mesh_tests;
+mod gradient_tests;
+mod integer_density_tests;
+mod lod_tests;
+mod mesh_io_tests;
+mod multi_material_tests;
+#[cfg(feature = "ndarray")]
+mod ndarray_tests;
+mod normals_tests;
+#[cfg(feature = "rayon")]
+mod parallel_tests;
+mod pyramid_tests;
+mod sdf_tests;
+mod separated_tests;
+mod splat_tests;
 mod tests;
+mod triplanar_tests;
+mod validate_tests;
+mod weld_tests;
@@ -0,0 +1,271 @@
+use crate::extraction::extract_from_field;
+use crate::generic_mesh::*;
+use crate::mesh_builder::NormalMode;
+use crate::multi_material::*;
+use crate::traits::{Density, VoxelData};
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::{default_block, sphere};
+use hamcrest2::prelude::*;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Voxel {
+    density: f32,
+    material: u8,
+}
+
+impl VoxelData for Voxel {
+    type Density = f32;
+
+    fn density(&self) -> Self::Density {
+        self.density
+    }
+}
+
+// Sphere with 3 material layers along y
+fn layered_sphere(x: f32, y: f32, z: f32) -> Voxel {
+    Voxel {
+        density: sphere(x, y, z),
+        material: if y < 3.5 {
+            0
+        } else if y < 6.5 {
+            1
+        } else {
+            2
+        },
+    }
+}
+
+fn extract<M: Copy + Ord>(
+    builder: MultiMaterialMeshBuilder<Voxel, f32, M>,
+    sides: TransitionSides,
+) -> MultiMaterialMesh<f32, M> {
+    let block = default_block(10);
+    extract_from_field(&layered_sphere, &block, 0.0, sides, builder).build()
+}
+
+fn sorted_tris(indices: &[usize]) -> Vec<[usize; 3]> {
+    let mut tris: Vec<[usize; 3]> = indices
+        .chunks_exact(3)
+        .map(|t| [t[0], t[1], t[2]])
+        .collect();
+    tris.sort();
+    tris
+}
+
+#[test]
+fn submeshes_partition_the_triangles() {
+    let mesh = extract(
+        MultiMaterialMeshBuilder::new(0.0, |v: &Voxel| v.material),
+        all_sides(),
+    );
+    assert_that!(
+        mesh.materials().collect::<Vec<_>>(),
+        equal_to(vec![0, 1, 2])
+    );
+    let all: Vec<usize> = mesh.submeshes.values().flatten().copied().collect();
+    assert_that!(
+        sorted_tris(&all),
+        equal_to(sorted_tris(&mesh.mesh.triangle_indices))
+    );
+    assert_that!(mesh.submesh(7).is_empty(), is(true));
+    // Same vertices as the generic builder
+    let block = default_block(10);
+    let generic = extract_from_field(
+        &layered_sphere,
+        &block,
+        0.0,
+        all_sides(),
+        GenericMeshBuilder::new(),
+    )
+    .build();
+    assert_that!(&mesh.mesh.positions, equal_to(&generic.positions));
+    assert_that!(&mesh.mesh.normals, equal_to(&generic.normals));
+}
+
+#[test]
+fn vertex_material_is_the_inside_one() {
+    let mesh = extract(
+        MultiMaterialMeshBuilder::new(0.0, |v: &Voxel| v.material),
+        no_side(),
+    );
+    let block = default_block(10);
+    let recorded = extract_from_field(
+        &layered_sphere,
+        &block,
+        0.0,
+        no_side(),
+        GenericMeshBuilder::new().with_voxel_data::<Voxel>(),
+    )
+    .build();
+    let voxel_data = recorded.voxel_data.unwrap();
+    assert_that!(mesh.vertex_materials.len(), equal_to(voxel_data.len()));
+    let mut mixed_edges = 0;
+    for (material, data) in mesh.vertex_materials.iter().zip(voxel_data.iter()) {
+        let inside = if data.a.density > 0.0 { data.a } else { data.b };
+        assert_that!(*material, equal_to(inside.material));
+        if data.a.material != data.b.material {
+            mixed_edges += 1;
+        }
+    }
+    // Some edges cross material boundaries, where the choice matters
+    assert_that!(mixed_edges, greater_than(0));
+}
+
+// A density where lower values are inside
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+struct Depth(f32);
+
+impl Density for Depth {
+    type Float = f32;
+
+    fn inside(&self, threshold: &Self) -> bool {
+        self < threshold
+    }
+
+    fn to_float(self) -> f32 {
+        self.0
+    }
+
+    fn from_float(f: f32) -> Self {
+        Depth(f)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct DepthVoxel {
+    depth: Depth,
+    material: u8,
+}
+
+impl VoxelData for DepthVoxel {
+    type Density = Depth;
+
+    fn density(&self) -> Self::Density {
+        self.depth
+    }
+}
+
+#[test]
+fn inside_follows_the_density_type() {
+    let expected = extract(
+        MultiMaterialMeshBuilder::new(0.0, |v: &Voxel| v.material),
+        no_side(),
+    );
+    // Same volume, with depths decreasing inward
+    let field = |x: f32, y: f32, z: f32| {
+        let voxel = layered_sphere(x, y, z);
+        DepthVoxel {
+            depth: Depth(-voxel.density),
+            material: voxel.material,
+        }
+    };
+    let block = default_block(10);
+    let builder = MultiMaterialMeshBuilder::new(Depth(0.0), |v: &DepthVoxel| v.material);
+    let mesh = extract_from_field(&field, &block, Depth(0.0), no_side(), builder).build();
+    assert_that!(&mesh.vertex_materials, equal_to(&expected.vertex_materials));
+}
+
+#[test]
+fn triangle_votes() {
+    assert_that!(TriangleVote::Majority.choose([1, 2, 2]), equal_to(2));
+    assert_that!(TriangleVote::Majority.choose([2, 1, 2]), equal_to(2));
+    assert_that!(TriangleVote::Majority.choose([3, 1, 2]), equal_to(3));
+    assert_that!(TriangleVote::FirstVertex.choose([3, 1, 1]), equal_to(3));
+    assert_that!(TriangleVote::Lowest.choose([3, 1, 2]), equal_to(1));
+    assert_that!(TriangleVote::Highest.choose([3, 1, 2]), equal_to(3));
+    let majority = extract(
+        MultiMaterialMeshBuilder::new(0.0, |v: &Voxel| v.material),
+        no_side(),
+    );
+    let lowest = extract(
+        MultiMaterialMeshBuilder::new(0.0, |v: &Voxel| v.material).with_vote(TriangleVote::Lowest),
+        no_side(),
+    );
+    let highest = extract(
+        MultiMaterialMeshBuilder::new(0.0, |v: &Voxel| v.material).with_vote(TriangleVote::Highest),
+        no_side(),
+    );
+    for material in 0..3 {
+        let count = |mesh: &MultiMaterialMesh<f32, u8>| mesh.submesh(material).len();
+        assert_that!(count(&lowest), greater_than(0));
+        assert_that!(count(&highest), greater_than(0));
+        assert_that!(count(&majority), greater_than(0));
+    }
+    // Boundary triangles go to the lowest material with Lowest, and to the highest with Highest
+    assert_that!(
+        lowest.submesh(0).len(),
+        greater_than(highest.submesh(0).len())
+    );
+    assert_that!(
+        highest.submesh(2).len(),
+        greater_than(lowest.submesh(2).len())
+    );
+}
+
+#[test]
+fn capturing_material_function() {
+    let palette = ["rock", "dirt", "grass"];
+    let mesh = extract(
+        MultiMaterialMeshBuilder::new(0.0, move |v: &Voxel| palette[v.material as usize])
+            .with_normal_mode(NormalMode::None),
+        no_side(),
+    );
+    assert_that!(
+        mesh.materials().collect::<Vec<_>>(),
+        equal_to(vec!["dirt", "grass", "rock"])
+    );
+    assert_that!(mesh.mesh.normals.is_empty(), is(true));
+}
+
+#[test]
+fn builder_can_be_sent_to_another_thread() {
+    let builder = MultiMaterialMeshBuilder::new(0.0, |v: &Voxel| v.material);
+    let mesh = std::thread::spawn(move || extract(builder, no_side()))
+        .join()
+        .unwrap();
+    assert_that!(mesh.materials().count(), equal_to(3));
+}
+
+#[test]
+fn face_normals() {
+    let shared = extract(
+        MultiMaterialMeshBuilder::new(0.0, |v: &Voxel| v.material),
+        no_side(),
+    );
+    let flat = extract(
+        MultiMaterialMeshBuilder::new(0.0, |v: &Voxel| v.material)
+            .with_normal_mode(NormalMode::Face),
+        no_side(),
+    );
+    let tris = shared.mesh.num_tris();
+    assert_that!(flat.mesh.num_tris(), equal_to(tris));
+    assert_that!(flat.mesh.normals.len(), equal_to(9 * tris));
+    assert_that!(flat.vertex_materials.len(), equal_to(3 * tris));
+    // Same triangles in each submesh, with their own copies of the vertices and their materials
+    let positions = |mesh: &MultiMaterialMesh<f32, u8>, material: u8| -> Vec<f32> {
+        mesh.submesh(material)
+            .iter()
+            .flat_map(|i| mesh.mesh.positions[3 * i..3 * i + 3].to_vec())
+            .collect()
+    };
+    let materials = |mesh: &MultiMaterialMesh<f32, u8>, material: u8| -> Vec<u8> {
+        mesh.submesh(material)
+            .iter()
+            .map(|i| mesh.vertex_materials[*i])
+            .collect()
+    };
+    assert_that!(
+        flat.materials().collect::<Vec<_>>(),
+        equal_to(shared.materials().collect::<Vec<_>>())
+    );
+    for material in shared.materials() {
+        assert_that!(
+            positions(&flat, material),
+            equal_to(positions(&shared, material))
+        );
+        assert_that!(
+            materials(&flat, material),
+            equal_to(materials(&shared, material))
+        );
+    }
+}
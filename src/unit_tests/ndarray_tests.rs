@@ -0,0 +1,114 @@
+use crate::extraction::{extract, extract_from_field, is_block_empty};
+use crate::generic_mesh::*;
+use crate::ndarray_source::*;
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::{default_block, sphere_density};
+use crate::voxel_source::{Block, DataField};
+use hamcrest2::prelude::*;
+use ndarray::Array3;
+
+fn sphere(x: f32, y: f32, z: f32) -> f32 {
+    sphere_density([2.0, 3.0, 4.0], 6.0, x, y, z)
+}
+
+// Samples every 0.5 from -2 to 12
+fn sampled() -> Array3<f32> {
+    Array3::from_shape_fn((29, 29, 29), |(i, j, k)| {
+        sphere(
+            -2.0 + 0.5 * i as f32,
+            -2.0 + 0.5 * j as f32,
+            -2.0 + 0.5 * k as f32,
+        )
+    })
+}
+
+fn assert_close(actual: &[f32], expected: &[f32]) {
+    assert_that!(actual.len(), equal_to(expected.len()));
+    for (a, e) in actual.iter().zip(expected.iter()) {
+        assert_that!((a - e).abs(), less_than(1e-4));
+    }
+}
+
+#[test]
+fn voxel_source_matches_field_extraction() {
+    let data = sampled();
+    let block = default_block(10);
+    let source = NdarrayVoxelSource::new(data.view(), [-2.0, -2.0, -2.0], 0.5, &block).unwrap();
+    let mesh = extract(source, &block, 0.0, all_sides(), GenericMeshBuilder::new()).build();
+    let expected =
+        extract_from_field(&sphere, &block, 0.0, all_sides(), GenericMeshBuilder::new()).build();
+    assert_that!(mesh.num_tris(), greater_than(0));
+    assert_that!(&mesh.triangle_indices, equal_to(&expected.triangle_indices));
+    assert_close(&mesh.positions, &expected.positions);
+    assert_close(&mesh.normals, &expected.normals);
+}
+
+#[test]
+fn voxel_source_interpolates_transition_voxels() {
+    // Cells are one sample wide, so double-resolution voxels fall between samples
+    let data = sampled();
+    let block = Block::from([5.0, 0.0, 0.0], 5.0, 10);
+    let mut source = NdarrayVoxelSource::new(data.view(), [-2.0, -2.0, -2.0], 0.5, &block).unwrap();
+    let mesh = extract(
+        &mut source,
+        &block,
+        0.0,
+        all_sides(),
+        GenericMeshBuilder::new(),
+    )
+    .build();
+    assert_that!(mesh.num_tris(), greater_than(0));
+}
+
+#[test]
+fn field_interpolates_linearly() {
+    let data = Array3::from_shape_fn((4, 4, 4), |(i, j, k)| i as f32 + 2.0 * j as f32 - k as f32);
+    let mut field = NdarrayField::new(data.view(), [1.0, 1.0, 1.0], 2.0);
+    let value: f32 = field.get_data(2.0, 4.0, 5.5);
+    assert_that!((value - (0.5 + 3.0 - 2.25)).abs(), less_than(1e-5));
+    // Clamped out of the array
+    let value: f32 = field.get_data(-10.0, 1.0, 1.0);
+    assert_that!(value, equal_to(0.0));
+}
+
+#[test]
+fn misaligned_blocks_are_rejected() {
+    let data = sampled();
+    let view = data.view();
+    let misaligned = Block::from([0.25, 0.0, 0.0], 10.0, 10);
+    assert_that!(
+        NdarrayVoxelSource::new(view, [-2.0, -2.0, -2.0], 0.5, &misaligned).err(),
+        equal_to(Some(NdarrayError::NotAligned))
+    );
+    let too_fine = default_block(40);
+    assert_that!(
+        NdarrayVoxelSource::new(view, [-2.0, -2.0, -2.0], 0.5, &too_fine).err(),
+        equal_to(Some(NdarrayError::NotAligned))
+    );
+    let outside = Block::from([5.0, 0.0, 0.0], 10.0, 10);
+    assert_that!(
+        NdarrayVoxelSource::new(view, [-2.0, -2.0, -2.0], 0.5, &outside).err(),
+        equal_to(Some(NdarrayError::OutOfBounds))
+    );
+}
+
+#[test]
+fn bounds() {
+    let data = sampled();
+    let inside = Block::from([1.0, 2.0, 3.0], 2.0, 4);
+    let crossing = default_block(10);
+    let source = NdarrayVoxelSource::new(data.view(), [-2.0, -2.0, -2.0], 0.5, &inside).unwrap();
+    assert_that!(is_block_empty(&source, 0.0), is(true));
+    let source = NdarrayVoxelSource::new(data.view(), [-2.0, -2.0, -2.0], 0.5, &crossing).unwrap();
+    assert_that!(is_block_empty(&source, 0.0), is(false));
+    let field = NdarrayField::new(data.view(), [-2.0, -2.0, -2.0], 0.5);
+    let (min, max) = field
+        .density_bounds([1.0, 2.0, 3.0], [3.0, 4.0, 5.0])
+        .unwrap();
+    assert_that!(min, greater_than(0.0));
+    assert_that!(max, equal_to(6.0));
+    let (min, _) = field
+        .density_bounds([0.0, 0.0, 0.0], [10.0, 10.0, 10.0])
+        .unwrap();
+    assert_that!(min, less_than(0.0));
+}
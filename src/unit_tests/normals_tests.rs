@@ -0,0 +1,103 @@
+use crate::extraction::{extract_from_field, extract_from_fn};
+use crate::generic_mesh::*;
+use crate::mesh_builder::NormalMode;
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::{default_block, sphere};
+use crate::voxel_source::Block;
+use hamcrest2::prelude::*;
+
+fn extract_sphere(mode: NormalMode, sides: TransitionSides) -> Mesh<f32> {
+    let block = default_block(10);
+    extract_from_field(
+        &sphere,
+        &block,
+        0.0,
+        sides,
+        GenericMeshBuilder::new().with_normal_mode(mode),
+    )
+    .build()
+}
+
+// Counts the density queries made when extracting a block
+fn queries_count(mode: NormalMode) -> usize {
+    let mut count = 0;
+    let block = default_block(10);
+    let field = |x: f32, y: f32, z: f32| {
+        count += 1;
+        sphere(x, y, z)
+    };
+    extract_from_fn(
+        field,
+        &block,
+        0.0,
+        no_side(),
+        GenericMeshBuilder::new().with_normal_mode(mode),
+    );
+    count
+}
+
+#[test]
+fn no_normals() {
+    let mesh = extract_sphere(NormalMode::None, all_sides());
+    let reference = extract_sphere(NormalMode::Gradient, all_sides());
+    assert_that!(mesh.normals.is_empty(), is(true));
+    assert_that!(&mesh.positions, equal_to(&reference.positions));
+    assert_that!(
+        &mesh.triangle_indices,
+        equal_to(&reference.triangle_indices)
+    );
+    for tri in mesh.tris() {
+        for v in tri.vertices.iter() {
+            assert_that!(v.normal, equal_to([0.0, 0.0, 0.0]));
+        }
+    }
+}
+
+#[test]
+fn face_normals() {
+    let mesh = extract_sphere(NormalMode::Face, all_sides());
+    let reference = extract_sphere(NormalMode::Gradient, all_sides());
+    assert_that!(mesh.num_tris(), equal_to(reference.num_tris()));
+    assert_that!(mesh.positions.len(), equal_to(9 * mesh.num_tris()));
+    for (tri, reference_tri) in mesh.tris().iter().zip(reference.tris().iter()) {
+        let normal = tri.vertices[0].normal;
+        for (v, reference_v) in tri.vertices.iter().zip(reference_tri.vertices.iter()) {
+            assert_that!(v.position, equal_to(reference_v.position));
+            assert_that!(v.normal, equal_to(normal));
+            // Pointing outward of the sphere, like the gradient normals
+            let dot: f32 = (0..3).map(|i| v.normal[i] * reference_v.normal[i]).sum();
+            assert_that!(dot, greater_than(0.5));
+        }
+    }
+}
+
+#[test]
+fn face_normals_on_flat_surface() {
+    let block = Block::from([0.0, 0.0, 0.0], 1.0, 2);
+    let field = |_x: f32, _y: f32, z: f32| 0.5 - z;
+    let mesh = extract_from_fn(
+        field,
+        &block,
+        0.0,
+        no_side(),
+        GenericMeshBuilder::new().with_normal_mode(NormalMode::Face),
+    )
+    .build();
+    assert_that!(mesh.num_tris(), greater_than(0));
+    for tri in mesh.tris() {
+        for v in tri.vertices.iter() {
+            assert_that!(v.normal, equal_to([0.0, 0.0, 1.0]));
+        }
+    }
+}
+
+#[test]
+fn gradients_are_not_queried_without_gradient_normals() {
+    // Only the (n+1)^3 regular grid voxels are needed
+    assert_that!(queries_count(NormalMode::None), equal_to(11 * 11 * 11));
+    assert_that!(queries_count(NormalMode::Face), equal_to(11 * 11 * 11));
+    assert_that!(
+        queries_count(NormalMode::Gradient),
+        greater_than(11 * 11 * 11)
+    );
+}
@@ -0,0 +1,81 @@
+use crate::extraction::extract_from_field;
+use crate::generic_mesh::*;
+use crate::parallel::extract_batch;
+use crate::sdf::{Sdf, Sphere};
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::{default_block, sphere_density};
+use crate::voxel_source::Block;
+use hamcrest2::prelude::*;
+
+fn sphere(x: f32, y: f32, z: f32) -> f32 {
+    sphere_density([5.0; 3], 7.0, x, y, z)
+}
+
+fn jobs() -> Vec<(Block<f32>, TransitionSides)> {
+    // Various sizes, so that reused storage has to shrink and grow
+    vec![
+        (default_block(10), no_side()),
+        (Block::from([10.0, 0.0, 0.0], 10.0, 3), all_sides()),
+        (
+            Block::from([0.0, 10.0, 0.0], 10.0, 16),
+            TransitionSide::LowY.into(),
+        ),
+        (Block::from([0.0, 0.0, -5.0], 5.0, 1), all_sides()),
+        (
+            Block::from([0.0, 0.0, 5.0], 5.0, 7),
+            TransitionSide::HighX | TransitionSide::LowZ,
+        ),
+    ]
+}
+
+fn assert_same_as_sequential(meshes: Vec<GenericMeshBuilder<f32>>) {
+    let jobs = jobs();
+    assert_that!(meshes.len(), equal_to(jobs.len()));
+    for (builder, (block, sides)) in meshes.into_iter().zip(jobs.iter()) {
+        let mesh = builder.build();
+        let expected =
+            extract_from_field(&sphere, block, 0.0, *sides, GenericMeshBuilder::new()).build();
+        assert_that!(mesh.num_tris(), greater_than(0));
+        assert_that!(mesh.positions, equal_to(expected.positions));
+        assert_that!(mesh.normals, equal_to(expected.normals));
+        assert_that!(mesh.triangle_indices, equal_to(expected.triangle_indices));
+    }
+}
+
+#[test]
+fn batch_matches_sequential_extraction() {
+    let meshes = extract_batch(&sphere, &jobs(), 0.0, GenericMeshBuilder::new);
+    assert_same_as_sequential(meshes);
+}
+
+#[test]
+fn storage_reuse_on_a_single_thread() {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap();
+    let meshes = pool.install(|| extract_batch(&sphere, &jobs(), 0.0, GenericMeshBuilder::new));
+    assert_same_as_sequential(meshes);
+}
+
+#[test]
+fn batch_of_a_shared_field() {
+    // Not Clone: only shared between the threads
+    struct Shared {
+        sphere: Sphere<f32>,
+    }
+    let shared = Shared {
+        sphere: Sphere::new([5.0; 3], 7.0),
+    };
+    let field = |x: f32, y: f32, z: f32| -shared.sphere.distance([x, y, z]);
+    let meshes = extract_batch(&field, &jobs(), 0.0, GenericMeshBuilder::new);
+    assert_same_as_sequential(meshes);
+    // Shapes are also shared directly, with their exact gradients
+    let meshes = extract_batch(&shared.sphere, &jobs(), 0.0, GenericMeshBuilder::new);
+    for (builder, (block, sides)) in meshes.into_iter().zip(jobs().iter()) {
+        let expected =
+            extract_from_field(shared.sphere, block, 0.0, *sides, GenericMeshBuilder::new())
+                .build();
+        assert_that!(builder.build().positions, equal_to(expected.positions));
+    }
+}
@@ -0,0 +1,125 @@
+use crate::extraction::{extract, extract_from_field};
+use crate::generic_mesh::*;
+use crate::pyramid::*;
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::sphere_density;
+use crate::voxel_source::Block;
+use hamcrest2::prelude::*;
+
+fn sphere(x: f32, y: f32, z: f32) -> f32 {
+    sphere_density([16.0, 12.0, 12.0], 6.0, x, y, z)
+}
+
+fn grid<F: Fn(f32, f32, f32) -> f32>(f: F, size: usize) -> Vec<f32> {
+    let mut samples = vec![];
+    for x in 0..size {
+        for y in 0..size {
+            for z in 0..size {
+                samples.push(f(x as f32, y as f32, z as f32));
+            }
+        }
+    }
+    samples
+}
+
+fn pyramid(filter: DownsampleFilter<f32>) -> VoxelPyramid<f32, f32> {
+    VoxelPyramid::build(grid(sphere, 33), [33; 3], [0.0; 3], 1.0, 3, filter).unwrap()
+}
+
+#[test]
+fn point_sampled_level_matches_field() {
+    let pyramid = pyramid(DownsampleFilter::PointSample);
+    assert_that!(pyramid.levels(), equal_to(3));
+    assert_that!(pyramid.level_dims(2), equal_to([9, 9, 9]));
+    // Level 1 block from 4 to 20, crossed by the sphere on its high X face
+    let block = Block::from([4.0, 4.0, 4.0], 16.0, 8);
+    let sides = TransitionSide::HighX | TransitionSide::LowY;
+    let source = pyramid.source(1, &block).unwrap();
+    let mesh = extract(source, &block, 0.0, sides, GenericMeshBuilder::new()).build();
+    let expected =
+        extract_from_field(&sphere, &block, 0.0, sides, GenericMeshBuilder::new()).build();
+    assert_that!(mesh.num_tris(), greater_than(0));
+    assert_that!(mesh.positions, equal_to(expected.positions));
+    assert_that!(mesh.normals, equal_to(expected.normals));
+    assert_that!(mesh.triangle_indices, equal_to(expected.triangle_indices));
+}
+
+#[test]
+fn average_keeps_linear_fields() {
+    let linear = |x: f32, y: f32, z: f32| x - 2.0 * y + 0.5 * z;
+    let pyramid = VoxelPyramid::build(
+        grid(linear, 17),
+        [17; 3],
+        [0.0; 3],
+        1.0,
+        2,
+        DownsampleFilter::Average,
+    )
+    .unwrap();
+    assert_that!(
+        pyramid.sample(1, [3, 2, 4]),
+        equal_to(linear(6.0, 4.0, 8.0))
+    );
+}
+
+#[test]
+fn min_max_keeps_extreme_values() {
+    // An inside voxel on the coarser grid, next to a more inside one
+    let field = |x: f32, y: f32, z: f32| match (x as i32, y as i32, z as i32) {
+        (4, 4, 4) => 0.5,
+        (5, 4, 4) => 1.0,
+        _ => -1.0,
+    };
+    let build =
+        |filter| VoxelPyramid::build(grid(field, 9), [9; 3], [0.0; 3], 1.0, 2, filter).unwrap();
+    let point_sampled = build(DownsampleFilter::PointSample);
+    let min_max = build(DownsampleFilter::MinMax(0.0));
+    assert_that!(point_sampled.sample(1, [2, 2, 2]), equal_to(0.5));
+    assert_that!(min_max.sample(1, [2, 2, 2]), equal_to(1.0));
+    // Sample 3 of level 1 is sample 6 of level 0, next to the inside ones, but outside: it stays outside
+    assert_that!(min_max.sample(1, [3, 2, 2]), equal_to(-1.0));
+}
+
+#[test]
+fn errors() {
+    let build = |samples: usize, dims: usize, levels: usize| {
+        VoxelPyramid::<f32, f32>::build(
+            vec![0.0; samples],
+            [dims; 3],
+            [0.0; 3],
+            1.0,
+            levels,
+            DownsampleFilter::PointSample,
+        )
+        .err()
+    };
+    assert_that!(
+        build(10, 3, 1),
+        equal_to(Some(PyramidError::SamplesCount {
+            expected: 27,
+            actual: 10
+        }))
+    );
+    assert_that!(
+        build(125, 5, 4),
+        equal_to(Some(PyramidError::TooManyLevels))
+    );
+    let pyramid = pyramid(DownsampleFilter::PointSample);
+    let source_error = |level: usize, block: Block<f32>| pyramid.source(level, &block).err();
+    assert_that!(
+        source_error(3, Block::from([0.0; 3], 8.0, 1)),
+        equal_to(Some(PyramidError::NoSuchLevel))
+    );
+    assert_that!(
+        source_error(1, Block::from([0.0; 3], 16.0, 16)),
+        equal_to(Some(PyramidError::NotAligned))
+    );
+    assert_that!(
+        source_error(1, Block::from([1.0, 0.0, 0.0], 16.0, 8)),
+        equal_to(Some(PyramidError::NotAligned))
+    );
+    assert_that!(
+        source_error(1, Block::from([18.0, 0.0, 0.0], 16.0, 8)),
+        equal_to(Some(PyramidError::OutOfBounds))
+    );
+}
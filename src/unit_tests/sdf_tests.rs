@@ -0,0 +1,202 @@
+use crate::extraction::*;
+use crate::generic_mesh::*;
+use crate::sdf::*;
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::default_block;
+use crate::voxel_source::{Block, DataField};
+use hamcrest2::prelude::*;
+use std::cell::Cell;
+
+const POINTS: [[f64; 3]; 6] = [
+    [3.1, 0.2, -0.7],
+    [-1.3, 2.9, 0.4],
+    [0.3, -0.4, 0.45],
+    [1.7, 1.1, 2.3],
+    [-2.2, -0.6, -1.9],
+    [0.05, 3.6, -0.3],
+];
+
+fn finite_difference_gradient(sdf: &impl Sdf<f64>, p: [f64; 3]) -> [f64; 3] {
+    let h = 1e-5;
+    let mut gradient = [0.0; 3];
+    for axis in 0..3 {
+        let mut plus = p;
+        let mut minus = p;
+        plus[axis] += h;
+        minus[axis] -= h;
+        gradient[axis] = (sdf.distance(plus) - sdf.distance(minus)) / (2.0 * h);
+    }
+    gradient
+}
+
+fn check_gradients(sdf: &impl Sdf<f64>) {
+    for p in POINTS {
+        let expected = finite_difference_gradient(sdf, p);
+        let actual = sdf.gradient(p);
+        for axis in 0..3 {
+            assert_that!(actual[axis], close_to(expected[axis], 1e-4));
+        }
+    }
+}
+
+fn check_bounds(sdf: &impl Sdf<f64>) {
+    let (min, max) = sdf.distance_bounds([-1.0, 0.0, -2.0], [1.5, 2.0, 0.5]);
+    for i in 0..=4 {
+        for j in 0..=4 {
+            for k in 0..=4 {
+                let p = [
+                    -1.0 + 2.5 * i as f64 / 4.0,
+                    2.0 * j as f64 / 4.0,
+                    -2.0 + 2.5 * k as f64 / 4.0,
+                ];
+                let d = sdf.distance(p);
+                assert_that!(d, greater_than_or_equal_to(min));
+                assert_that!(d, less_than_or_equal_to(max));
+            }
+        }
+    }
+}
+
+#[test]
+fn primitive_distances() {
+    let sphere = Sphere::new([1.0, 0.0, 0.0], 2.0);
+    assert_that!(sphere.distance([4.0, 0.0, 0.0]), close_to(1.0, 1e-9));
+    assert_that!(sphere.distance([1.0, 0.0, 0.0]), close_to(-2.0, 1e-9));
+    let cuboid = Cuboid::new([0.0, 0.0, 0.0], [1.0, 2.0, 3.0]);
+    assert_that!(cuboid.distance([0.0, 0.0, 0.0]), close_to(-1.0, 1e-9));
+    assert_that!(cuboid.distance([4.0, 6.0, 0.0]), close_to(5.0, 1e-9));
+    let rounded = RoundedBox::new([0.0, 0.0, 0.0], [1.0, 1.0, 1.0], 0.5);
+    assert_that!(rounded.distance([2.0, 0.0, 0.0]), close_to(1.0, 1e-9));
+    let capsule = Capsule::new([0.0, 0.0, 0.0], [0.0, 4.0, 0.0], 1.0);
+    assert_that!(capsule.distance([3.0, 2.0, 0.0]), close_to(2.0, 1e-9));
+    assert_that!(capsule.distance([0.0, 7.0, 0.0]), close_to(2.0, 1e-9));
+    let cylinder = Cylinder::new([0.0, 0.0, 0.0], 1.0, 2.0);
+    assert_that!(cylinder.distance([0.0, 5.0, 0.0]), close_to(3.0, 1e-9));
+    assert_that!(cylinder.distance([0.0, 0.0, 3.0]), close_to(2.0, 1e-9));
+    let torus = Torus::new([0.0, 0.0, 0.0], 3.0, 1.0);
+    assert_that!(torus.distance([3.0, 0.0, 0.0]), close_to(-1.0, 1e-9));
+    assert_that!(torus.distance([0.0, 0.0, 0.0]), close_to(2.0, 1e-9));
+    let plane = Plane::new([0.0, 2.0, 0.0], [0.0, 1.0, 0.0]);
+    assert_that!(plane.distance([5.0, 4.0, 5.0]), close_to(3.0, 1e-9));
+}
+
+#[test]
+fn combinators_distances() {
+    let a = Sphere::new([0.0, 0.0, 0.0], 1.0);
+    let b = Sphere::new([3.0, 0.0, 0.0], 1.0);
+    assert_that!(a.union(b).distance([3.0, 0.0, 0.0]), close_to(-1.0, 1e-9));
+    assert_that!(
+        a.intersection(b).distance([0.0, 0.0, 0.0]),
+        close_to(2.0, 1e-9)
+    );
+    assert_that!(
+        a.subtraction(b).distance([0.0, 0.0, 0.0]),
+        close_to(-1.0, 1e-9)
+    );
+    assert_that!(
+        b.subtraction(b).distance([3.0, 0.0, 0.0]),
+        close_to(1.0, 1e-9)
+    );
+    let smooth = a.smooth_union(b, 1.0);
+    assert_that!(smooth.distance([1.5, 0.0, 0.0]), close_to(0.25, 1e-9));
+    assert_that!(smooth.distance([-2.0, 0.0, 0.0]), close_to(1.0, 1e-9));
+    let moved = a.translate([0.0, 5.0, 0.0]);
+    assert_that!(moved.distance([0.0, 5.0, 0.0]), close_to(-1.0, 1e-9));
+    let scaled = a.scale(3.0);
+    assert_that!(scaled.distance([5.0, 0.0, 0.0]), close_to(2.0, 1e-9));
+    let rotated = Cuboid::new([2.0, 0.0, 0.0], [1.0, 0.5, 0.5])
+        .rotate([0.0, 0.0, 1.0], std::f64::consts::FRAC_PI_2);
+    assert_that!(rotated.distance([0.0, 2.0, 0.0]), close_to(-0.5, 1e-9));
+    let repeated = a.repeat([4.0, 0.0, 0.0]);
+    assert_that!(repeated.distance([8.0, 0.0, 0.0]), close_to(-1.0, 1e-9));
+    assert_that!(repeated.distance([8.0, 3.0, 0.0]), close_to(2.0, 1e-9));
+}
+
+#[test]
+fn analytic_gradients() {
+    check_gradients(&Sphere::new([0.5, 0.0, 0.0], 1.5));
+    check_gradients(&Cuboid::new([0.0, 0.5, 0.0], [1.0, 2.0, 0.8]));
+    check_gradients(&RoundedBox::new([0.0, 0.5, 0.0], [1.0, 2.0, 0.8], 0.3));
+    check_gradients(&Capsule::new([0.0, 0.0, 0.0], [1.0, 2.0, 0.0], 0.5));
+    check_gradients(&Cylinder::new([0.0, 0.5, 0.0], 1.0, 1.5));
+    check_gradients(&Torus::new([0.0, 0.0, 0.0], 2.0, 0.5));
+    check_gradients(&Plane::new([1.0, 2.0, -1.0], [0.0, 1.0, 0.0]));
+    let a = Sphere::new([0.0, 0.0, 0.0], 1.5);
+    let b = Cuboid::new([1.0, 1.0, 0.0], [1.0, 1.0, 1.0]);
+    check_gradients(&a.union(b));
+    check_gradients(&a.intersection(b));
+    check_gradients(&a.subtraction(b));
+    check_gradients(&a.smooth_union(b, 1.0).translate([0.5, -0.5, 0.2]));
+    check_gradients(&b.rotate([1.0, 1.0, 0.0], 0.7));
+    check_gradients(&b.scale(1.7));
+    check_gradients(&Sphere::new([0.0, 0.0, 0.0], 0.5).repeat([2.0, 0.0, 2.0]));
+}
+
+#[test]
+fn distance_bounds_are_conservative() {
+    let a = Sphere::new([0.0, 0.0, 0.0], 1.5);
+    let b = Torus::new([1.0, 1.0, 0.0], 1.0, 0.4);
+    check_bounds(&a);
+    check_bounds(&b);
+    check_bounds(&a.union(b));
+    check_bounds(&a.intersection(b));
+    check_bounds(&a.subtraction(b));
+    check_bounds(&a.smooth_union(b, 2.0));
+    check_bounds(&a.smooth_union(b, 2.0).translate([0.3, 0.0, 1.0]));
+}
+
+#[test]
+fn sdf_as_data_field() {
+    let mut sphere = Sphere::new([0.0f32, 0.0, 0.0], 2.0);
+    assert_that!(sphere.get_data(3.0, 0.0, 0.0), close_to(-1.0, 1e-6));
+    let (min, max) = sphere
+        .density_bounds([1.0, -1.0, -1.0], [3.0, 1.0, 1.0])
+        .unwrap();
+    assert_that!(min, less_than_or_equal_to(-1.0));
+    assert_that!(max, greater_than_or_equal_to(1.0));
+    let gradient = sphere.get_gradient(0.0, 3.0, 0.0).unwrap();
+    assert_that!(gradient[1], close_to(-1.0, 1e-6));
+    let mut wrapped = SdfField(sphere);
+    assert_that!(wrapped.get_data(0.0, 0.0, 0.0), close_to(2.0, 1e-6));
+}
+
+// Counts the distance queries
+struct Counting<S> {
+    sdf: S,
+    queries: Cell<usize>,
+}
+
+impl<S: Sdf<f32>> Sdf<f32> for Counting<S> {
+    fn distance(&self, p: [f32; 3]) -> f32 {
+        self.queries.set(self.queries.get() + 1);
+        self.sdf.distance(p)
+    }
+
+    fn gradient(&self, p: [f32; 3]) -> [f32; 3] {
+        self.sdf.gradient(p)
+    }
+}
+
+#[test]
+fn extraction_and_far_blocks() {
+    let shape = Sphere::new([5.0f32, 5.0, 5.0], 3.0).union(Torus::new([5.0, 5.0, 5.0], 3.5, 0.5));
+    let block = default_block(10);
+    let mesh = extract_from_field(shape, &block, 0.0, no_side(), GenericMeshBuilder::new()).build();
+    assert_that!(mesh.num_tris(), greater_than(0));
+    let mut field = SdfField(Counting {
+        sdf: shape,
+        queries: Cell::new(0),
+    });
+    let far = Block::from([20.0, 0.0, 0.0], 10.0, 10);
+    let mesh = extract_from_field(
+        &mut field as &mut dyn DataField<f32, f32>,
+        &far,
+        0.0,
+        all_sides(),
+        GenericMeshBuilder::new(),
+    )
+    .build();
+    assert_that!(mesh.num_tris(), equal_to(0));
+    // Only for the bounds
+    assert_that!(field.0.queries.get(), equal_to(1));
+}
@@ -0,0 +1,147 @@
+use crate::extraction::{extract_from_field, extract_separated_from_field};
+use crate::generic_mesh::*;
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::{default_block, sphere_density};
+use crate::voxel_source::Block;
+use flagset::Flags;
+use hamcrest2::prelude::*;
+
+// Sphere crossing the 3 low faces of the block
+fn sphere(x: f32, y: f32, z: f32) -> f32 {
+    sphere_density([0.0; 3], 5.0, x, y, z)
+}
+
+fn tris_count(block: &Block<f32>, sides: TransitionSides) -> usize {
+    extract_from_field(&sphere, block, 0.0, sides, GenericMeshBuilder::new())
+        .build()
+        .num_tris()
+}
+
+#[test]
+fn regular_cells_are_not_shrunk() {
+    let block = default_block(10);
+    let separated =
+        extract_separated_from_field(&sphere, &block, 0.0, all_sides(), GenericMeshBuilder::new);
+    let regular = separated.regular.build();
+    let plain = extract_from_field(&sphere, &block, 0.0, no_side(), GenericMeshBuilder::new());
+    let plain = plain.build();
+    assert_that!(regular.positions, equal_to(plain.positions));
+    assert_that!(regular.triangle_indices, equal_to(plain.triangle_indices));
+}
+
+#[test]
+fn transition_cells_are_separated_by_side() {
+    let block = default_block(10);
+    let sides = TransitionSide::LowX | TransitionSide::LowZ | TransitionSide::HighY;
+    let separated =
+        extract_separated_from_field(&sphere, &block, 0.0, sides, GenericMeshBuilder::new);
+    assert_that!(
+        separated.transition(TransitionSide::HighX).is_none(),
+        is(true)
+    );
+    let regular_count = tris_count(&block, no_side());
+    let mut transitions = separated.transitions;
+    assert_that!(
+        separated.regular.build().num_tris(),
+        equal_to(regular_count)
+    );
+    for side in TransitionSide::LIST.iter() {
+        let patch = transitions[*side as usize].take();
+        if sides.contains(*side) {
+            let patch_count = patch.unwrap().build().num_tris();
+            let full_count = tris_count(&block, (*side).into());
+            assert_that!(patch_count, equal_to(full_count - regular_count));
+        } else {
+            assert_that!(patch.is_none(), is(true));
+        }
+    }
+    // The sphere does not reach the high Y face, so the corresponding transition is empty
+    assert_that!(
+        tris_count(&block, TransitionSide::HighY.into()),
+        equal_to(regular_count)
+    );
+}
+
+#[test]
+fn all_transitions_together() {
+    let block = default_block(10);
+    let separated =
+        extract_separated_from_field(&sphere, &block, 0.0, all_sides(), GenericMeshBuilder::new);
+    let mut count = separated.regular.build().num_tris();
+    for patch in separated.transitions {
+        count += patch.unwrap().build().num_tris();
+    }
+    assert_that!(count, equal_to(tris_count(&block, all_sides())));
+}
+
+// Half sphere on the low Z face, not reaching any other face
+fn half_sphere(x: f32, y: f32, z: f32) -> f32 {
+    sphere_density([5.0, 5.0, 0.0], 3.0, x, y, z)
+}
+
+#[test]
+fn secondary_positions_match_shrunk_extraction() {
+    let block = default_block(10);
+    let builder = GenericMeshBuilder::new().with_secondary_positions();
+    let mesh = extract_from_field(&half_sphere, &block, 0.0, no_side(), builder).build();
+    let shrunk = extract_from_field(
+        &half_sphere,
+        &block,
+        0.0,
+        TransitionSide::LowZ.into(),
+        GenericMeshBuilder::new(),
+    )
+    .build();
+    // Regular cells vertices come first in the shrunk mesh, followed by transition cells vertices
+    let moved = mesh
+        .positions_for_sides(TransitionSide::LowZ.into())
+        .unwrap();
+    assert_that!(moved.len(), less_than(shrunk.positions.len()));
+    assert_that!(&moved[..], equal_to(&shrunk.positions[..moved.len()]));
+    assert_that!(moved, not(equal_to(mesh.positions.clone())));
+    // Other sides than the ones the vertices are near do not move anything
+    let not_moved = mesh
+        .positions_for_sides(TransitionSide::HighX.into())
+        .unwrap();
+    assert_that!(not_moved, equal_to(mesh.positions.clone()));
+    let near_face_mask = mesh.near_face_mask.unwrap();
+    assert_that!(near_face_mask.len() * 3, equal_to(mesh.positions.len() * 2));
+    let low_z_bit = TransitionSides::from(TransitionSide::LowZ).bits();
+    for mask in near_face_mask {
+        assert_that!(mask & !low_z_bit, equal_to(0));
+    }
+}
+
+// Plane crossing the block diagonally, through edges and corners of the block
+fn plane(x: f32, y: f32, z: f32) -> f32 {
+    13.7 - x - 0.9 * y - 0.8 * z
+}
+
+#[test]
+fn secondary_positions_near_block_edges() {
+    let block = default_block(5);
+    let builder = GenericMeshBuilder::new().with_secondary_positions();
+    let mesh = extract_from_field(&plane, &block, 0.0, no_side(), builder).build();
+    for bits in 0..64u8 {
+        let sides = TransitionSides::new(bits).unwrap();
+        let shrunk =
+            extract_from_field(&plane, &block, 0.0, sides, GenericMeshBuilder::new()).build();
+        let moved = mesh.positions_for_sides(sides).unwrap();
+        assert_that!(&moved[..], equal_to(&shrunk.positions[..moved.len()]));
+    }
+}
+
+#[test]
+fn no_secondary_positions_by_default() {
+    let block = default_block(10);
+    let mesh = extract_from_field(
+        &half_sphere,
+        &block,
+        0.0,
+        no_side(),
+        GenericMeshBuilder::new(),
+    );
+    let mesh = mesh.build();
+    assert_that!(mesh.secondary_positions.is_none(), is(true));
+    assert_that!(mesh.positions_for_sides(all_sides()).is_none(), is(true));
+}
@@ -0,0 +1,308 @@
+use crate::extraction::extract_from_field;
+use crate::generic_mesh::*;
+use crate::mesh_builder::*;
+use crate::splat::*;
+use crate::traits::{DensityFloat, VoxelData};
+use crate::transition_sides::TransitionSide::{HighZ, LowX};
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::{default_block, sphere};
+use crate::voxel_source::Block;
+use hamcrest2::prelude::*;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Voxel {
+    density: f32,
+    material: u32,
+}
+
+impl VoxelData for Voxel {
+    type Density = f32;
+
+    fn density(&self) -> Self::Density {
+        self.density
+    }
+}
+
+// Sphere with 2 materials, split at x=5
+fn split_sphere(x: f32, y: f32, z: f32) -> Voxel {
+    Voxel {
+        density: sphere(x, y, z),
+        material: if x < 5.0 { 1 } else { 2 },
+    }
+}
+
+fn extract(builder: SplatMeshBuilder<Voxel, f32>, sides: TransitionSides) -> Mesh<f32> {
+    let block = default_block(10);
+    extract_from_field(&split_sphere, &block, 0.0, sides, builder).build()
+}
+
+fn attribute<'a>(mesh: &'a Mesh<f32>, name: &str) -> &'a [f32] {
+    &mesh
+        .attributes
+        .iter()
+        .find(|a| a.name == name)
+        .unwrap()
+        .values
+}
+
+#[test]
+fn weights_are_normalized() {
+    let mesh = extract(
+        SplatMeshBuilder::new(|v: &Voxel| Some(v.material)),
+        all_sides(),
+    );
+    assert_that!(mesh.attributes.len(), equal_to(8));
+    for i in 0..mesh.positions.len() / 3 {
+        let weights: Vec<f32> = MATERIAL_WEIGHT_ATTRIBUTES
+            .iter()
+            .map(|name| attribute(&mesh, name)[i])
+            .collect();
+        let sum: f32 = weights.iter().sum();
+        assert_that!((sum - 1.0).abs(), less_than(1e-5));
+        // Sorted by decreasing weight
+        assert_that!(weights.windows(2).all(|w| w[0] >= w[1]), is(true));
+    }
+    // Same vertices as the generic builder
+    let block = default_block(10);
+    let generic = extract_from_field(
+        &split_sphere,
+        &block,
+        0.0,
+        all_sides(),
+        GenericMeshBuilder::new(),
+    )
+    .build();
+    assert_that!(&mesh.positions, equal_to(&generic.positions));
+    assert_that!(&mesh.triangle_indices, equal_to(&generic.triangle_indices));
+}
+
+#[test]
+fn single_material_regions_and_boundaries() {
+    let mesh = extract(
+        SplatMeshBuilder::new(|v: &Voxel| Some(v.material)),
+        no_side(),
+    );
+    let first_material = attribute(&mesh, MATERIAL_INDEX_ATTRIBUTES[0]);
+    let first_weight = attribute(&mesh, MATERIAL_WEIGHT_ATTRIBUTES[0]);
+    let second_weight = attribute(&mesh, MATERIAL_WEIGHT_ATTRIBUTES[1]);
+    let mut mixed = 0;
+    for i in 0..mesh.positions.len() / 3 {
+        let x = mesh.positions[3 * i];
+        if x < 3.5 {
+            assert_that!(first_material[i], equal_to(1.0));
+            assert_that!(first_weight[i], equal_to(1.0));
+        } else if x > 6.5 {
+            assert_that!(first_material[i], equal_to(2.0));
+            assert_that!(first_weight[i], equal_to(1.0));
+        }
+        if second_weight[i] > 0.0 {
+            assert_that!(x, greater_than(3.0));
+            assert_that!(x, less_than(7.0));
+            mixed += 1;
+        }
+    }
+    assert_that!(mixed, greater_than(0));
+    // Only 2 materials: the last slots are unused
+    for name in &MATERIAL_WEIGHT_ATTRIBUTES[2..] {
+        assert_that!(attribute(&mesh, name).iter().all(|w| *w == 0.0), is(true));
+    }
+}
+
+#[test]
+fn non_contributing_voxels() {
+    // Only inside voxels have a material, and no corner weight: the inside endpoint gets all the weight
+    let mesh = extract(
+        SplatMeshBuilder::new(|v: &Voxel| (v.density > 0.0).then_some(v.material))
+            .with_corner_weight(0.0),
+        no_side(),
+    );
+    // (except for vertices exactly on a grid point of density 0, where the inside endpoint has no weight)
+    let first_weight = attribute(&mesh, MATERIAL_WEIGHT_ATTRIBUTES[0]);
+    assert_that!(
+        first_weight.iter().all(|w| *w == 1.0 || *w == 0.0),
+        is(true)
+    );
+    assert_that!(
+        first_weight.iter().filter(|w| **w == 1.0).count(),
+        greater_than(first_weight.len() / 2)
+    );
+    // Nothing contributes
+    let mesh = extract(SplatMeshBuilder::new(|_: &Voxel| None), no_side());
+    for name in MATERIAL_WEIGHT_ATTRIBUTES {
+        assert_that!(attribute(&mesh, name).iter().all(|w| *w == 0.0), is(true));
+    }
+}
+
+// A voxel knowing where it was sampled
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct PositionVoxel {
+    density: f32,
+    position: [f32; 3],
+}
+
+impl VoxelData for PositionVoxel {
+    type Density = f32;
+
+    fn density(&self) -> Self::Density {
+        self.density
+    }
+}
+
+// Records the grid points given to add_vertex_between
+struct GridPointRecorder {
+    needs_neighbours: bool,
+    grid_points: Vec<GridPoint<PositionVoxel, f32>>,
+}
+
+impl MeshBuilder<PositionVoxel, f32> for GridPointRecorder {
+    fn needs_neighbours(&self) -> bool {
+        self.needs_neighbours
+    }
+
+    fn add_vertex_between(
+        &mut self,
+        point_a: GridPoint<PositionVoxel, f32>,
+        point_b: GridPoint<PositionVoxel, f32>,
+        _interp_toward_b: DensityFloat<PositionVoxel>,
+    ) -> VertexIndex {
+        self.grid_points.push(point_a);
+        self.grid_points.push(point_b);
+        VertexIndex(self.grid_points.len() / 2 - 1)
+    }
+
+    fn add_triangle(&mut self, _: VertexIndex, _: VertexIndex, _: VertexIndex) {}
+}
+
+fn record_grid_points(needs_neighbours: bool) -> Vec<GridPoint<PositionVoxel, f32>> {
+    let block = default_block(10);
+    // Horizontal plane, crossing the transition faces
+    let plane = |x: f32, y: f32, z: f32| PositionVoxel {
+        density: 5.5 - y,
+        position: [x, y, z],
+    };
+    let recorder = GridPointRecorder {
+        needs_neighbours,
+        grid_points: vec![],
+    };
+    extract_from_field(&plane, &block, 0.0, LowX | HighZ, recorder).grid_points
+}
+
+#[test]
+fn grid_point_neighbours_are_only_obtained_when_needed() {
+    let grid_points = record_grid_points(false);
+    assert_that!(grid_points.len(), greater_than(0));
+    for point in grid_points.iter() {
+        assert_that!(point.neighbours, equal_to([PositionVoxel::default(); 6]));
+    }
+}
+
+#[test]
+fn grid_point_neighbours_are_one_step_away() {
+    let grid_points = record_grid_points(true);
+    // Regular grid points, and grid points halfway on the high resolution faces
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+    let (mut full_steps, mut half_steps) = (0, 0);
+    for point in grid_points.iter() {
+        let p = point.unshrunk_position;
+        let p = [p.x, p.y, p.z];
+        for (i, neighbour) in point.neighbours.iter().enumerate() {
+            let axis = i / 2;
+            let delta = neighbour.position[axis] - p[axis];
+            assert_that!(delta > 0.0, equal_to(i % 2 == 1));
+            for other_axis in (0..3).filter(|a| *a != axis) {
+                assert_that!(
+                    close(neighbour.position[other_axis], p[other_axis]),
+                    is(true)
+                );
+            }
+            if close(delta.abs(), 1.0) {
+                full_steps += 1;
+            } else {
+                assert_that!(close(delta.abs(), 0.5), is(true));
+                half_steps += 1;
+            }
+        }
+    }
+    assert_that!(full_steps, greater_than(0));
+    assert_that!(half_steps, greater_than(0));
+}
+
+#[test]
+fn builder_can_be_sent_to_another_thread() {
+    let builder = SplatMeshBuilder::new(|v: &Voxel| Some(v.material));
+    let mesh = std::thread::spawn(move || extract(builder, no_side()))
+        .join()
+        .unwrap();
+    assert_that!(mesh.attributes.len(), equal_to(8));
+}
+
+#[test]
+fn face_normals() {
+    let shared = extract(
+        SplatMeshBuilder::new(|v: &Voxel| Some(v.material)),
+        no_side(),
+    );
+    let flat = extract(
+        SplatMeshBuilder::new(|v: &Voxel| Some(v.material)).with_normal_mode(NormalMode::Face),
+        no_side(),
+    );
+    let num_vertices = shared.triangle_indices.len();
+    assert_that!(flat.num_tris(), equal_to(shared.num_tris()));
+    assert_that!(flat.normals.len(), equal_to(3 * num_vertices));
+    // Each copy of a vertex keeps its materials and weights
+    for name in MATERIAL_INDEX_ATTRIBUTES
+        .iter()
+        .chain(MATERIAL_WEIGHT_ATTRIBUTES.iter())
+    {
+        let expected: Vec<f32> = shared
+            .triangle_indices
+            .iter()
+            .map(|i| attribute(&shared, name)[*i])
+            .collect();
+        assert_that!(attribute(&flat, name).to_vec(), equal_to(expected));
+    }
+}
+
+#[test]
+fn shared_vertices_match_across_blocks() {
+    // 4 materials, changing across the face shared by the blocks and within it
+    let field = |x: f32, y: f32, z: f32| Voxel {
+        material: if y < 5.0 { 0 } else { 2 } + if x < 5.0 { 1 } else { 2 },
+        ..split_sphere(x, y, z)
+    };
+    let extract_both = |corner_weight: f32| {
+        [[0.0, 0.0, 0.0], [5.0, 0.0, 0.0]]
+            .iter()
+            .map(|origin| {
+                let block = Block::from(*origin, 5.0, 5);
+                let builder = SplatMeshBuilder::new(|v: &Voxel| Some(v.material))
+                    .with_corner_weight(corner_weight);
+                extract_from_field(&field, &block, 0.0, no_side(), builder).build()
+            })
+            .collect::<Vec<_>>()
+    };
+    // Attributes of the vertices on the shared face, by position (without duplicates)
+    let face_attributes = |mesh: &Mesh<f32>| {
+        let mut vertices: Vec<(Vec<f32>, Vec<f32>)> = vec![];
+        for i in 0..mesh.positions.len() / 3 {
+            if mesh.positions[3 * i] == 5.0 {
+                let attributes = mesh.attributes.iter().map(|a| a.values[i]).collect();
+                vertices.push((mesh.positions[3 * i..3 * i + 3].to_vec(), attributes));
+            }
+        }
+        vertices.sort_by(|v1, v2| v1.partial_cmp(v2).unwrap());
+        vertices.dedup();
+        vertices
+    };
+    // With and without the corners, which are the same for both blocks
+    let with_corners = extract_both(0.5);
+    let low = face_attributes(&with_corners[0]);
+    let high = face_attributes(&with_corners[1]);
+    assert_that!(low.len(), greater_than(0));
+    assert_that!(&low, equal_to(&high));
+    let without_corners = extract_both(0.0);
+    let low_without_corners = face_attributes(&without_corners[0]);
+    let high_without_corners = face_attributes(&without_corners[1]);
+    assert_that!(&low_without_corners, equal_to(&high_without_corners));
+    assert_that!(low != low_without_corners, is(true));
+}
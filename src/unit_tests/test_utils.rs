@@ -6,6 +6,7 @@ use crate::{
 };
 use crate::{generic_mesh::*, transition_sides::*, voxel_source::VoxelSource};
 use ndarray::{Array3, Array6};
+use num::Float;
 use std::fmt::Debug;
 use std::fmt::Display;
 
@@ -466,3 +467,19 @@ pub fn extract_from_grid(
     let builder = GenericMeshBuilder::new();
     extract(field, block, threshold, transition_sides, builder).build()
 }
+
+// Density of a sphere: positive inside, and the distance to the surface outside, negated
+pub fn sphere_density<F: Float>(center: [F; 3], radius: F, x: F, y: F, z: F) -> F {
+    let (dx, dy, dz) = (x - center[0], y - center[1], z - center[2]);
+    radius - (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+// The sphere most tests extract: radius 4, centered in the default block
+pub fn sphere(x: f32, y: f32, z: f32) -> f32 {
+    sphere_density([5.0; 3], 4.0, x, y, z)
+}
+
+// The block most tests extract, from 0 to 10 on each axis
+pub fn default_block(subdivisions: usize) -> Block<f32> {
+    Block::from([0.0, 0.0, 0.0], 10.0, subdivisions)
+}
@@ -4,6 +4,7 @@ use crate::extraction::extract_from_fn;
 use crate::generic_mesh::*;
 use crate::transition_sides::*;
 use crate::unit_tests::test_utils::*;
+use crate::validate::validate;
 use crate::{
     extraction::extract,
     voxel_source::{VoxelSource, WorldMappingVoxelSource},
@@ -440,17 +441,31 @@ fn count_density_calls_random() {
 
 #[test]
 fn random_data() {
-    // Just to get some small confidence that it doesn't crash
     let seed = match env::var("TEST_SEED") {
         Ok(s) => s.parse::<u64>().unwrap(),
         _ => rng().random(),
     };
+    check_random_data(seed);
+}
+
+#[test]
+fn random_data_known_seeds() {
+    // Seeds that once failed. Open edges on the corner line of 2 neighbours were taken for cracks
+    for seed in [11774307887759342917] {
+        check_random_data(seed);
+    }
+}
+
+// Random densities: the mesh must be crack-free against double resolution neighbours on its transition sides
+fn check_random_data(seed: u64) {
     println!("Using seed {}", seed);
     let mut rng = StdRng::seed_from_u64(seed);
     let subdivisions = rng.random_range(2..30);
     let block = Block::from([0.0, 0.0, 0.0], 10.0, subdivisions);
     let sides = random_sides(&mut rng);
-    let source = |_, _, _| rng.random_range(-1.0..1.0);
+    let field_seed: u64 = rng.random();
+    let half_cell = 5.0 / subdivisions as f32;
+    let source = |x, y, z| hashed_density(field_seed, [x, y, z], half_cell);
     let m = GenericMeshBuilder::new();
     let m = extract_from_fn(source, &block, 0.5, sides, m).build();
     println!(
@@ -458,6 +473,70 @@ fn random_data() {
         m.num_tris(),
         sides
     );
+    let neighbours: Vec<(Block<f32>, Mesh<f32>)> = double_resolution_neighbours(&block, sides)
+        .into_iter()
+        .map(|b| {
+            let mesh = extract_from_fn(source, &b, 0.5, no_side(), GenericMeshBuilder::new());
+            (b, mesh.build())
+        })
+        .collect();
+    let mut meshes = vec![(&block, sides, &m)];
+    for (b, mesh) in neighbours.iter() {
+        meshes.push((b, no_side(), mesh));
+    }
+    // Random data gives pinched vertices (non-manifold edges) and degenerate triangles, but never cracks
+    let report = validate(&meshes);
+    println!("Validation: {}", report);
+    assert!(report.is_watertight(), "{}", report);
+}
+
+// Random density in [-1, 1), only depending on the grid point (with the given grid spacing) nearest to the position
+fn hashed_density(seed: u64, position: [f32; 3], spacing: f32) -> f32 {
+    let mut hash = seed;
+    for coordinate in position {
+        let index = (coordinate / spacing).round() as i64;
+        // splitmix64 step
+        hash = hash
+            .wrapping_add(index as u64)
+            .wrapping_add(0x9e3779b97f4a7c15);
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+        hash ^= hash >> 31;
+    }
+    (hash >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+}
+
+// The 4 blocks of half size (so double resolution) against each side
+fn double_resolution_neighbours(block: &Block<f32>, sides: TransitionSides) -> Vec<Block<f32>> {
+    let base = block.dims.base;
+    let size = block.dims.size;
+    let half = size / 2.0;
+    let mut neighbours = vec![];
+    for side in sides {
+        let (axis, offset) = match side {
+            TransitionSide::LowX => (0, -half),
+            TransitionSide::HighX => (0, size),
+            TransitionSide::LowY => (1, -half),
+            TransitionSide::HighY => (1, size),
+            TransitionSide::LowZ => (2, -half),
+            TransitionSide::HighZ => (2, size),
+        };
+        for quarter in 0..4 {
+            // Offsets on the 2 other axes
+            let along_face = [(quarter & 1) as f32 * half, (quarter >> 1) as f32 * half];
+            let mut along_face = along_face.iter();
+            let neighbour_base: [f32; 3] = std::array::from_fn(|c| {
+                base[c]
+                    + if c == axis {
+                        offset
+                    } else {
+                        *along_face.next().unwrap()
+                    }
+            });
+            neighbours.push(Block::from(neighbour_base, half, block.subdivisions));
+        }
+    }
+    neighbours
 }
 
 fn random_sides(rng: &mut StdRng) -> TransitionSides {
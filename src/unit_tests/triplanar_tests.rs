@@ -0,0 +1,181 @@
+use crate::extraction::extract_from_field;
+use crate::generic_mesh::*;
+use crate::mesh_builder::NormalMode;
+use crate::transition_sides::*;
+use crate::triplanar::*;
+use crate::unit_tests::test_utils::{default_block, sphere, sphere_density};
+use crate::voxel_source::Block;
+use hamcrest2::prelude::*;
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: &[f32], b: &[f32]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn extract(normal_mode: NormalMode) -> Mesh<f32> {
+    let block = default_block(10);
+    extract_from_field(
+        &sphere,
+        &block,
+        0.0,
+        no_side(),
+        GenericMeshBuilder::new().with_normal_mode(normal_mode),
+    )
+    .build()
+}
+
+#[test]
+fn axis_aligned_faces() {
+    let triplanar = Triplanar::new(0.5);
+    let on_high_x = triplanar.project([2.0, 4.0, 6.0], [1.0, 0.0, 0.0]);
+    assert_that!(on_high_x.weights, equal_to([1.0, 0.0, 0.0]));
+    assert_that!(on_high_x.dominant_axis(), equal_to(0));
+    assert_that!(on_high_x.uvs[0], equal_to([-3.0, 2.0]));
+    assert_that!(on_high_x.tangents[0], equal_to([0.0, 0.0, -1.0, 1.0]));
+    // Not mirrored on the other side
+    let on_low_x = triplanar.project([2.0, 4.0, 6.0], [-1.0, 0.0, 0.0]);
+    assert_that!(on_low_x.uvs[0], equal_to([3.0, 2.0]));
+    assert_that!(on_low_x.tangents[0], equal_to([0.0, 0.0, 1.0, 1.0]));
+    let on_high_y = triplanar.project([2.0, 4.0, 6.0], [0.0, 1.0, 0.0]);
+    assert_that!(on_high_y.dominant_axis(), equal_to(1));
+    assert_that!(on_high_y.uvs[1], equal_to([1.0, -3.0]));
+    assert_that!(on_high_y.tangents[1], equal_to([1.0, 0.0, 0.0, 1.0]));
+    let on_low_z = triplanar.project([2.0, 4.0, 6.0], [0.0, 0.0, -1.0]);
+    assert_that!(on_low_z.dominant_axis(), equal_to(2));
+    assert_that!(on_low_z.uvs[2], equal_to([-1.0, 2.0]));
+    assert_that!(on_low_z.tangents[2], equal_to([-1.0, 0.0, 0.0, 1.0]));
+}
+
+#[test]
+fn blend_weights() {
+    let diagonal = 1.0 / 3.0f32.sqrt();
+    let smooth = Triplanar::new(1.0).with_sharpness(1.0);
+    let vertex = smooth.project([0.0; 3], [diagonal, -diagonal, diagonal]);
+    for weight in vertex.weights {
+        assert_that!((weight - 1.0 / 3.0).abs(), less_than(1e-6));
+    }
+    let mesh = extract(NormalMode::Gradient);
+    let attributes = Triplanar::new(1.0).compute(&mesh);
+    for weights in attributes.weights.chunks_exact(3) {
+        let sum: f32 = weights.iter().sum();
+        assert_that!((sum - 1.0).abs(), less_than(1e-5));
+    }
+}
+
+// Tangents must follow the direction in which U increases on the surface, as MikkTSpace does
+#[test]
+fn tangents_follow_uvs() {
+    let mesh = extract(NormalMode::Gradient);
+    let attributes = Triplanar::new(1.0).compute(&mesh);
+    let mut checked = 0;
+    for tri in mesh.triangle_indices.chunks_exact(3) {
+        let p = |i: usize| &mesh.positions[3 * tri[i]..3 * tri[i] + 3];
+        let edge_1 = [p(1)[0] - p(0)[0], p(1)[1] - p(0)[1], p(1)[2] - p(0)[2]];
+        let edge_2 = [p(2)[0] - p(0)[0], p(2)[1] - p(0)[1], p(2)[2] - p(0)[2]];
+        for axis in 0..3 {
+            let uv = |i: usize| &attributes.uvs[axis][2 * tri[i]..2 * tri[i] + 2];
+            // The projection flips where the normal crosses the projection plane: skip triangles doing so
+            let side = |i: usize| mesh.normals[3 * tri[i] + axis] >= 0.0;
+            if side(1) != side(0) || side(2) != side(0) {
+                continue;
+            }
+            let (du_1, dv_1) = (uv(1)[0] - uv(0)[0], uv(1)[1] - uv(0)[1]);
+            let (du_2, dv_2) = (uv(2)[0] - uv(0)[0], uv(2)[1] - uv(0)[1]);
+            let det = du_1 * dv_2 - du_2 * dv_1;
+            if det.abs() < 1e-4 {
+                continue;
+            }
+            let triangle_tangent: Vec<f32> = (0..3)
+                .map(|c| (edge_1[c] * dv_2 - edge_2[c] * dv_1) / det)
+                .collect();
+            let triangle_bitangent: Vec<f32> = (0..3)
+                .map(|c| (edge_2[c] * du_1 - edge_1[c] * du_2) / det)
+                .collect();
+            for &vertex in tri {
+                let normal = &mesh.normals[3 * vertex..3 * vertex + 3];
+                let tangent = &attributes.tangents[axis][4 * vertex..4 * vertex + 4];
+                assert_that!(dot(tangent, normal).abs(), less_than(1e-5));
+                assert_that!((dot(tangent, tangent) - 1.0).abs(), less_than(1e-5));
+                assert_that!(dot(tangent, &triangle_tangent), greater_than(0.0));
+                let bitangent = cross(normal, tangent).map(|c| c * tangent[3]);
+                assert_that!(dot(&bitangent, &triangle_bitangent), greater_than(0.0));
+            }
+            checked += 1;
+        }
+    }
+    assert_that!(checked, greater_than(100));
+}
+
+#[test]
+fn consistent_across_blocks() {
+    let triplanar = Triplanar::new(0.3);
+    let low = Block::from([0.0, 0.0, 0.0], 5.0, 10);
+    let high = Block::from([5.0, 0.0, 0.0], 5.0, 10);
+    let extract_block = |block: &Block<f32>| {
+        let big_sphere = |x: f32, y: f32, z: f32| sphere_density([5.0, 2.5, 2.5], 3.0, x, y, z);
+        let mesh = extract_from_field(
+            &big_sphere,
+            block,
+            0.0,
+            no_side(),
+            GenericMeshBuilder::new(),
+        )
+        .build();
+        let attributes = triplanar.compute(&mesh);
+        (mesh, attributes)
+    };
+    let (low_mesh, low_attributes) = extract_block(&low);
+    let (high_mesh, high_attributes) = extract_block(&high);
+    let mut shared = 0;
+    for i in 0..low_mesh.positions.len() / 3 {
+        if low_mesh.positions[3 * i] != 5.0 {
+            continue;
+        }
+        let j = (0..high_mesh.positions.len() / 3)
+            .find(|j| high_mesh.positions[3 * j..3 * j + 3] == low_mesh.positions[3 * i..3 * i + 3])
+            .unwrap();
+        for axis in 0..3 {
+            assert_that!(
+                &high_attributes.uvs[axis][2 * j..2 * j + 2],
+                equal_to(&low_attributes.uvs[axis][2 * i..2 * i + 2])
+            );
+            assert_that!(
+                &high_attributes.tangents[axis][4 * j..4 * j + 4],
+                equal_to(&low_attributes.tangents[axis][4 * i..4 * i + 4])
+            );
+        }
+        shared += 1;
+    }
+    assert_that!(shared, greater_than(0));
+}
+
+#[test]
+fn meshes_without_normals() {
+    let without = extract(NormalMode::None);
+    let attributes = Triplanar::new(1.0).compute(&without);
+    let vertices = without.positions.len() / 3;
+    assert_that!(attributes.weights.len(), equal_to(3 * vertices));
+    for axis in 0..3 {
+        assert_that!(attributes.uvs[axis].len(), equal_to(2 * vertices));
+        assert_that!(attributes.tangents[axis].len(), equal_to(4 * vertices));
+    }
+    // Averaged triangle normals are close to the gradient ones on a sphere
+    let with = extract(NormalMode::Gradient);
+    let reference = Triplanar::new(1.0).compute(&with);
+    for (averaged, gradient) in attributes
+        .weights
+        .chunks_exact(3)
+        .zip(reference.weights.chunks_exact(3))
+    {
+        for c in 0..3 {
+            assert_that!((averaged[c] - gradient[c]).abs(), less_than(0.2));
+        }
+    }
+}
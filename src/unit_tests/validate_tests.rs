@@ -0,0 +1,112 @@
+use crate::extraction::extract_from_field;
+use crate::generic_mesh::*;
+use crate::transition_sides::TransitionSide::LowZ;
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::{default_block, sphere_density};
+use crate::validate::*;
+use crate::voxel_source::Block;
+use hamcrest2::prelude::*;
+
+fn sphere(x: f32, y: f32, z: f32) -> f32 {
+    sphere_density([5.0; 3], 3.7, x, y, z)
+}
+
+fn sphere_mesh() -> (Block<f32>, Mesh<f32>) {
+    let block = default_block(10);
+    let mesh =
+        extract_from_field(&sphere, &block, 0.0, no_side(), GenericMeshBuilder::new()).build();
+    (block, mesh)
+}
+
+#[test]
+fn closed_mesh_is_valid() {
+    let (block, mesh) = sphere_mesh();
+    let report = validate(&[(&block, no_side(), &mesh)]);
+    assert_that!(report.is_valid(), is(true));
+    assert_that!(report.mesh.num_tris(), equal_to(mesh.num_tris()));
+}
+
+#[test]
+fn winding_problems() {
+    let (block, mut mesh) = sphere_mesh();
+    mesh.triangle_indices.swap(0, 1);
+    let report = validate(&[(&block, no_side(), &mesh)]);
+    assert_that!(report.inconsistent_winding_edges.len(), equal_to(3));
+    assert_that!(report.non_manifold_edges.is_empty(), is(true));
+    assert_that!(report.is_watertight(), is(true));
+    assert_that!(report.is_valid(), is(false));
+}
+
+#[test]
+fn non_manifold_edges() {
+    let (block, mut mesh) = sphere_mesh();
+    let first: Vec<usize> = mesh.triangle_indices[0..3].to_vec();
+    mesh.triangle_indices.extend_from_slice(&first);
+    let report = validate(&[(&block, no_side(), &mesh)]);
+    assert_that!(report.non_manifold_edges.len(), equal_to(3));
+    assert_that!(report.is_valid(), is(false));
+}
+
+#[test]
+fn degenerate_triangles() {
+    let (block, mut mesh) = sphere_mesh();
+    let (a, b) = (mesh.triangle_indices[0], mesh.triangle_indices[1]);
+    mesh.triangle_indices.extend_from_slice(&[a, b, a]);
+    let report = validate(&[(&block, no_side(), &mesh)]);
+    assert_that!(
+        report.degenerate_triangles,
+        equal_to(vec![TriangleRef {
+            mesh: 0,
+            triangle: mesh.num_tris() - 1,
+        }])
+    );
+    assert_that!(report.mesh.num_tris(), equal_to(mesh.num_tris() - 1));
+}
+
+// Ground crossing all the blocks, and a coarse block above 4 finer ones
+fn coarse_over_fine(coarse_sides: TransitionSides) -> ValidationReport<f32> {
+    let ground = |x: f32, y: f32, z: f32| 10.0 + (x * 0.3).sin() + (y * 0.4).cos() - z;
+    let coarse = Block::from([0.0, 0.0, 10.0], 10.0, 5);
+    let coarse_mesh = extract_from_field(
+        &ground,
+        &coarse,
+        0.0,
+        coarse_sides,
+        GenericMeshBuilder::new(),
+    )
+    .build();
+    let fine: Vec<(Block<f32>, Mesh<f32>)> = (0..4)
+        .map(|i| {
+            let block = Block::from([(i & 1) as f32 * 5.0, (i >> 1) as f32 * 5.0, 5.0], 5.0, 5);
+            let mesh =
+                extract_from_field(&ground, &block, 0.0, no_side(), GenericMeshBuilder::new())
+                    .build();
+            (block, mesh)
+        })
+        .collect();
+    let mut meshes = vec![(&coarse, coarse_sides, &coarse_mesh)];
+    for (block, mesh) in fine.iter() {
+        meshes.push((block, no_side(), mesh));
+    }
+    validate(&meshes)
+}
+
+#[test]
+fn cracks_between_resolutions() {
+    let report = coarse_over_fine(LowZ.into());
+    assert_that!(report.is_valid(), is(true));
+    let report = coarse_over_fine(no_side());
+    assert_that!(report.is_watertight(), is(false));
+    for edge in report.unmatched_edges.iter() {
+        for position in report.edge_positions(*edge) {
+            assert_that!(position[2], equal_to(10.0));
+        }
+    }
+    assert_that!(
+        report.to_string().starts_with(&format!(
+            "{} unmatched edges, 0 non-manifold edges",
+            report.unmatched_edges.len()
+        )),
+        is(true)
+    );
+}
@@ -0,0 +1,193 @@
+use crate::extraction::{extract_from_field, extract_separated_from_field};
+use crate::generic_mesh::*;
+use crate::transition_sides::TransitionSide::HighX;
+use crate::transition_sides::*;
+use crate::unit_tests::test_utils::{default_block, sphere_density};
+use crate::voxel_source::Block;
+use crate::weld::*;
+use hamcrest2::prelude::*;
+
+fn sphere(x: f32, y: f32, z: f32) -> f32 {
+    sphere_density([5.0; 3], 3.7, x, y, z)
+}
+
+fn mesh(block: &Block<f32>, sides: TransitionSides) -> Mesh<f32> {
+    extract_from_field(&sphere, block, 0.0, sides, GenericMeshBuilder::new()).build()
+}
+
+// Each undirected edge must be used by exactly two triangles
+fn is_closed(mesh: &Mesh<f32>) -> bool {
+    let mut edges = std::collections::HashMap::new();
+    for tri in mesh.triangle_indices.chunks_exact(3) {
+        for k in 0..3 {
+            let (a, b) = (tri[k], tri[(k + 1) % 3]);
+            *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+        }
+    }
+    edges.values().all(|count| *count == 2)
+}
+
+#[test]
+fn same_resolution_blocks() {
+    let whole = default_block(10);
+    let reference = mesh(&whole, no_side());
+    assert_that!(is_closed(&reference), is(true));
+    let blocks: Vec<Block<f32>> = (0..8)
+        .map(|i| {
+            let base = [
+                (i & 1) as f32 * 5.0,
+                (i >> 1 & 1) as f32 * 5.0,
+                (i >> 2) as f32 * 5.0,
+            ];
+            Block::from(base, 5.0, 5)
+        })
+        .collect();
+    let meshes: Vec<Mesh<f32>> = blocks.iter().map(|b| mesh(b, no_side())).collect();
+    let mut welder = MeshWelder::new();
+    for (block, mesh) in blocks.iter().zip(meshes.iter()) {
+        welder.add_block(block, no_side(), mesh);
+    }
+    let welded = welder.weld();
+    let total_vertices: usize = meshes.iter().map(|m| m.positions.len() / 3).sum();
+    assert_that!(
+        welded.mesh.positions.len() / 3,
+        equal_to(reference.positions.len() / 3)
+    );
+    assert_that!(
+        welded.welded_vertices,
+        equal_to(total_vertices - reference.positions.len() / 3)
+    );
+    assert_that!(welded.mesh.num_tris(), equal_to(reference.num_tris()));
+    assert_that!(welded.unmatched_edges.is_empty(), is(true));
+    assert_that!(is_closed(&welded.mesh), is(true));
+    // Welded normals are normalized
+    for normal in welded.mesh.normals.chunks_exact(3) {
+        let norm = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+        assert_that!((norm - 1.0).abs(), less_than(1e-5));
+    }
+}
+
+fn big_sphere(x: f32, y: f32, z: f32) -> f32 {
+    sphere_density([0.0; 3], 12.3, x, y, z)
+}
+
+// The 4 blocks with twice the resolution of the [0, 10] block, on its high x side
+fn fine_blocks() -> Vec<(Block<f32>, Mesh<f32>)> {
+    (0..4)
+        .map(|i| {
+            let block = Block::from([10.0, (i & 1) as f32 * 5.0, (i >> 1) as f32 * 5.0], 5.0, 5);
+            let mesh = extract_from_field(
+                &big_sphere,
+                &block,
+                0.0,
+                no_side(),
+                GenericMeshBuilder::new(),
+            )
+            .build();
+            (block, mesh)
+        })
+        .collect()
+}
+
+fn coarse_and_fine(coarse_sides: TransitionSides) -> WeldedMesh<f32> {
+    let coarse = default_block(5);
+    let coarse_mesh = extract_from_field(
+        &big_sphere,
+        &coarse,
+        0.0,
+        coarse_sides,
+        GenericMeshBuilder::new(),
+    )
+    .build();
+    let fine = fine_blocks();
+    let mut welder = MeshWelder::new();
+    welder.add_block(&coarse, coarse_sides, &coarse_mesh);
+    for (block, mesh) in fine.iter() {
+        welder.add_block(block, no_side(), mesh);
+    }
+    welder.weld()
+}
+
+#[test]
+fn transition_faces_are_welded_to_finer_blocks() {
+    let welded = coarse_and_fine(HighX.into());
+    assert_that!(welded.welded_vertices, greater_than(0));
+    assert_that!(welded.unmatched_edges.is_empty(), is(true));
+}
+
+#[test]
+fn cracks_are_reported() {
+    let welded = coarse_and_fine(no_side());
+    assert_that!(welded.unmatched_edges.is_empty(), is(false));
+    for [a, b] in welded.unmatched_edges.iter() {
+        assert_that!(welded.mesh.positions[3 * a], equal_to(10.0));
+        assert_that!(welded.mesh.positions[3 * b], equal_to(10.0));
+    }
+}
+
+#[test]
+fn separated_meshes() {
+    let coarse = default_block(5);
+    let separated = extract_separated_from_field(&big_sphere, &coarse, 0.0, all_sides(), || {
+        GenericMeshBuilder::new().with_secondary_positions()
+    })
+    .map(|builder| builder.build());
+    let fine = fine_blocks();
+    let mut welder = MeshWelder::new();
+    welder.add_separated(&coarse, HighX.into(), &separated);
+    for (block, mesh) in fine.iter() {
+        welder.add_block(block, no_side(), mesh);
+    }
+    let welded = welder.weld();
+    assert_that!(welded.unmatched_edges.is_empty(), is(true));
+    // Same as with the mesh extracted with the transition side
+    let expected = coarse_and_fine(HighX.into());
+    assert_that!(
+        welded.mesh.positions.len(),
+        equal_to(expected.mesh.positions.len())
+    );
+    assert_that!(welded.mesh.num_tris(), equal_to(expected.mesh.num_tris()));
+    // Without the transition side, the regular mesh does not match the finer blocks
+    let mut welder = MeshWelder::new();
+    welder.add_separated(&coarse, no_side(), &separated);
+    for (block, mesh) in fine.iter() {
+        welder.add_block(block, no_side(), mesh);
+    }
+    assert_that!(welder.weld().unmatched_edges.is_empty(), is(false));
+}
+
+#[test]
+fn corner_lines_of_missing_blocks_are_outer_boundary() {
+    // 3 blocks in an L, without the block diagonal to the first one in the xy plane
+    let blocks =
+        [[0.0, 0.0, 0.0], [5.0, 0.0, 0.0], [0.0, 5.0, 0.0]].map(|base| Block::from(base, 5.0, 5));
+    // Plane through the x=5, y=5 corner line, going into the first block and the missing one
+    let plane = |x: f32, y: f32, _: f32| (x - 5.0) - 0.37 * (y - 5.0);
+    let meshes: Vec<Mesh<f32>> = blocks
+        .iter()
+        .map(|block| {
+            extract_from_field(&plane, block, 0.0, no_side(), GenericMeshBuilder::new()).build()
+        })
+        .collect();
+    let mut welder = MeshWelder::new();
+    for (block, mesh) in blocks.iter().zip(meshes.iter()) {
+        welder.add_block(block, no_side(), mesh);
+    }
+    let welded = welder.weld();
+    // The mesh has open edges on the corner line, but they are on the outer boundary
+    let on_corner_line =
+        |i: usize| welded.mesh.positions[3 * i] == 5.0 && welded.mesh.positions[3 * i + 1] == 5.0;
+    let mut uses = std::collections::HashMap::new();
+    for tri in welded.mesh.triangle_indices.chunks_exact(3) {
+        for k in 0..3 {
+            let (a, b) = (tri[k], tri[(k + 1) % 3]);
+            *uses.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+        }
+    }
+    let open_on_corner_line = uses
+        .iter()
+        .filter(|((a, b), count)| **count == 1 && on_corner_line(*a) && on_corner_line(*b))
+        .count();
+    assert_that!(open_on_corner_line, greater_than(0));
+    assert_that!(welded.unmatched_edges.is_empty(), is(true));
+}
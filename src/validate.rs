@@ -0,0 +1,179 @@
+/*!
+Checking that a set of block meshes forms a crack-free surface
+
+[validate] merges the meshes of several blocks (see [weld](crate::weld)), and reports:
+ * unmatched edges: edges on faces shared by two blocks, used by a single triangle. These are cracks between the blocks,
+   typically because a block is missing a transition side towards a double resolution neighbour
+ * non-manifold edges: edges used by more than 2 triangles
+ * inconsistent winding: edges used by 2 triangles in the same direction (so the triangles face opposite ways)
+ * degenerate triangles (with no area) in the block meshes
+
+```rust
+use transvoxel::generic_mesh::GenericMeshBuilder;
+use transvoxel::prelude::*;
+use transvoxel::transition_sides::TransitionSide;
+use transvoxel::validate::validate;
+
+let field = |x: f32, y: f32, z: f32| 12.3 - (x * x + y * y + z * z).sqrt();
+let coarse = Block::from([0.0, 0.0, 0.0], 10.0, 5);
+let fine = Block::from([10.0, 0.0, 0.0], 5.0, 5);
+let fine_mesh = extract_from_field(&field, &fine, 0.0, transition_sides::no_side(), GenericMeshBuilder::new()).build();
+
+// Without a transition side toward the finer block, there are cracks
+let sides = transition_sides::no_side();
+let coarse_mesh = extract_from_field(&field, &coarse, 0.0, sides, GenericMeshBuilder::new()).build();
+let report = validate(&[(&coarse, sides, &coarse_mesh), (&fine, transition_sides::no_side(), &fine_mesh)]);
+assert!(!report.is_watertight());
+
+let sides = TransitionSide::HighX.into();
+let coarse_mesh = extract_from_field(&field, &coarse, 0.0, sides, GenericMeshBuilder::new()).build();
+let report = validate(&[(&coarse, sides, &coarse_mesh), (&fine, transition_sides::no_side(), &fine_mesh)]);
+assert!(report.is_watertight(), "{}", report);
+```
+*/
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::generic_mesh::{face_normal, Mesh};
+use crate::traits::Coordinate;
+use crate::transition_sides::TransitionSides;
+use crate::voxel_source::Block;
+use crate::weld::MeshWelder;
+
+/// A triangle of one of the validated meshes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TriangleRef {
+    /// Index of the mesh, in the validated ones
+    pub mesh: usize,
+    /// Index of the triangle in that mesh
+    pub triangle: usize,
+}
+
+/// Result of [validate]. Edges are pairs of vertex indices in `mesh`
+#[derive(Debug)]
+pub struct ValidationReport<F>
+where
+    F: Coordinate,
+{
+    /// The merged mesh the edges refer to
+    pub mesh: Mesh<F>,
+    /// Edges used by a single triangle, on faces shared by two blocks
+    pub unmatched_edges: Vec<[usize; 2]>,
+    /// Edges used by more than 2 triangles
+    pub non_manifold_edges: Vec<[usize; 2]>,
+    /// Edges used by 2 triangles in the same direction
+    pub inconsistent_winding_edges: Vec<[usize; 2]>,
+    /// Triangles with no area, in the validated meshes
+    pub degenerate_triangles: Vec<TriangleRef>,
+}
+
+impl<F> ValidationReport<F>
+where
+    F: Coordinate,
+{
+    /// Whether there are no cracks between the blocks
+    pub fn is_watertight(&self) -> bool {
+        self.unmatched_edges.is_empty()
+    }
+
+    /// Whether no problem at all was found
+    pub fn is_valid(&self) -> bool {
+        self.is_watertight()
+            && self.non_manifold_edges.is_empty()
+            && self.inconsistent_winding_edges.is_empty()
+            && self.degenerate_triangles.is_empty()
+    }
+
+    /// Positions of the two vertices of an edge
+    pub fn edge_positions(&self, edge: [usize; 2]) -> [[F; 3]; 2] {
+        edge.map(|i| {
+            [
+                self.mesh.positions[3 * i],
+                self.mesh.positions[3 * i + 1],
+                self.mesh.positions[3 * i + 2],
+            ]
+        })
+    }
+}
+
+impl<F> Display for ValidationReport<F>
+where
+    F: Coordinate + Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} unmatched edges, {} non-manifold edges, {} edges with inconsistent winding, {} degenerate triangles",
+            self.unmatched_edges.len(),
+            self.non_manifold_edges.len(),
+            self.inconsistent_winding_edges.len(),
+            self.degenerate_triangles.len()
+        )?;
+        if let Some(edge) = self.unmatched_edges.first() {
+            let [a, b] = self.edge_positions(*edge);
+            write!(
+                f,
+                " (first unmatched edge: [{}, {}, {}] - [{}, {}, {}])",
+                a[0], a[1], a[2], b[0], b[1], b[2]
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/**
+Validate the meshes of some blocks, each extracted with the given transition sides (or, if it was built with
+secondary positions, to be used with these sides: see [MeshWelder::add_block])
+*/
+pub fn validate<F, R>(meshes: &[(&Block<F>, TransitionSides, &Mesh<F, R>)]) -> ValidationReport<F>
+where
+    F: Coordinate,
+{
+    let mut welder = MeshWelder::new();
+    let mut degenerate_triangles = vec![];
+    for (mesh_index, (block, transition_sides, mesh)) in meshes.iter().enumerate() {
+        welder.add_block(block, *transition_sides, mesh);
+        let positions = mesh
+            .positions_for_sides(*transition_sides)
+            .unwrap_or_else(|| mesh.positions.clone());
+        let position = |i: usize| [positions[3 * i], positions[3 * i + 1], positions[3 * i + 2]];
+        for (triangle, tri) in mesh.triangle_indices.chunks_exact(3).enumerate() {
+            let normal = face_normal(&position(tri[0]), &position(tri[1]), &position(tri[2]));
+            if normal == [F::zero(); 3] {
+                degenerate_triangles.push(TriangleRef {
+                    mesh: mesh_index,
+                    triangle,
+                });
+            }
+        }
+    }
+    let welded = welder.weld();
+    // Uses of each edge, in each direction (from the lowest index to the highest, and the other way)
+    let mut uses: HashMap<[usize; 2], [usize; 2]> = HashMap::new();
+    for tri in welded.mesh.triangle_indices.chunks_exact(3) {
+        for k in 0..3 {
+            let (a, b) = (tri[k], tri[(k + 1) % 3]);
+            let directions = uses.entry([a.min(b), a.max(b)]).or_default();
+            directions[(a > b) as usize] += 1;
+        }
+    }
+    let mut non_manifold_edges = vec![];
+    let mut inconsistent_winding_edges = vec![];
+    for (edge, [forward, backward]) in uses {
+        if forward + backward > 2 {
+            non_manifold_edges.push(edge);
+        } else if forward == 2 || backward == 2 {
+            inconsistent_winding_edges.push(edge);
+        }
+    }
+    non_manifold_edges.sort_unstable();
+    inconsistent_winding_edges.sort_unstable();
+    ValidationReport {
+        mesh: welded.mesh,
+        unmatched_edges: welded.unmatched_edges,
+        non_manifold_edges,
+        inconsistent_winding_edges,
+        degenerate_triangles,
+    }
+}
@@ -178,6 +178,24 @@ impl HighResolutionVoxelIndex {
         rotation.to_position_in_block(block.subdivisions, self)
     }
 
+    /**
+    Index of this voxel on a grid of double the block's resolution (with `2 * block_subdivisions` cells across the block, and the same origin).
+    Like for [RegularVoxelIndex], it can refer to a voxel slightly outside of the block (from -1 to `2 * block_subdivisions` + 1 included)
+    */
+    pub fn to_double_resolution_index(&self, block_subdivisions: usize) -> RegularVoxelIndex {
+        let rot = Rotation::for_side(self.cell.side);
+        let cell = self.cell;
+        let delta = self.delta;
+        let double_size = 2 * block_subdivisions as isize;
+        let u = 2 * cell.cell_u as isize + delta.u;
+        let v = 2 * cell.cell_v as isize + delta.v;
+        RegularVoxelIndex {
+            x: double_size * rot.uvw_base.x + u * rot.u.x + v * rot.v.x + delta.w * rot.w.x,
+            y: double_size * rot.uvw_base.y + u * rot.u.y + v * rot.v.y + delta.w * rot.w.y,
+            z: double_size * rot.uvw_base.z + u * rot.u.z + v * rot.v.z + delta.w * rot.w.z,
+        }
+    }
+
     /// `self` being a double-resolution voxel on a transition face in this block, it coincides with a regular voxel on the neighbouring block at that face. This gives that voxel's index within that block
     pub fn to_higher_res_neighbour_block_index(&self, this_block_size: usize) -> RegularVoxelIndex {
         let higher_res_block_size = this_block_size as isize * 2;
@@ -3,7 +3,7 @@ Traits/structs for providing access to a voxel grid
 */
 
 use crate::{
-    traits::{Coordinate, VoxelData},
+    traits::{Coordinate, DensityFloat, VoxelData},
     voxel_coordinates::{HighResolutionVoxelIndex, RegularVoxelIndex},
 };
 
@@ -23,6 +23,7 @@ let a_block = Block {
 let another_block = Block::from([10.0, 20.0, 30.0], 10.0, 8);
 ```
 */
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Block<C>
 where
@@ -50,6 +51,7 @@ where
 /**
 A cubic zone of the world, for which to run an extraction
 */
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BlockDims<C>
 where
@@ -87,6 +89,46 @@ pub trait VoxelSource<V: VoxelData> {
     will try to call `get_density` instead
     */
     fn get_transition_voxel(&mut self, index: &HighResolutionVoxelIndex) -> V;
+
+    /**
+    Optionally, conservative bounds (min, max) of the densities of the voxels of the block.
+    This must include all regular voxels from 0 to `subdivisions`, and the transition voxels on the block faces
+    (voxels out of the block, used only for gradients, do not matter).
+
+    When these bounds are all on one side of the threshold, the extraction skips the block without querying any voxel.
+    The default implementation gives no bounds.
+    */
+    fn density_bounds(&self) -> Option<(V::Density, V::Density)> {
+        None
+    }
+
+    /**
+    Optionally, the exact gradient of the density (in world x, y, z directions) at a regular voxel.
+
+    When provided, the extraction uses it instead of estimating the gradient from the neighbouring voxels, which gives
+    exact normals and avoids querying the voxels out of the block.
+    Only the direction matters: the gradients get normalized into normals.
+    The default implementation gives no gradient.
+    */
+    fn get_regular_voxel_gradient(
+        &mut self,
+        voxel_index: &RegularVoxelIndex,
+    ) -> Option<[DensityFloat<V>; 3]> {
+        let _ = voxel_index;
+        None
+    }
+
+    /**
+    Optionally, the exact gradient of the density at a transition voxel. See [VoxelSource::get_regular_voxel_gradient].
+    The default implementation gives no gradient.
+    */
+    fn get_transition_voxel_gradient(
+        &mut self,
+        index: &HighResolutionVoxelIndex,
+    ) -> Option<[DensityFloat<V>; 3]> {
+        let _ = index;
+        None
+    }
 }
 
 /**
@@ -113,22 +155,60 @@ where
     V: VoxelData,
 {
     fn get_regular_voxel(&mut self, voxel_index: &RegularVoxelIndex) -> V {
+        let [x, y, z] = self.regular_voxel_position(voxel_index);
+        self.field.get_data(x, y, z)
+    }
+
+    fn get_transition_voxel(&mut self, index: &HighResolutionVoxelIndex) -> V {
+        let [x, y, z] = self.transition_voxel_position(index);
+        self.field.get_data(x, y, z)
+    }
+
+    fn density_bounds(&self) -> Option<(V::Density, V::Density)> {
+        let base = self.block.dims.base;
+        let size = self.block.dims.size;
+        let max = [base[0] + size, base[1] + size, base[2] + size];
+        self.field.density_bounds(base, max)
+    }
+
+    fn get_regular_voxel_gradient(
+        &mut self,
+        voxel_index: &RegularVoxelIndex,
+    ) -> Option<[DensityFloat<V>; 3]> {
+        let [x, y, z] = self.regular_voxel_position(voxel_index);
+        self.field.get_gradient(x, y, z)
+    }
+
+    fn get_transition_voxel_gradient(
+        &mut self,
+        index: &HighResolutionVoxelIndex,
+    ) -> Option<[DensityFloat<V>; 3]> {
+        let [x, y, z] = self.transition_voxel_position(index);
+        self.field.get_gradient(x, y, z)
+    }
+}
+
+impl<S, C> WorldMappingVoxelSource<'_, S, C>
+where
+    C: Coordinate,
+{
+    fn regular_voxel_position(&self, voxel_index: &RegularVoxelIndex) -> [C; 3] {
         let x = self.block.dims.base[0]
             + self.block.dims.size * C::from_ratio(voxel_index.x, self.block.subdivisions);
         let y = self.block.dims.base[1]
             + self.block.dims.size * C::from_ratio(voxel_index.y, self.block.subdivisions);
         let z = self.block.dims.base[2]
             + self.block.dims.size * C::from_ratio(voxel_index.z, self.block.subdivisions);
-        self.field.get_data(x, y, z)
+        [x, y, z]
     }
 
-    fn get_transition_voxel(&mut self, index: &HighResolutionVoxelIndex) -> V {
+    fn transition_voxel_position(&self, index: &HighResolutionVoxelIndex) -> [C; 3] {
         let rotation = super::implementation::rotation::Rotation::for_side(index.cell.side);
         let position_in_block = rotation.to_position_in_block::<C>(self.block.subdivisions, index);
         let x = self.block.dims.base[0] + self.block.dims.size * position_in_block.x;
         let y = self.block.dims.base[1] + self.block.dims.size * position_in_block.y;
         let z = self.block.dims.base[2] + self.block.dims.size * position_in_block.z;
-        self.field.get_data(x, y, z)
+        [x, y, z]
     }
 }
 
@@ -145,6 +225,24 @@ where
     fn get_transition_voxel(&mut self, index: &HighResolutionVoxelIndex) -> V {
         (**self).get_transition_voxel(index)
     }
+
+    fn density_bounds(&self) -> Option<(V::Density, V::Density)> {
+        (**self).density_bounds()
+    }
+
+    fn get_regular_voxel_gradient(
+        &mut self,
+        voxel_index: &RegularVoxelIndex,
+    ) -> Option<[DensityFloat<V>; 3]> {
+        (**self).get_regular_voxel_gradient(voxel_index)
+    }
+
+    fn get_transition_voxel_gradient(
+        &mut self,
+        index: &HighResolutionVoxelIndex,
+    ) -> Option<[DensityFloat<V>; 3]> {
+        (**self).get_transition_voxel_gradient(index)
+    }
 }
 
 /**
@@ -155,6 +253,24 @@ pub trait DataField<V: VoxelData, C: Coordinate> {
     Obtain the data at the given point in space
     */
     fn get_data(&mut self, x: C, y: C, z: C) -> V;
+
+    /**
+    Optionally, conservative bounds (min, max) of the densities in the box between `min` and `max` (included).
+    See [VoxelSource::density_bounds]. The default implementation gives no bounds.
+    */
+    fn density_bounds(&self, min: [C; 3], max: [C; 3]) -> Option<(V::Density, V::Density)> {
+        let _ = (min, max);
+        None
+    }
+
+    /**
+    Optionally, the exact gradient of the density at the given point. See [VoxelSource::get_regular_voxel_gradient].
+    The default implementation gives no gradient.
+    */
+    fn get_gradient(&mut self, x: C, y: C, z: C) -> Option<[DensityFloat<V>; 3]> {
+        let _ = (x, y, z);
+        None
+    }
 }
 
 /**
@@ -164,6 +280,14 @@ impl<V: VoxelData, C: Coordinate> DataField<V, C> for &mut dyn DataField<V, C> {
     fn get_data(&mut self, x: C, y: C, z: C) -> V {
         (*self).get_data(x, y, z)
     }
+
+    fn density_bounds(&self, min: [C; 3], max: [C; 3]) -> Option<(V::Density, V::Density)> {
+        (**self).density_bounds(min, max)
+    }
+
+    fn get_gradient(&mut self, x: C, y: C, z: C) -> Option<[DensityFloat<V>; 3]> {
+        (**self).get_gradient(x, y, z)
+    }
 }
 
 /**
@@ -177,3 +301,20 @@ where
         self(x, y, z)
     }
 }
+
+// Bounds of the densities of some voxels. None if there is no voxel
+pub(crate) fn density_bounds_of<V, I>(voxels: I) -> Option<(V::Density, V::Density)>
+where
+    V: VoxelData,
+    I: IntoIterator<Item = V>,
+{
+    voxels.into_iter().fold(None, |bounds, voxel| {
+        let d = voxel.density();
+        match bounds {
+            None => Some((d, d)),
+            Some((min, max)) => {
+                Some((if d < min { d } else { min }, if d > max { d } else { max }))
+            }
+        }
+    })
+}
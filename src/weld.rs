@@ -0,0 +1,331 @@
+/*!
+Merging the meshes of several blocks into one watertight mesh
+
+Each block mesh is independent: vertices on the faces shared by adjacent blocks are duplicated.
+[MeshWelder] merges block meshes into a single indexed mesh, welding all coincident vertices (within a tolerance).
+This includes the vertices of the high resolution faces of transition cells, which match the vertices on the faces of the
+finer neighbouring blocks, and the vertices shared by the regular and transition cells of a block.
+
+Edges used by only one triangle, and surrounded by the blocks on all sides (typically lying on a face shared by two of the
+blocks), are reported as unmatched:
+they reveal cracks, typically because of a missing transition side, or because of blocks not being aligned.
+Open edges on the outer boundary of the merged blocks are expected, and not reported. This includes the edges on the
+corner line of two blocks, when their diagonal neighbour is absent.
+
+```rust
+use transvoxel::generic_mesh::GenericMeshBuilder;
+use transvoxel::prelude::*;
+use transvoxel::transition_sides::TransitionSide;
+use transvoxel::weld::MeshWelder;
+
+let field = |x: f32, y: f32, z: f32| 12.3 - (x * x + y * y + z * z).sqrt();
+// A block, and a block with twice the resolution on its high x side
+let coarse = Block::from([0.0, 0.0, 0.0], 10.0, 5);
+let fine = Block::from([10.0, 0.0, 0.0], 5.0, 5);
+let coarse_sides = TransitionSide::HighX.into();
+let coarse_mesh = extract_from_field(&field, &coarse, 0.0, coarse_sides, GenericMeshBuilder::new()).build();
+let fine_mesh = extract_from_field(&field, &fine, 0.0, transition_sides::no_side(), GenericMeshBuilder::new()).build();
+
+let mut welder = MeshWelder::new();
+welder.add_block(&coarse, coarse_sides, &coarse_mesh);
+welder.add_block(&fine, transition_sides::no_side(), &fine_mesh);
+let welded = welder.weld();
+assert!(welded.welded_vertices > 0);
+assert!(welded.unmatched_edges.is_empty());
+```
+*/
+
+use std::collections::HashMap;
+
+use crate::extraction::SeparatedMeshes;
+use crate::generic_mesh::Mesh;
+use crate::traits::Coordinate;
+use crate::transition_sides::TransitionSides;
+use crate::voxel_source::{Block, BlockDims};
+
+/// Result of [MeshWelder::weld]
+#[derive(Debug)]
+pub struct WeldedMesh<F>
+where
+    F: Coordinate,
+{
+    /// The merged mesh. Normals of welded vertices are averaged. It has normals only if all the block meshes had some
+    pub mesh: Mesh<F>,
+    /// How many vertices were merged into another one
+    pub welded_vertices: usize,
+    /// Edges (pairs of vertex indices in `mesh`) used by a single triangle, and surrounded by blocks on all sides
+    pub unmatched_edges: Vec<[usize; 2]>,
+}
+
+// What is kept from a block for welding
+struct WeldPart<'a, F>
+where
+    F: Coordinate,
+{
+    dims: BlockDims<F>,
+    cell_size: F,
+    positions: Vec<F>,
+    normals: &'a [F],
+    triangle_indices: &'a [usize],
+}
+
+/// Collects block meshes, to merge them
+pub struct MeshWelder<'a, F>
+where
+    F: Coordinate,
+{
+    parts: Vec<WeldPart<'a, F>>,
+    tolerance: Option<F>,
+}
+
+#[allow(clippy::new_without_default)]
+impl<'a, F> MeshWelder<'a, F>
+where
+    F: Coordinate,
+{
+    /// A welder with no blocks yet
+    pub fn new() -> Self {
+        Self {
+            parts: vec![],
+            tolerance: None,
+        }
+    }
+
+    /// Maximum distance (on each axis) between vertices to weld. The default is a thousandth of the smallest cell size
+    pub fn with_tolerance(mut self, tolerance: F) -> Self {
+        self.tolerance = Some(tolerance);
+        self
+    }
+
+    /**
+    Add the mesh extracted for a block. `transition_sides` are the sides the mesh should be used with: if it was built with
+    secondary positions, they are applied for these sides (see [Mesh::positions_for_sides]). Otherwise, they must be the
+    sides the mesh was extracted with
+    */
+    pub fn add_block<R>(
+        &mut self,
+        block: &Block<F>,
+        transition_sides: TransitionSides,
+        mesh: &'a Mesh<F, R>,
+    ) {
+        let positions = mesh
+            .positions_for_sides(transition_sides)
+            .unwrap_or_else(|| mesh.positions.clone());
+        self.add_part(block, positions, mesh);
+    }
+
+    /**
+    Add the meshes of a block extracted with [extract_separated](crate::extraction::extract_separated), as they should be
+    drawn with the given transition sides: the transition meshes of these sides are included (and welded to the regular mesh),
+    and secondary positions are applied for these sides
+    */
+    pub fn add_separated<R>(
+        &mut self,
+        block: &Block<F>,
+        transition_sides: TransitionSides,
+        meshes: &'a SeparatedMeshes<Mesh<F, R>>,
+    ) {
+        let transitions = transition_sides
+            .into_iter()
+            .filter_map(|side| meshes.transition(side));
+        for mesh in std::iter::once(&meshes.regular).chain(transitions) {
+            let positions = mesh
+                .positions_for_sides(transition_sides)
+                .unwrap_or_else(|| mesh.positions.clone());
+            self.add_part(block, positions, mesh);
+        }
+    }
+
+    fn add_part<R>(&mut self, block: &Block<F>, positions: Vec<F>, mesh: &'a Mesh<F, R>) {
+        self.parts.push(WeldPart {
+            dims: block.dims,
+            cell_size: block.dims.size / F::from(block.subdivisions).unwrap(),
+            positions,
+            normals: &mesh.normals,
+            triangle_indices: &mesh.triangle_indices,
+        });
+    }
+
+    /// Merge all the added meshes
+    pub fn weld(&self) -> WeldedMesh<F> {
+        let smallest_cell = self
+            .parts
+            .iter()
+            .map(|part| part.cell_size)
+            .fold(F::infinity(), F::min);
+        let tolerance = self
+            .tolerance
+            .unwrap_or_else(|| smallest_cell / F::from(1000).unwrap());
+        let with_normals = self.parts.iter().all(|part| !part.normals.is_empty());
+        let mut positions: Vec<F> = vec![];
+        let mut normals: Vec<F> = vec![];
+        let mut triangle_indices: Vec<usize> = vec![];
+        let mut welded_vertices = 0;
+        let mut grid = SpatialHash::new(tolerance);
+        for part in self.parts.iter() {
+            let mut remap = Vec::with_capacity(part.positions.len() / 3);
+            for (i, position) in part.positions.chunks_exact(3).enumerate() {
+                let position = [position[0], position[1], position[2]];
+                let index = match grid.find(&position, &positions) {
+                    Some(index) => {
+                        welded_vertices += 1;
+                        if with_normals {
+                            for c in 0..3 {
+                                normals[3 * index + c] =
+                                    normals[3 * index + c] + part.normals[3 * i + c];
+                            }
+                        }
+                        index
+                    }
+                    None => {
+                        let index = positions.len() / 3;
+                        positions.extend_from_slice(&position);
+                        if with_normals {
+                            normals.extend_from_slice(&part.normals[3 * i..3 * i + 3]);
+                        }
+                        grid.insert(&position, index);
+                        index
+                    }
+                };
+                remap.push(index);
+            }
+            for tri in part.triangle_indices.chunks_exact(3) {
+                let [a, b, c] = [remap[tri[0]], remap[tri[1]], remap[tri[2]]];
+                // Welding can collapse small triangles
+                if a != b && b != c && c != a {
+                    triangle_indices.extend_from_slice(&[a, b, c]);
+                }
+            }
+        }
+        for normal in normals.chunks_exact_mut(3) {
+            let norm =
+                (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+            if norm > F::zero() {
+                normal.iter_mut().for_each(|c| *c = *c / norm);
+            }
+        }
+        let unmatched_edges =
+            self.unmatched_edges(&positions, &triangle_indices, tolerance, smallest_cell);
+        WeldedMesh {
+            mesh: Mesh {
+                positions,
+                normals,
+                triangle_indices,
+                secondary_positions: None,
+                near_face_mask: None,
+                voxel_data: None,
+                colors: None,
+                attributes: vec![],
+            },
+            welded_vertices,
+            unmatched_edges,
+        }
+    }
+
+    fn unmatched_edges(
+        &self,
+        positions: &[F],
+        triangle_indices: &[usize],
+        tolerance: F,
+        smallest_cell: F,
+    ) -> Vec<[usize; 2]> {
+        let mut blocks: Vec<&BlockDims<F>> = vec![];
+        for part in self.parts.iter() {
+            if !blocks.contains(&&part.dims) {
+                blocks.push(&part.dims);
+            }
+        }
+        // Points this close to an edge surrounded by blocks are all in some block, unlike for edges on the outer boundary
+        let probe = smallest_cell / F::from(100).unwrap();
+        let mut uses: HashMap<[usize; 2], usize> = HashMap::new();
+        for tri in triangle_indices.chunks_exact(3) {
+            for k in 0..3 {
+                let (a, b) = (tri[k], tri[(k + 1) % 3]);
+                *uses.entry([a.min(b), a.max(b)]).or_default() += 1;
+            }
+        }
+        let two = F::one() + F::one();
+        let mut unmatched: Vec<[usize; 2]> = uses
+            .into_iter()
+            .filter(|(_, count)| *count == 1)
+            .map(|(edge, _)| edge)
+            .filter(|[a, b]| {
+                let middle: [F; 3] =
+                    std::array::from_fn(|c| (positions[3 * a + c] + positions[3 * b + c]) / two);
+                let containing_blocks = blocks
+                    .iter()
+                    .filter(|dims| in_block(dims, &middle, tolerance))
+                    .count();
+                // Probing the 8 octants at once: one axis at a time misses the gap at the
+                // corner line of 2 blocks whose diagonal neighbour is absent
+                let surrounded = (0..8).all(|octant| {
+                    let around: [F; 3] = std::array::from_fn(|c| {
+                        if octant & (1 << c) == 0 {
+                            middle[c] - probe
+                        } else {
+                            middle[c] + probe
+                        }
+                    });
+                    blocks.iter().any(|dims| in_block(dims, &around, F::zero()))
+                });
+                containing_blocks >= 2 && surrounded
+            })
+            .collect();
+        unmatched.sort_unstable();
+        unmatched
+    }
+}
+
+fn in_block<F: Coordinate>(dims: &BlockDims<F>, position: &[F; 3], tolerance: F) -> bool {
+    (0..3).all(|c| {
+        position[c] >= dims.base[c] - tolerance
+            && position[c] <= dims.base[c] + dims.size + tolerance
+    })
+}
+
+// Vertex indices, by cells of the tolerance size
+struct SpatialHash<F> {
+    tolerance: F,
+    cells: HashMap<[i64; 3], Vec<usize>>,
+}
+
+impl<F: Coordinate> SpatialHash<F> {
+    fn new(tolerance: F) -> Self {
+        Self {
+            tolerance,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, position: &[F; 3]) -> [i64; 3] {
+        position.map(|c| (c / self.tolerance).floor().to_i64().unwrap_or(0))
+    }
+
+    fn insert(&mut self, position: &[F; 3], index: usize) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push(index);
+    }
+
+    // A vertex within the tolerance on each axis. Such a vertex can only be in a neighbouring cell
+    fn find(&self, position: &[F; 3], positions: &[F]) -> Option<usize> {
+        let [x, y, z] = self.cell(position);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let key = [x + dx, y + dy, z + dz];
+                    let found = self.cells.get(&key).and_then(|indices| {
+                        indices.iter().copied().find(|&index| {
+                            (0..3).all(|c| {
+                                (positions[3 * index + c] - position[c]).abs() <= self.tolerance
+                            })
+                        })
+                    });
+                    if found.is_some() {
+                        return found;
+                    }
+                }
+            }
+        }
+        None
+    }
+}